@@ -1,5 +1,7 @@
 # type: ignore[list-item]
-from typing import List, Optional
+from datetime import date, datetime, time
+from os import PathLike
+from typing import Any, BinaryIO, Dict, List, Literal, Optional, Tuple, Union
 from polars import DataFrame
 from enum import Enum  # <-- Важно импортировать Enum
 
@@ -7,11 +9,14 @@ from enum import Enum  # <-- Важно импортировать Enum
 
 class HorizAlignment(Enum):
     """Горизонтальное выравнивание."""
+    General: "HorizAlignment"
     Left: "HorizAlignment"
     Center: "HorizAlignment"
     Right: "HorizAlignment"
     Fill: "HorizAlignment"
     Justify: "HorizAlignment"
+    CenterContinuous: "HorizAlignment"
+    Distributed: "HorizAlignment"
 
 class VertAlignment(Enum):
     """Вертикальное выравнивание."""
@@ -19,6 +24,7 @@ class VertAlignment(Enum):
     Center: "VertAlignment"
     Bottom: "VertAlignment"
     Justify: "VertAlignment"
+    Distributed: "VertAlignment"
 
 class AlignSpec:
     """Спецификация выравнивания для ячейки."""
@@ -27,34 +33,250 @@ class AlignSpec:
         horiz: Optional[HorizAlignment] = None,
         vert: Optional[VertAlignment] = None,
         wrap: bool = False,
+        indent: Optional[int] = None,
+        text_rotation: Optional[int] = None,  # 0–90 против часовой, 91–180 по часовой, 255 – вертикально
+        shrink_to_fit: Optional[bool] = None,  # False снимает уже включённый флаг
+        reading_order: Optional[Literal["context", "ltr", "rtl"]] = None,
     ) -> None: ...
 
+class Color:
+    """Цвет: ровно одно из rgb ("FF0000"), theme (0–11, с tint от -1 до 1) или indexed."""
+    def __init__(
+        self,
+        rgb: Optional[str] = None,
+        theme: Optional[int] = None,
+        tint: float = 0.0,
+        indexed: Optional[int] = None,
+    ) -> None: ...
+
+# строка – RGB/ARGB
+ColorInput = Union[str, Color]
+
+PatternType = Literal[
+    "none", "solid", "mediumGray", "darkGray", "lightGray",
+    "darkHorizontal", "darkVertical", "darkDown", "darkUp", "darkGrid", "darkTrellis",
+    "lightHorizontal", "lightVertical", "lightDown", "lightUp", "lightGrid", "lightTrellis",
+    "gray125", "gray0625",
+]
+
+class BorderSide:
+    """Одна сторона рамки: стиль линии (thin, medium, thick, ...; "none" стирает) и цвет."""
+    def __init__(self, style: str, color: Optional[Union[str, Color]] = None) -> None: ...
+
+class BorderSpec:
+    """Рамка по сторонам; стороны None не меняются."""
+    def __init__(
+        self,
+        left: Optional[BorderSide] = None,
+        right: Optional[BorderSide] = None,
+        top: Optional[BorderSide] = None,
+        bottom: Optional[BorderSide] = None,
+        diagonal: Optional[BorderSide] = None,
+        diagonal_up: bool = False,
+        diagonal_down: bool = False,
+    ) -> None: ...
+
+CfOperator = Literal[
+    "lessThan", "lessThanOrEqual", "equal", "notEqual",
+    "greaterThanOrEqual", "greaterThan", "between", "notBetween",
+    "<", "<=", "=", "==", "<>", "!=", ">=", ">",
+]
+
+class DxfStyle:
+    """Стиль условного форматирования; поля None не меняют ячейку."""
+    def __init__(
+        self,
+        font_color: Optional[ColorInput] = None,
+        bold: Optional[bool] = None,
+        italic: Optional[bool] = None,
+        strike: Optional[bool] = None,
+        underline: Optional[str] = None,
+        fill: Optional[ColorInput] = None,
+        num_fmt: Optional[str] = None,
+        border: Optional[BorderSide] = None,
+    ) -> None: ...
+
+class ConditionalRule:
+    """Правило условного форматирования.
+
+    Пороги (cfvo) – строки: "min", "max", "num:10", "percent:50",
+    "percentile:90", "formula:$B$1".
+    """
+    @staticmethod
+    def cell_is(
+        operator: CfOperator, formula: str, style: DxfStyle, formula2: Optional[str] = None
+    ) -> "ConditionalRule": ...
+    @staticmethod
+    def expression(formula: str, style: DxfStyle) -> "ConditionalRule": ...
+    @staticmethod
+    def color_scale(stops: List[Tuple[str, ColorInput]]) -> "ConditionalRule": ...
+    @staticmethod
+    def data_bar(color: ColorInput, min: str = "min", max: str = "max") -> "ConditionalRule": ...
+    @staticmethod
+    def icon_set(
+        icons: str = "3TrafficLights1",
+        thresholds: Optional[List[str]] = None,
+        reverse: bool = False,
+        show_value: bool = True,
+    ) -> "ConditionalRule": ...
+    @staticmethod
+    def top(rank: int, style: DxfStyle, bottom: bool = False, percent: bool = False) -> "ConditionalRule": ...
+    @staticmethod
+    def duplicates(style: DxfStyle) -> "ConditionalRule": ...
+    @staticmethod
+    def unique(style: DxfStyle) -> "ConditionalRule": ...
+
+ValidationType = Literal["whole", "decimal", "date", "time", "textLength"]
+
+class ValidationRule:
+    """Правило проверки данных (выпадающий список или ограничение)."""
+    @staticmethod
+    def list(values: List[str]) -> "ValidationRule": ...
+    @staticmethod
+    def list_range(reference: str) -> "ValidationRule": ...
+    @staticmethod
+    def compare(
+        kind: ValidationType, operator: CfOperator, formula1: str, formula2: Optional[str] = None
+    ) -> "ValidationRule": ...
+    @staticmethod
+    def custom(formula: str) -> "ValidationRule": ...
+
 # --- СУЩЕСТВУЮЩИЕ И ОБНОВЛЕННЫЕ КЛАССЫ ---
 
-def scan_excel(path: str) -> List[str]: ...
+# str: "=..." → формула, числовой текст → число; None → пустая ячейка;
+# date/datetime/time → дата с числовым форматом
+CellInput = Union[str, int, float, bool, date, datetime, time, None]
+
+# путь, содержимое файла или бинарный file-like объект
+WorkbookSource = Union[str, PathLike, bytes, bytearray, BinaryIO]
+
+def scan_excel(source: WorkbookSource) -> List[str]: ...
 def create_excel(path: str, sheet_name: str = "Sheet1") -> None: ...
 
 class Editor:
-    def __init__(self, path: str, sheet_name: Optional[str] = None) -> None: ...
+    def __init__(self, source: WorkbookSource, sheet_name: Optional[str] = None) -> None: ...
     @staticmethod
     def create(path: str, sheet_name: str = "Sheet1") -> "Editor": ...
     @staticmethod
-    def open(path: str, sheet_name: Optional[str] = None) -> "Editor": ...
-    def append_row(self, cells: List[str]) -> None: ...
-    def append_table_at(self, cells: List[List[str]], start_cell: str) -> None: ...
-    def save(self, path: str) -> None: ...
+    def open(source: WorkbookSource, sheet_name: Optional[str] = None) -> "Editor": ...
+    def append_row(self, cells: List[CellInput]) -> None: ...
+    def append_table_at(self, cells: List[List[CellInput]], start_cell: str) -> None: ...
+    def save(self, target: Union[str, PathLike, BinaryIO]) -> None: ...
+    def to_bytes(self) -> bytes: ...
     def last_row_index(self, col_name: str) -> int: ...
     def last_rows_index(self, col_name: str) -> List[int]: ...
     def with_polars(self, df: DataFrame, start_cell: Optional[str] = None) -> None: ...
+    def to_polars(
+        self,
+        range: Optional[str] = None,
+        header: bool = True,
+        dtypes: Optional[Dict[str, Any]] = None,
+    ) -> DataFrame: ...
     def add_worksheet(self, sheet_name: str) -> "Editor": ...
     def add_worksheet_at(self, sheet_name: str, index: int) -> "Editor": ...
     def with_worksheet(self, sheet_name: str) -> "Editor": ...
     def delete_worksheet(self, sheet_name: str) -> "Editor": ...
+    def copy_worksheet(
+        self, src_name: str, new_name: str, index: Optional[int] = None
+    ) -> "Editor": ...
+    def import_worksheet(
+        self,
+        source: Union["Editor", WorkbookSource],
+        sheet_name: str,
+        new_name: Optional[str] = None,
+        index: Optional[int] = None,
+    ) -> "Editor": ...
+    def move_worksheet(self, sheet_name: str, new_index: int) -> "Editor": ...
     def rename_worksheet(self, old_name: str, new_name: str) -> "Editor": ...
+    def insert_rows(self, at: int, n: int = 1) -> "Editor": ...
+    def delete_rows(self, at: int, n: int = 1) -> "Editor": ...
+    def insert_columns(self, col: str, n: int = 1) -> "Editor": ...
+    def delete_columns(self, col: str, n: int = 1) -> "Editor": ...
     def set_number_format(self, range: str, fmt: str) -> "Editor": ...
-    def set_fill(self, range: str, fmt: str) -> "Editor": ...
-    def merge_cells(self, range: str) -> "Editor": ...
+    def set_fill(
+        self,
+        range: str,
+        fmt: ColorInput,
+        pattern: PatternType = "solid",
+        bg_color: Optional[ColorInput] = None,
+    ) -> "Editor": ...
+    def set_gradient_fill(
+        self,
+        range: str,
+        stops: List[Tuple[float, ColorInput]],
+        degree: float = 0.0,
+        path: Optional[Tuple[float, float, float, float]] = None,  # left, right, top, bottom
+    ) -> "Editor": ...
+    def merge_cells(
+        self,
+        range: str,
+        replace_overlapping: bool = False,
+        clear_non_anchor: bool = False,
+    ) -> "Editor": ...
+    def unmerge_cells(self, range: str) -> "Editor": ...
+    def merged_ranges(self) -> List[str]: ...
     def set_border(self, range: str, style: str) -> "Editor": ...
+    def set_border_spec(self, range: str, spec: BorderSpec) -> "Editor": ...
+    def set_outline_border(self, range: str, side: BorderSide) -> "Editor": ...
+    def set_inner_borders(
+        self,
+        range: str,
+        horizontal: Optional[BorderSide] = None,
+        vertical: Optional[BorderSide] = None,
+    ) -> "Editor": ...
+    def add_conditional_format(self, range: str, rule: ConditionalRule) -> "Editor": ...
+    def add_data_validation(
+        self,
+        range: str,
+        rule: ValidationRule,
+        allow_blank: bool = True,
+        in_cell_dropdown: bool = True,
+        prompt_title: Optional[str] = None,
+        prompt: Optional[str] = None,
+        error_title: Optional[str] = None,
+        error: Optional[str] = None,
+        error_style: Literal["stop", "warning", "information"] = "stop",
+    ) -> "Editor": ...
+    def remove_data_validations(self, range: str) -> "Editor": ...
+    def data_validation_ranges(self) -> List[str]: ...
+    def set_hyperlink(
+        self, coord: str, target: str, display: Optional[str] = None, tooltip: Optional[str] = None
+    ) -> "Editor": ...
+    def remove_hyperlink(self, range: str) -> "Editor": ...
+    def hyperlink(self, coord: str) -> Optional[str]: ...
+    def add_comment(self, coord: str, author: str, text: str) -> "Editor": ...
+    def comments(self) -> List[Tuple[str, str, str]]: ...
+    def set_cell_protection(self, range: str, locked: bool = True, hidden: bool = False) -> "Editor": ...
+    def protect_sheet(
+        self,
+        password: Optional[str] = None,
+        legacy_hash: bool = False,
+        select_locked_cells: bool = True,
+        select_unlocked_cells: bool = True,
+        format_cells: bool = False,
+        format_columns: bool = False,
+        format_rows: bool = False,
+        insert_columns: bool = False,
+        insert_rows: bool = False,
+        insert_hyperlinks: bool = False,
+        delete_columns: bool = False,
+        delete_rows: bool = False,
+        sort: bool = False,
+        auto_filter: bool = False,
+        pivot_tables: bool = False,
+        edit_objects: bool = False,
+        edit_scenarios: bool = False,
+    ) -> "Editor": ...
+    def unprotect_sheet(self) -> "Editor": ...
+    def is_sheet_protected(self) -> bool: ...
+    def protect_workbook(
+        self,
+        password: Optional[str] = None,
+        lock_structure: bool = True,
+        lock_windows: bool = False,
+        legacy_hash: bool = False,
+    ) -> "Editor": ...
+    def unprotect_workbook(self) -> "Editor": ...
     
     # --- ОБНОВЛЕННЫЙ МЕТОД ---
     def set_font(
@@ -64,13 +286,22 @@ class Editor:
         size: float, 
         bold: bool = False, 
         italic: bool = False, 
-        align: Optional[AlignSpec] = None  # <-- Добавлен опциональный аргумент
+        align: Optional[AlignSpec] = None,  # <-- Добавлен опциональный аргумент
+        color: Optional[ColorInput] = None,  # RGB/ARGB, например "FF0000", или Color
+        theme_color: Optional[int] = None,
+        indexed_color: Optional[int] = None,
+        underline: Optional[Literal["single", "double", "singleAccounting", "doubleAccounting"]] = None,
+        strike: bool = False,
+        vert_align: Optional[Literal["superscript", "subscript"]] = None,
+        family: Optional[int] = None,
+        scheme: Optional[Literal["major", "minor", "none"]] = None,
     ) -> "Editor": ...
     
     # --- НОВЫЙ МЕТОД ---
     def set_alignment(self, range: str, spec: AlignSpec) -> "Editor": ...
 
-    def set_cell(self, coords: str, cell: str) -> None: ...
+    def use_shared_strings(self, enabled: bool = True) -> "Editor": ...
+    def set_cell(self, coords: str, cell: CellInput) -> None: ...
     def set_column_width(self, col_letter: str, width: float) -> "Editor": ...
     def set_column_width_range(self, col_range: str, width: float) -> "Editor": ...
     def set_columns_width(self, col_letters: List[str], width: float) -> "Editor": ...
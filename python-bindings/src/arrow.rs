@@ -0,0 +1,268 @@
+//! arrow.rs – приём DataFrame через Arrow C Stream Interface (`__arrow_c_stream__`)
+//!
+//! Колонки конвертируются в `CellValue` целиком, без обхода строк в Python, и пишутся
+//! в лист по колонкам через `append_columns_at`.
+
+use arrow_array::{
+    Array, RecordBatch,
+    cast::AsArray,
+    ffi_stream::{ArrowArrayStreamReader, FFI_ArrowArrayStream},
+    types::*,
+};
+use arrow_schema::{DataType, TimeUnit};
+use pyo3::exceptions::PyRuntimeError;
+use pyo3::prelude::*;
+use pyo3::types::PyCapsule;
+use rust_core::{CellValue, DateValue};
+
+/// Забирает поток у объекта с `__arrow_c_stream__`; `None`, если протокол не поддержан.
+pub(crate) fn arrow_stream(obj: &Bound<'_, PyAny>) -> PyResult<Option<ArrowArrayStreamReader>> {
+    if !obj.hasattr("__arrow_c_stream__")? {
+        return Ok(None);
+    }
+    let capsule = obj.call_method0("__arrow_c_stream__")?;
+    let capsule = capsule.cast::<PyCapsule>()?;
+    let ptr = capsule.pointer_checked(Some(c"arrow_array_stream"))?;
+    // from_raw переносит структуру к нам и оставляет в капсуле пустой поток,
+    // так что деструктор капсулы ничего не освободит повторно
+    let reader =
+        unsafe { ArrowArrayStreamReader::from_raw(ptr.as_ptr() as *mut FFI_ArrowArrayStream) }
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    Ok(Some(reader))
+}
+
+/// Типы колонок, которые умеем писать в ячейки напрямую.
+pub(crate) fn is_supported(dt: &DataType) -> bool {
+    match dt {
+        DataType::Null
+        | DataType::Boolean
+        | DataType::Int8
+        | DataType::Int16
+        | DataType::Int32
+        | DataType::Int64
+        | DataType::UInt8
+        | DataType::UInt16
+        | DataType::UInt32
+        | DataType::UInt64
+        | DataType::Float32
+        | DataType::Float64
+        | DataType::Decimal128(_, _)
+        | DataType::Utf8
+        | DataType::LargeUtf8
+        | DataType::Utf8View
+        | DataType::Date32
+        | DataType::Date64
+        | DataType::Timestamp(_, None)
+        | DataType::Time32(_)
+        | DataType::Time64(_) => true,
+        // именованные зоны (`Europe/Berlin`) идут медленным путём: polars сам отдаёт местное время
+        DataType::Timestamp(_, Some(tz)) => tz_offset_secs(tz).is_some(),
+        DataType::Dictionary(_, values) => is_supported(values),
+        _ => false,
+    }
+}
+
+/// Батч → колонки ячеек: каждая колонка конвертируется из своего типизированного массива.
+pub(crate) fn batch_to_columns(batch: &RecordBatch) -> PyResult<Vec<Vec<CellValue>>> {
+    batch
+        .schema()
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| {
+            array_to_cells(column.as_ref())
+                .map_err(|e| PyRuntimeError::new_err(format!("column `{}`: {e}", field.name())))
+        })
+        .collect()
+}
+
+macro_rules! cells {
+    ($iter:expr, $f:expr) => {
+        $iter
+            .map(|v| v.map_or(CellValue::Blank, $f))
+            .collect::<Vec<_>>()
+    };
+}
+
+fn number<T: Into<f64>>(n: T) -> CellValue {
+    CellValue::Number(n.into())
+}
+
+fn date_only(d: DateValue) -> CellValue {
+    match d {
+        DateValue::DateTime {
+            year, month, day, ..
+        } => CellValue::Date(DateValue::date(year, month, day)),
+        other => CellValue::Date(other),
+    }
+}
+
+fn time_of_day(nanos: i64) -> CellValue {
+    let secs = nanos.div_euclid(1_000_000_000);
+    CellValue::Date(DateValue::time(
+        (secs / 3600) as u32,
+        (secs / 60 % 60) as u32,
+        (secs % 60) as f64 + nanos.rem_euclid(1_000_000_000) as f64 / 1e9,
+    ))
+}
+
+/// Смещение зоны в секундах: "UTC", "+03:00", "-0530", "+09". Именованные зоны
+/// (`Europe/Berlin`) без базы tz не разрешить – для них `None`.
+fn tz_offset_secs(tz: &str) -> Option<i64> {
+    if matches!(tz, "UTC" | "Etc/UTC" | "GMT" | "Etc/GMT" | "Z") {
+        return Some(0);
+    }
+    let (sign, rest) = match tz.as_bytes().first()? {
+        b'+' => (1, &tz[1..]),
+        b'-' => (-1, &tz[1..]),
+        _ => return None,
+    };
+    let digits: String = rest.chars().filter(|&c| c != ':').collect();
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i64 = digits[..2].parse().ok()?;
+    let minutes: i64 = if digits.len() == 4 {
+        digits[2..].parse().ok()?
+    } else {
+        0
+    };
+    (hours < 24 && minutes < 60).then_some(sign * (hours * 3600 + minutes * 60))
+}
+
+fn timestamp(value: i64, unit: &TimeUnit) -> CellValue {
+    let per_sec: i64 = match unit {
+        TimeUnit::Second => 1,
+        TimeUnit::Millisecond => 1_000,
+        TimeUnit::Microsecond => 1_000_000,
+        TimeUnit::Nanosecond => 1_000_000_000,
+    };
+    let nanos = value.rem_euclid(per_sec) * (1_000_000_000 / per_sec);
+    CellValue::Date(DateValue::from_unix(
+        value.div_euclid(per_sec),
+        nanos as u32,
+    ))
+}
+
+fn array_to_cells(array: &dyn Array) -> PyResult<Vec<CellValue>> {
+    Ok(match array.data_type() {
+        DataType::Null => vec![CellValue::Blank; array.len()],
+        DataType::Boolean => cells!(array.as_boolean().iter(), CellValue::Bool),
+        DataType::Int8 => cells!(array.as_primitive::<Int8Type>().iter(), number),
+        DataType::Int16 => cells!(array.as_primitive::<Int16Type>().iter(), number),
+        DataType::Int32 => cells!(array.as_primitive::<Int32Type>().iter(), number),
+        DataType::Int64 => cells!(array.as_primitive::<Int64Type>().iter(), |n| {
+            CellValue::Number(n as f64)
+        }),
+        DataType::UInt8 => cells!(array.as_primitive::<UInt8Type>().iter(), number),
+        DataType::UInt16 => cells!(array.as_primitive::<UInt16Type>().iter(), number),
+        DataType::UInt32 => cells!(array.as_primitive::<UInt32Type>().iter(), number),
+        DataType::UInt64 => cells!(array.as_primitive::<UInt64Type>().iter(), |n| {
+            CellValue::Number(n as f64)
+        }),
+        DataType::Float32 => cells!(array.as_primitive::<Float32Type>().iter(), number),
+        DataType::Float64 => cells!(array.as_primitive::<Float64Type>().iter(), number),
+        DataType::Decimal128(_, scale) => {
+            let div = 10f64.powi(*scale as i32);
+            cells!(array.as_primitive::<Decimal128Type>().iter(), |n| {
+                CellValue::Number(n as f64 / div)
+            })
+        }
+        DataType::Utf8 => cells!(array.as_string::<i32>().iter(), |s| {
+            CellValue::Text(s.to_owned())
+        }),
+        DataType::LargeUtf8 => cells!(array.as_string::<i64>().iter(), |s| {
+            CellValue::Text(s.to_owned())
+        }),
+        DataType::Utf8View => cells!(array.as_string_view().iter(), |s| {
+            CellValue::Text(s.to_owned())
+        }),
+        DataType::Date32 => cells!(array.as_primitive::<Date32Type>().iter(), |d| {
+            date_only(DateValue::from_unix(d as i64 * 86_400, 0))
+        }),
+        DataType::Date64 => cells!(array.as_primitive::<Date64Type>().iter(), |ms| {
+            date_only(DateValue::from_unix(ms.div_euclid(1000), 0))
+        }),
+        DataType::Timestamp(unit, tz) => {
+            let unit = *unit;
+            // Excel не хранит зону: момент UTC пишется как местное время зоны столбца
+            let shift = match tz.as_deref() {
+                None => 0,
+                Some(tz) => tz_offset_secs(tz).ok_or_else(|| {
+                    PyRuntimeError::new_err(format!(
+                        "timezone `{tz}` is not supported; convert the column to a fixed \
+                         offset or drop the timezone (e.g. `dt.replace_time_zone(None)`)"
+                    ))
+                })?,
+            };
+            let shift = shift
+                * match unit {
+                    TimeUnit::Second => 1,
+                    TimeUnit::Millisecond => 1_000,
+                    TimeUnit::Microsecond => 1_000_000,
+                    TimeUnit::Nanosecond => 1_000_000_000,
+                };
+            let values: Vec<Option<i64>> = match unit {
+                TimeUnit::Second => array.as_primitive::<TimestampSecondType>().iter().collect(),
+                TimeUnit::Millisecond => array
+                    .as_primitive::<TimestampMillisecondType>()
+                    .iter()
+                    .collect(),
+                TimeUnit::Microsecond => array
+                    .as_primitive::<TimestampMicrosecondType>()
+                    .iter()
+                    .collect(),
+                TimeUnit::Nanosecond => array
+                    .as_primitive::<TimestampNanosecondType>()
+                    .iter()
+                    .collect(),
+            };
+            cells!(values.into_iter(), |v| timestamp(v + shift, &unit))
+        }
+        DataType::Time32(TimeUnit::Second) => {
+            cells!(array.as_primitive::<Time32SecondType>().iter(), |s| {
+                time_of_day(s as i64 * 1_000_000_000)
+            })
+        }
+        DataType::Time32(_) => cells!(array.as_primitive::<Time32MillisecondType>().iter(), |ms| {
+            time_of_day(ms as i64 * 1_000_000)
+        }),
+        DataType::Time64(TimeUnit::Microsecond) => {
+            cells!(array.as_primitive::<Time64MicrosecondType>().iter(), |us| {
+                time_of_day(us * 1_000)
+            })
+        }
+        DataType::Time64(_) => {
+            cells!(
+                array.as_primitive::<Time64NanosecondType>().iter(),
+                time_of_day
+            )
+        }
+        DataType::Dictionary(_, _) => {
+            let dict = array
+                .as_any_dictionary_opt()
+                .ok_or_else(|| PyRuntimeError::new_err("invalid Arrow dictionary array"))?;
+            let values = array_to_cells(dict.values().as_ref())?;
+            if values.is_empty() {
+                vec![CellValue::Blank; array.len()]
+            } else {
+                dict.normalized_keys()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, k)| {
+                        if array.is_null(i) {
+                            CellValue::Blank
+                        } else {
+                            values[k].clone()
+                        }
+                    })
+                    .collect()
+            }
+        }
+        other => {
+            return Err(PyRuntimeError::new_err(format!(
+                "unsupported Arrow column type {other}"
+            )));
+        }
+    })
+}
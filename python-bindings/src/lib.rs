@@ -1,18 +1,214 @@
+mod arrow;
+
+use arrow_array::RecordBatchReader;
 use pyo3::PyRefMut;
 use pyo3::exceptions::PyRuntimeError;
 use pyo3::prelude::*;
-use pyo3::types::PyDict;
-use rust_core::style::{AlignSpec, HorizAlignment, VertAlignment};
-use rust_core::{XlsxEditor, scan};
-use std::fs::File;
+use pyo3::types::{
+    PyBool, PyByteArray, PyBytes, PyDate, PyDateAccess, PyDateTime, PyDict, PyFloat, PyInt,
+    PyString, PyTime, PyTimeAccess,
+};
+use rust_core::style::{
+    AlignSpec, AlignmentSpec, BorderSide, BorderSpec, CellProtection, Color, DxfStyle, FillSpec,
+    FontSpec, FontVertAlign, GradientType, HorizAlignment, PatternType, ReadingOrder, Underline,
+    VertAlignment,
+};
+use rust_core::{
+    CellValue, CfOperator, Cfvo, ConditionalRule, DataValidation, DateValue, ErrorStyle,
+    MergeOptions, SheetProtection, StringStorage, ValidationRule, ValidationType, XlsxEditor, scan,
+    scan_reader,
+};
+use std::io::Cursor;
 use std::path::{Path, PathBuf};
-use zip::write::FileOptions;
 
-fn py_value_to_excel_string(value: &Bound<'_, PyAny>) -> PyResult<String> {
+/// `None`, `bool`, `int`, `float` and `datetime.date`/`datetime`/`time` map to native
+/// cell types. `str` goes through the legacy sniffing (`"=..."` formula, numeric text →
+/// number) when `sniff_str` is set and is kept as text otherwise; any other object is
+/// written as its `str()`.
+fn py_value_to_cell_value(value: &Bound<'_, PyAny>, sniff_str: bool) -> PyResult<CellValue> {
     if value.is_none() {
-        return Ok(String::new());
+        return Ok(CellValue::Blank);
+    }
+    if value.is_instance_of::<PyBool>() {
+        return Ok(CellValue::Bool(value.extract()?));
+    }
+    if value.is_instance_of::<PyInt>() || value.is_instance_of::<PyFloat>() {
+        return Ok(CellValue::Number(value.extract()?));
+    }
+    // datetime – подкласс date, проверяем первым; tzinfo игнорируется
+    if let Ok(dt) = value.cast::<PyDateTime>() {
+        return Ok(CellValue::Date(DateValue::datetime(
+            dt.get_year(),
+            dt.get_month() as u32,
+            dt.get_day() as u32,
+            dt.get_hour() as u32,
+            dt.get_minute() as u32,
+            dt.get_second() as f64 + dt.get_microsecond() as f64 / 1e6,
+        )));
+    }
+    if let Ok(d) = value.cast::<PyDate>() {
+        return Ok(CellValue::Date(DateValue::date(
+            d.get_year(),
+            d.get_month() as u32,
+            d.get_day() as u32,
+        )));
+    }
+    if let Ok(t) = value.cast::<PyTime>() {
+        return Ok(CellValue::Date(DateValue::time(
+            t.get_hour() as u32,
+            t.get_minute() as u32,
+            t.get_second() as f64 + t.get_microsecond() as f64 / 1e6,
+        )));
+    }
+    let text = if value.is_instance_of::<PyString>() {
+        value.extract::<String>()?
+    } else {
+        value.str()?.to_str()?.to_owned()
+    };
+    Ok(if sniff_str {
+        CellValue::from(text.as_str())
+    } else {
+        CellValue::Text(text)
+    })
+}
+
+/// Column dtype for `to_polars`, inferred from the cell values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Null,
+    Int,
+    Float,
+    Bool,
+    Text,
+    Date,
+    DateTime,
+    Time,
+}
+
+// формулы читаются по закэшированному результату
+fn frame_value(v: CellValue) -> CellValue {
+    match v {
+        CellValue::Formula { cached, .. } => cached.map_or(CellValue::Blank, |c| frame_value(*c)),
+        other => other,
+    }
+}
+
+fn infer_column_kind(values: &[CellValue]) -> ColumnKind {
+    use ColumnKind::*;
+    values.iter().fold(Null, |acc, v| {
+        let k = match v {
+            CellValue::Blank => return acc,
+            CellValue::Number(n) if n.fract() == 0.0 && n.abs() < 9.007_199_254_740_992e15 => Int,
+            CellValue::Number(_) => Float,
+            CellValue::Bool(_) => Bool,
+            CellValue::Date(DateValue::Date { .. }) => Date,
+            CellValue::Date(DateValue::DateTime { .. }) => DateTime,
+            CellValue::Date(DateValue::Time { .. }) => Time,
+            _ => Text,
+        };
+        match (acc, k) {
+            (Null, k) => k,
+            (a, k) if a == k => a,
+            (Int, Float) | (Float, Int) => Float,
+            (Date, DateTime) | (DateTime, Date) => DateTime,
+            _ => Text,
+        }
+    })
+}
+
+fn cell_display(v: &CellValue) -> String {
+    match v {
+        CellValue::Blank => String::new(),
+        CellValue::Number(n) => n.to_string(),
+        CellValue::Text(s) | CellValue::Error(s) => s.clone(),
+        CellValue::Bool(b) => b.to_string(),
+        CellValue::Date(DateValue::Date { year, month, day }) => {
+            format!("{year:04}-{month:02}-{day:02}")
+        }
+        CellValue::Date(DateValue::DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }) => format!("{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{second:02}"),
+        CellValue::Date(DateValue::Time {
+            hour,
+            minute,
+            second,
+        }) => format!("{hour:02}:{minute:02}:{second:02}"),
+        CellValue::Formula { cached, formula } => cached
+            .as_deref()
+            .map_or_else(|| format!("={formula}"), cell_display),
     }
-    Ok(value.str()?.to_str()?.to_owned())
+}
+
+fn py_datetime<'py>(
+    py: Python<'py>,
+    (year, month, day): (i32, u32, u32),
+    (hour, minute, second): (u32, u32, f64),
+) -> PyResult<Bound<'py, PyAny>> {
+    let micros = (second.fract() * 1e6).round() as u32;
+    Ok(PyDateTime::new(
+        py,
+        year,
+        month as u8,
+        day as u8,
+        hour as u8,
+        minute as u8,
+        second as u8,
+        micros.min(999_999),
+        None,
+    )?
+    .into_any())
+}
+
+fn cell_to_py<'py>(
+    py: Python<'py>,
+    v: &CellValue,
+    kind: ColumnKind,
+) -> PyResult<Bound<'py, PyAny>> {
+    Ok(match (v, kind) {
+        (CellValue::Blank, _) => py.None().into_bound(py),
+        (CellValue::Number(n), ColumnKind::Int) => (*n as i64).into_pyobject(py)?.into_any(),
+        (CellValue::Number(n), ColumnKind::Float) => n.into_pyobject(py)?.into_any(),
+        (CellValue::Bool(b), ColumnKind::Bool) => b.into_pyobject(py)?.to_owned().into_any(),
+        (CellValue::Date(DateValue::Date { year, month, day }), ColumnKind::Date) => {
+            PyDate::new(py, *year, *month as u8, *day as u8)?.into_any()
+        }
+        (CellValue::Date(DateValue::Date { year, month, day }), ColumnKind::DateTime) => {
+            py_datetime(py, (*year, *month, *day), (0, 0, 0.0))?
+        }
+        (
+            CellValue::Date(DateValue::DateTime {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            }),
+            ColumnKind::DateTime,
+        ) => py_datetime(py, (*year, *month, *day), (*hour, *minute, *second))?,
+        (
+            CellValue::Date(DateValue::Time {
+                hour,
+                minute,
+                second,
+            }),
+            ColumnKind::Time,
+        ) => PyTime::new(
+            py,
+            *hour as u8,
+            *minute as u8,
+            *second as u8,
+            ((second.fract() * 1e6).round() as u32).min(999_999),
+            None,
+        )?
+        .into_any(),
+        (other, _) => cell_display(other).into_pyobject(py)?.into_any(),
+    })
 }
 
 fn index_to_excel_col(mut idx: usize) -> String {
@@ -96,138 +292,68 @@ fn normalize_sheet_name(sheet_name: &str) -> PyResult<String> {
     Ok(normalized.to_owned())
 }
 
-fn xml_escape_attr(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('"', "&quot;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('\'', "&apos;")
-}
-
 fn create_empty_excel_file(path: &Path, sheet_name: &str) -> PyResult<()> {
     let sheet_name = normalize_sheet_name(sheet_name)?;
     if let Some(parent) = path.parent() {
         std::fs::create_dir_all(parent).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
     }
+    XlsxEditor::new_workbook(&sheet_name)
+        .and_then(|mut editor| editor.save(path))
+        .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
 
-    let file = File::create(path).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    let mut zip = zip::ZipWriter::new(file);
-    let options: FileOptions<'_, ()> =
-        FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
-
-    let content_types = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
-  <Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
-  <Default Extension="xml" ContentType="application/xml"/>
-  <Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/>
-  <Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>
-  <Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/>
-</Types>"#;
-
-    let rels_root = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/>
-</Relationships>"#;
-
-    let workbook = format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\
-<workbook xmlns=\"http://schemas.openxmlformats.org/spreadsheetml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\">\
-  <sheets>\
-    <sheet name=\"{}\" sheetId=\"1\" r:id=\"rId1\"/>\
-  </sheets>\
-</workbook>",
-        xml_escape_attr(&sheet_name)
-    );
-
-    let workbook_rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
-  <Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/>
-  <Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
-</Relationships>"#;
-
-    let sheet1 = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-  <dimension ref="A1"/>
-  <sheetData></sheetData>
-</worksheet>"#;
-
-    let styles = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-  <fonts count="1">
-    <font>
-      <sz val="11"/>
-      <color theme="1"/>
-      <name val="Calibri"/>
-      <family val="2"/>
-    </font>
-  </fonts>
-  <fills count="2">
-    <fill><patternFill patternType="none"/></fill>
-    <fill><patternFill patternType="gray125"/></fill>
-  </fills>
-  <borders count="1">
-    <border>
-      <left/><right/><top/><bottom/><diagonal/>
-    </border>
-  </borders>
-  <cellStyleXfs count="1">
-    <xf numFmtId="0" fontId="0" fillId="0" borderId="0"/>
-  </cellStyleXfs>
-  <cellXfs count="1">
-    <xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/>
-  </cellXfs>
-  <cellStyles count="1">
-    <cellStyle name="Normal" xfId="0" builtinId="0"/>
-  </cellStyles>
-</styleSheet>"#;
-
-    zip.start_file("[Content_Types].xml", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, content_types.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.start_file("_rels/.rels", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, rels_root.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.start_file("xl/workbook.xml", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, workbook.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.start_file("xl/_rels/workbook.xml.rels", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, workbook_rels.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.start_file("xl/worksheets/sheet1.xml", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, sheet1.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.start_file("xl/styles.xml", options)
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    std::io::Write::write_all(&mut zip, styles.as_bytes())
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-
-    zip.finish()
-        .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-    Ok(())
+/// Where a workbook is read from: a path, or the contents of an XLSX file.
+enum WorkbookSource {
+    Path(PathBuf),
+    Bytes(Vec<u8>),
 }
 
-fn open_editor_with_optional_sheet(path: PathBuf, sheet_name: Option<&str>) -> PyResult<Editor> {
+/// Accepts a path (`str`/`os.PathLike`), `bytes`/`bytearray` or a binary file-like
+/// object with `.read()`.
+fn workbook_source(source: &Bound<'_, PyAny>) -> PyResult<WorkbookSource> {
+    if let Ok(b) = source.cast::<PyBytes>() {
+        return Ok(WorkbookSource::Bytes(b.as_bytes().to_vec()));
+    }
+    if let Ok(b) = source.cast::<PyByteArray>() {
+        return Ok(WorkbookSource::Bytes(b.to_vec()));
+    }
+    if source.hasattr("read")? {
+        let data = source.call_method0("read")?;
+        return match workbook_source(&data)? {
+            WorkbookSource::Bytes(b) => Ok(WorkbookSource::Bytes(b)),
+            WorkbookSource::Path(_) => Err(PyRuntimeError::new_err(
+                "File-like object must be opened in binary mode",
+            )),
+        };
+    }
+    Ok(WorkbookSource::Path(source.extract()?))
+}
+
+fn scan_source(source: &WorkbookSource) -> PyResult<Vec<String>> {
+    match source {
+        WorkbookSource::Path(path) => scan(path),
+        WorkbookSource::Bytes(bytes) => scan_reader(Cursor::new(bytes)),
+    }
+    .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+}
+
+fn open_editor_with_optional_sheet(
+    source: WorkbookSource,
+    sheet_name: Option<&str>,
+) -> PyResult<Editor> {
     let sheet = match sheet_name {
         Some(name) => normalize_sheet_name(name)?,
-        None => scan(&path)
-            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+        None => scan_source(&source)?
             .into_iter()
             .next()
             .ok_or_else(|| PyRuntimeError::new_err("Workbook contains no worksheets"))?,
     };
 
-    let opened =
-        XlsxEditor::open(path, &sheet).map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+    let opened = match source {
+        WorkbookSource::Path(path) => XlsxEditor::open(path, &sheet),
+        WorkbookSource::Bytes(bytes) => XlsxEditor::open_from_bytes(bytes, &sheet),
+    }
+    .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
     Ok(Editor { editor: opened })
 }
 
@@ -241,17 +367,30 @@ struct PyVertAlignment(VertAlignment);
 
 #[pyclass(name = "AlignSpec", from_py_object)]
 #[derive(Clone)]
-struct PyAlignSpec(AlignSpec);
+struct PyAlignSpec(AlignmentSpec);
 
 #[pymethods]
 impl PyAlignSpec {
     #[new]
-    #[pyo3(signature = (horiz = None, vert = None, wrap = false))]
+    #[pyo3(signature = (
+        horiz = None,
+        vert = None,
+        wrap = false,
+        indent = None,
+        text_rotation = None,
+        shrink_to_fit = None,
+        reading_order = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn new(
         py: Python<'_>,
         horiz: Option<Py<PyAny>>,
         vert: Option<Py<PyAny>>,
         wrap: bool,
+        indent: Option<u32>,
+        text_rotation: Option<u32>,
+        shrink_to_fit: Option<bool>,
+        reading_order: Option<&str>,
     ) -> PyResult<Self> {
         let h_opt = if let Some(h_obj) = horiz {
             let h_any = h_obj.bind(py);
@@ -271,17 +410,297 @@ impl PyAlignSpec {
             None
         };
 
-        Ok(Self(AlignSpec {
+        let reading_order = reading_order
+            .map(str::parse::<ReadingOrder>)
+            .transpose()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+
+        let mut spec = AlignmentSpec::from(AlignSpec {
             horiz: h_opt,
             vert: v_opt,
             wrap,
+        });
+        if let Some(s) = shrink_to_fit {
+            spec = spec.with_shrink_to_fit(s);
+        }
+        if let Some(i) = indent {
+            spec = spec.with_indent(i);
+        }
+        if let Some(r) = text_rotation {
+            spec = spec.with_text_rotation(r);
+        }
+        if let Some(o) = reading_order {
+            spec = spec.with_reading_order(o);
+        }
+        Ok(Self(spec))
+    }
+}
+
+#[pyclass(name = "Color", from_py_object)]
+#[derive(Clone)]
+struct PyColor(Color);
+
+#[pymethods]
+impl PyColor {
+    #[new]
+    #[pyo3(signature = (rgb = None, theme = None, tint = 0.0, indexed = None))]
+    fn new(
+        rgb: Option<&str>,
+        theme: Option<u32>,
+        tint: f64,
+        indexed: Option<u32>,
+    ) -> PyResult<Self> {
+        Ok(Self(match (rgb, theme, indexed) {
+            (Some(rgb), None, None) => Color::rgb(rgb),
+            (None, Some(t), None) => Color::theme_tint(t, tint),
+            (None, None, Some(i)) => Color::indexed(i),
+            _ => {
+                return Err(PyRuntimeError::new_err(
+                    "pass exactly one of rgb, theme, indexed",
+                ));
+            }
         }))
     }
 }
 
+// str – RGB, иначе Color
+fn extract_color(obj: &Bound<'_, PyAny>) -> PyResult<Color> {
+    if let Ok(rgb) = obj.extract::<String>() {
+        return Ok(Color::Rgb(rgb));
+    }
+    let c: PyRef<PyColor> = obj.extract()?;
+    Ok(c.0.clone())
+}
+
+#[pyclass(name = "BorderSide", from_py_object)]
+#[derive(Clone)]
+struct PyBorderSide(BorderSide);
+
+#[pymethods]
+impl PyBorderSide {
+    #[new]
+    #[pyo3(signature = (style, color = None))]
+    fn new(style: &str, color: Option<&Bound<'_, PyAny>>) -> PyResult<Self> {
+        let side = BorderSide::new(style);
+        Ok(Self(match color {
+            Some(c) => side.with_color_spec(extract_color(c)?),
+            None => side,
+        }))
+    }
+}
+
+#[pyclass(name = "BorderSpec", from_py_object)]
+#[derive(Clone)]
+struct PyBorderSpec(BorderSpec);
+
+#[pymethods]
+impl PyBorderSpec {
+    #[new]
+    #[pyo3(signature = (left = None, right = None, top = None, bottom = None, diagonal = None, diagonal_up = false, diagonal_down = false))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        left: Option<PyBorderSide>,
+        right: Option<PyBorderSide>,
+        top: Option<PyBorderSide>,
+        bottom: Option<PyBorderSide>,
+        diagonal: Option<PyBorderSide>,
+        diagonal_up: bool,
+        diagonal_down: bool,
+    ) -> Self {
+        Self(BorderSpec {
+            left: left.map(|s| s.0),
+            right: right.map(|s| s.0),
+            top: top.map(|s| s.0),
+            bottom: bottom.map(|s| s.0),
+            diagonal: diagonal.map(|s| s.0),
+            diagonal_up,
+            diagonal_down,
+        })
+    }
+}
+
+#[pyclass(name = "DxfStyle", from_py_object)]
+#[derive(Clone)]
+struct PyDxfStyle(DxfStyle);
+
+#[pymethods]
+impl PyDxfStyle {
+    #[new]
+    #[pyo3(signature = (font_color = None, bold = None, italic = None, strike = None, underline = None, fill = None, num_fmt = None, border = None))]
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        font_color: Option<Bound<'_, PyAny>>,
+        bold: Option<bool>,
+        italic: Option<bool>,
+        strike: Option<bool>,
+        underline: Option<&str>,
+        fill: Option<Bound<'_, PyAny>>,
+        num_fmt: Option<String>,
+        border: Option<PyBorderSide>,
+    ) -> PyResult<Self> {
+        Ok(Self(DxfStyle {
+            font_color: font_color.as_ref().map(extract_color).transpose()?,
+            bold,
+            italic,
+            strike,
+            underline: underline
+                .map(|u| u.parse::<Underline>())
+                .transpose()
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?,
+            fill: fill.as_ref().map(extract_color).transpose()?,
+            num_fmt,
+            border: border.map(|b| b.0),
+        }))
+    }
+}
+
+fn parse_cfvo(s: &str) -> PyResult<Cfvo> {
+    s.parse()
+        .map_err(|e: anyhow::Error| PyRuntimeError::new_err(e.to_string()))
+}
+
+#[pyclass(name = "ConditionalRule", from_py_object)]
+#[derive(Clone)]
+struct PyConditionalRule(ConditionalRule);
+
+#[pymethods]
+impl PyConditionalRule {
+    #[staticmethod]
+    #[pyo3(signature = (operator, formula, style, formula2 = None))]
+    fn cell_is(
+        operator: &str,
+        formula: String,
+        style: PyDxfStyle,
+        formula2: Option<String>,
+    ) -> PyResult<Self> {
+        let operator = operator
+            .parse::<CfOperator>()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self(ConditionalRule::CellIs {
+            operator,
+            formula,
+            formula2,
+            style: style.0,
+        }))
+    }
+
+    #[staticmethod]
+    fn expression(formula: String, style: PyDxfStyle) -> Self {
+        Self(ConditionalRule::Expression {
+            formula,
+            style: style.0,
+        })
+    }
+
+    #[staticmethod]
+    fn color_scale(stops: Vec<(String, Bound<'_, PyAny>)>) -> PyResult<Self> {
+        let stops = stops
+            .iter()
+            .map(|(v, c)| Ok((parse_cfvo(v)?, extract_color(c)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self(ConditionalRule::ColorScale { stops }))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (color, min = "min", max = "max"))]
+    fn data_bar(color: &Bound<'_, PyAny>, min: &str, max: &str) -> PyResult<Self> {
+        Ok(Self(ConditionalRule::DataBar {
+            min: parse_cfvo(min)?,
+            max: parse_cfvo(max)?,
+            color: extract_color(color)?,
+        }))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (icons = "3TrafficLights1", thresholds = None, reverse = false, show_value = true))]
+    fn icon_set(
+        icons: &str,
+        thresholds: Option<Vec<String>>,
+        reverse: bool,
+        show_value: bool,
+    ) -> PyResult<Self> {
+        let thresholds = thresholds
+            .unwrap_or_default()
+            .iter()
+            .map(|t| parse_cfvo(t))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok(Self(ConditionalRule::IconSet {
+            icons: icons.to_owned(),
+            thresholds,
+            reverse,
+            show_value,
+        }))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (rank, style, bottom = false, percent = false))]
+    fn top(rank: u32, style: PyDxfStyle, bottom: bool, percent: bool) -> Self {
+        Self(ConditionalRule::Top {
+            rank,
+            bottom,
+            percent,
+            style: style.0,
+        })
+    }
+
+    #[staticmethod]
+    fn duplicates(style: PyDxfStyle) -> Self {
+        Self(ConditionalRule::Duplicates { style: style.0 })
+    }
+
+    #[staticmethod]
+    fn unique(style: PyDxfStyle) -> Self {
+        Self(ConditionalRule::Unique { style: style.0 })
+    }
+}
+
+#[pyclass(name = "ValidationRule", from_py_object)]
+#[derive(Clone)]
+struct PyValidationRule(ValidationRule);
+
+#[pymethods]
+impl PyValidationRule {
+    #[staticmethod]
+    fn list(values: Vec<String>) -> Self {
+        Self(ValidationRule::List(values))
+    }
+
+    #[staticmethod]
+    fn list_range(reference: String) -> Self {
+        Self(ValidationRule::ListRange(reference))
+    }
+
+    #[staticmethod]
+    #[pyo3(signature = (kind, operator, formula1, formula2 = None))]
+    fn compare(
+        kind: &str,
+        operator: &str,
+        formula1: String,
+        formula2: Option<String>,
+    ) -> PyResult<Self> {
+        let kind = kind
+            .parse::<ValidationType>()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let operator = operator
+            .parse::<CfOperator>()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(Self(ValidationRule::Compare {
+            kind,
+            operator,
+            formula1,
+            formula2,
+        }))
+    }
+
+    #[staticmethod]
+    fn custom(formula: String) -> Self {
+        Self(ValidationRule::Custom(formula))
+    }
+}
+
 #[pyfunction]
-fn scan_excel(path: PathBuf) -> PyResult<Vec<String>> {
-    scan(&path).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+fn scan_excel(source: &Bound<'_, PyAny>) -> PyResult<Vec<String>> {
+    scan_source(&workbook_source(source)?)
 }
 
 #[pyfunction]
@@ -298,22 +717,22 @@ struct Editor {
 #[pymethods]
 impl Editor {
     #[new]
-    #[pyo3(signature = (path, sheet_name = None))]
-    fn new(path: PathBuf, sheet_name: Option<&str>) -> PyResult<Self> {
-        open_editor_with_optional_sheet(path, sheet_name)
+    #[pyo3(signature = (source, sheet_name = None))]
+    fn new(source: &Bound<'_, PyAny>, sheet_name: Option<&str>) -> PyResult<Self> {
+        open_editor_with_optional_sheet(workbook_source(source)?, sheet_name)
     }
 
     #[staticmethod]
     #[pyo3(signature = (path, sheet_name = "Sheet1"))]
     fn create(path: PathBuf, sheet_name: &str) -> PyResult<Self> {
         create_empty_excel_file(&path, sheet_name)?;
-        open_editor_with_optional_sheet(path, Some(sheet_name))
+        open_editor_with_optional_sheet(WorkbookSource::Path(path), Some(sheet_name))
     }
 
     #[staticmethod]
-    #[pyo3(signature = (path, sheet_name = None))]
-    fn open(path: PathBuf, sheet_name: Option<&str>) -> PyResult<Self> {
-        open_editor_with_optional_sheet(path, sheet_name)
+    #[pyo3(signature = (source, sheet_name = None))]
+    fn open(source: &Bound<'_, PyAny>, sheet_name: Option<&str>) -> PyResult<Self> {
+        open_editor_with_optional_sheet(workbook_source(source)?, sheet_name)
     }
 
     fn add_worksheet<'py>(
@@ -350,6 +769,65 @@ impl Editor {
         Ok(slf)
     }
 
+    #[pyo3(signature = (src_name, new_name, index = None))]
+    fn copy_worksheet<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        src_name: &str,
+        new_name: &str,
+        index: Option<usize>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let src_name = normalize_sheet_name(src_name)?;
+        let new_name = normalize_sheet_name(new_name)?;
+        // None → в конец (индекс ограничивается числом листов)
+        slf.editor
+            .copy_worksheet(&src_name, &new_name, index.unwrap_or(usize::MAX))
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    /// `source` – другой `Editor` или книга (путь, bytes, file-like).
+    #[pyo3(signature = (source, sheet_name, new_name = None, index = None))]
+    fn import_worksheet<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        source: &Bound<'py, PyAny>,
+        sheet_name: &str,
+        new_name: Option<&str>,
+        index: Option<usize>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let sheet_name = normalize_sheet_name(sheet_name)?;
+        let new_name = match new_name {
+            Some(n) => normalize_sheet_name(n)?,
+            None => sheet_name.clone(),
+        };
+        let index = index.unwrap_or(usize::MAX);
+        let result = if let Ok(other) = source.cast::<Editor>() {
+            let other = other.try_borrow()?;
+            slf.editor
+                .import_worksheet(&other.editor, &sheet_name, &new_name, index)
+                .map(|_| ())
+        } else {
+            let other =
+                open_editor_with_optional_sheet(workbook_source(source)?, Some(&sheet_name))?;
+            slf.editor
+                .import_worksheet(&other.editor, &sheet_name, &new_name, index)
+                .map(|_| ())
+        };
+        result.map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn move_worksheet<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        sheet_name: &str,
+        new_index: usize,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let sheet_name = normalize_sheet_name(sheet_name)?;
+        slf.editor
+            .move_worksheet(&sheet_name, new_index)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
     fn rename_worksheet<'py>(
         mut slf: PyRefMut<'py, Self>,
         old_name: &str,
@@ -374,21 +852,97 @@ impl Editor {
         Ok(slf)
     }
 
-    fn set_cell(&mut self, coords: &str, cell: String) -> PyResult<()> {
+    #[pyo3(signature = (at, n = 1))]
+    fn insert_rows<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        at: u32,
+        n: u32,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .insert_rows(at, n)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (at, n = 1))]
+    fn delete_rows<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        at: u32,
+        n: u32,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .delete_rows(at, n)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (col, n = 1))]
+    fn insert_columns<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        col: &str,
+        n: u32,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .insert_columns(col, n)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (col, n = 1))]
+    fn delete_columns<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        col: &str,
+        n: u32,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .delete_columns(col, n)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (enabled = true))]
+    fn use_shared_strings<'py>(mut slf: PyRefMut<'py, Self>, enabled: bool) -> PyRefMut<'py, Self> {
+        let storage = if enabled {
+            StringStorage::Shared
+        } else {
+            StringStorage::Inline
+        };
+        slf.editor.set_string_storage(storage);
+        slf
+    }
+
+    fn set_cell(&mut self, coords: &str, cell: &Bound<'_, PyAny>) -> PyResult<()> {
+        let value = py_value_to_cell_value(cell, true)?;
         self.editor
-            .set_cell(coords, cell)
+            .set_cell(coords, value)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
-    fn append_row(&mut self, cells: Vec<String>) -> PyResult<()> {
+    fn append_row(&mut self, cells: Vec<Bound<'_, PyAny>>) -> PyResult<()> {
+        let row = cells
+            .iter()
+            .map(|c| py_value_to_cell_value(c, true))
+            .collect::<PyResult<Vec<_>>>()?;
         self.editor
-            .append_row(cells)
+            .append_row(row)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
-    fn append_table_at(&mut self, cells: Vec<Vec<String>>, start_cell: &str) -> PyResult<()> {
+    fn append_table_at(
+        &mut self,
+        cells: Vec<Vec<Bound<'_, PyAny>>>,
+        start_cell: &str,
+    ) -> PyResult<()> {
+        let table = cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|c| py_value_to_cell_value(c, true))
+                    .collect()
+            })
+            .collect::<PyResult<Vec<Vec<_>>>>()?;
         self.editor
-            .append_table_at(start_cell, cells)
+            .append_table_at(start_cell, table)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
@@ -404,12 +958,29 @@ impl Editor {
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
-    fn save(&mut self, path: PathBuf) -> PyResult<()> {
+    /// `target` is a path or a binary file-like object with `.write()`.
+    fn save(&mut self, target: &Bound<'_, PyAny>) -> PyResult<()> {
+        if target.hasattr("write")? {
+            let bytes = self.workbook_bytes(target.py())?;
+            target.call_method1("write", (bytes,))?;
+            return Ok(());
+        }
+        let path: PathBuf = target.extract()?;
         self.editor
             .save(path)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
+    /// The workbook as XLSX bytes.
+    #[pyo3(name = "to_bytes")]
+    fn workbook_bytes<'py>(&mut self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let bytes = self
+            .editor
+            .to_bytes()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(PyBytes::new(py, &bytes))
+    }
+
     #[pyo3(signature = (py_df, start_cell = None))]
     fn with_polars(
         &mut self,
@@ -417,6 +988,19 @@ impl Editor {
         py_df: &Bound<'_, PyAny>,
         start_cell: Option<String>,
     ) -> PyResult<()> {
+        let start = start_cell.as_deref().unwrap_or("A1");
+
+        // быстрый путь: Arrow C stream, колонки конвертируются в Rust без iter_rows()
+        if let Some(reader) = arrow::arrow_stream(py_df)?
+            && reader
+                .schema()
+                .fields()
+                .iter()
+                .all(|f| arrow::is_supported(f.data_type()))
+        {
+            return self.write_arrow(reader, start);
+        }
+
         let columns: Vec<String> = py_df
             .getattr("columns")
             .map_err(|_| PyRuntimeError::new_err("Expected polars.DataFrame with .columns"))?
@@ -426,26 +1010,115 @@ impl Editor {
             .call_method0("iter_rows")
             .map_err(|_| PyRuntimeError::new_err("Expected polars.DataFrame with .iter_rows()"))?;
 
-        let mut table: Vec<Vec<String>> = Vec::new();
-        table.push(columns);
+        let mut table: Vec<Vec<CellValue>> = Vec::new();
+        table.push(columns.into_iter().map(CellValue::Text).collect());
 
         for row in rows_iter.try_iter()? {
             let row_any = row?;
             let row_values: Vec<Py<PyAny>> = row_any.extract()?;
             let mut out_row = Vec::with_capacity(row_values.len());
             for value in row_values {
-                out_row.push(py_value_to_excel_string(value.bind(py).as_any())?);
+                // значения DataFrame уже типизированы – строки не угадываем
+                out_row.push(py_value_to_cell_value(value.bind(py).as_any(), false)?);
             }
             table.push(out_row);
         }
 
-        let start = start_cell.as_deref().unwrap_or("A1");
         self.editor
             .append_table_at(start, table)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         Ok(())
     }
 
+    /// Reads `range` (the used range by default) into a polars DataFrame.
+    ///
+    /// Column dtypes are inferred from the cells: Int64/Float64, Boolean, String, and
+    /// Date/Datetime/Time for numbers with a date number format; mixed columns become
+    /// String. `dtypes` maps column names to polars dtypes to cast to.
+    #[pyo3(signature = (range = None, header = true, dtypes = None))]
+    fn to_polars<'py>(
+        &self,
+        py: Python<'py>,
+        range: Option<&str>,
+        header: bool,
+        dtypes: Option<&Bound<'py, PyDict>>,
+    ) -> PyResult<Bound<'py, PyAny>> {
+        let pl = py.import("polars")?;
+        let range = match range {
+            Some(r) => r.to_owned(),
+            None => match self
+                .editor
+                .used_range()
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+            {
+                Some(r) => r,
+                None => return pl.getattr("DataFrame")?.call0(),
+            },
+        };
+        let mut grid = self
+            .editor
+            .get_range_with_dates(&range)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+            .into_iter();
+        let width = grid.as_slice().first().map_or(0, Vec::len);
+
+        let mut names: Vec<String> = match header.then(|| grid.next()).flatten() {
+            Some(row) => row
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| match frame_value(v) {
+                    CellValue::Blank => format!("column_{i}"),
+                    v => cell_display(&v),
+                })
+                .collect(),
+            None => (0..width).map(|i| format!("column_{i}")).collect(),
+        };
+        // polars требует уникальные имена
+        for i in 1..names.len() {
+            let mut n = 1;
+            let base = names[i].clone();
+            while names[..i].contains(&names[i]) {
+                names[i] = format!("{base}_{n}");
+                n += 1;
+            }
+        }
+
+        let mut columns: Vec<Vec<CellValue>> = vec![Vec::new(); width];
+        for row in grid {
+            for (col, v) in columns.iter_mut().zip(row) {
+                col.push(frame_value(v));
+            }
+        }
+
+        let series_cls = pl.getattr("Series")?;
+        let mut series = Vec::with_capacity(width);
+        for (name, values) in names.iter().zip(&columns) {
+            let kind = infer_column_kind(values);
+            let dtype = pl.getattr(match kind {
+                ColumnKind::Null => "Null",
+                ColumnKind::Int => "Int64",
+                ColumnKind::Float => "Float64",
+                ColumnKind::Bool => "Boolean",
+                ColumnKind::Text => "String",
+                ColumnKind::Date => "Date",
+                ColumnKind::DateTime => "Datetime",
+                ColumnKind::Time => "Time",
+            })?;
+            let items = values
+                .iter()
+                .map(|v| cell_to_py(py, v, kind))
+                .collect::<PyResult<Vec<_>>>()?;
+            let kwargs = PyDict::new(py);
+            kwargs.set_item("dtype", dtype)?;
+            let mut s = series_cls.call((name, items), Some(&kwargs))?;
+            if let Some(dt) = dtypes.map(|d| d.get_item(name)).transpose()?.flatten() {
+                s = s.call_method1("cast", (dt,))?;
+            }
+            series.push(s);
+        }
+        pl.getattr("DataFrame")?.call1((series,))
+    }
+
     fn set_number_format<'py>(
         mut slf: PyRefMut<'py, Self>,
         range: &str,
@@ -457,18 +1130,72 @@ impl Editor {
         Ok(slf)
     }
 
+    #[pyo3(signature = (range, fmt, pattern = "solid", bg_color = None))]
     fn set_fill<'py>(
         mut slf: PyRefMut<'py, Self>,
         range: &str,
-        fmt: &str,
+        fmt: &Bound<'py, PyAny>,
+        pattern: &str,
+        bg_color: Option<&Bound<'py, PyAny>>,
     ) -> PyResult<PyRefMut<'py, Self>> {
+        let pattern: PatternType = pattern
+            .parse()
+            .map_err(|e: anyhow::Error| PyRuntimeError::new_err(e.to_string()))?;
+        let spec = FillSpec::pattern(
+            pattern,
+            Some(extract_color(fmt)?),
+            bg_color.map(extract_color).transpose()?,
+        );
         slf.editor
-            .set_fill(range, fmt)
+            .set_fill_spec(range, &spec)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         Ok(slf)
     }
 
-    #[pyo3(signature = (range, name, size, bold = false, italic = false, align = None))]
+    #[pyo3(signature = (range, stops, degree = 0.0, path = None))]
+    fn set_gradient_fill<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        stops: Vec<(f64, Bound<'py, PyAny>)>,
+        degree: f64,
+        path: Option<(f64, f64, f64, f64)>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let stops = stops
+            .iter()
+            .map(|(pos, c)| Ok((*pos, extract_color(c)?)))
+            .collect::<PyResult<Vec<_>>>()?;
+        let gradient = match path {
+            Some((left, right, top, bottom)) => GradientType::Path {
+                left,
+                right,
+                top,
+                bottom,
+            },
+            None => GradientType::Linear { degree },
+        };
+        slf.editor
+            .set_fill_spec(range, &FillSpec::Gradient { gradient, stops })
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (
+        range,
+        name,
+        size,
+        bold = false,
+        italic = false,
+        align = None,
+        color = None,
+        theme_color = None,
+        indexed_color = None,
+        underline = None,
+        strike = false,
+        vert_align = None,
+        family = None,
+        scheme = None
+    ))]
+    #[allow(clippy::too_many_arguments)]
     fn set_font<'py>(
         mut slf: PyRefMut<'py, Self>,
         range: &str,
@@ -477,16 +1204,53 @@ impl Editor {
         bold: bool,
         italic: bool,
         align: Option<PyAlignSpec>,
+        color: Option<&Bound<'py, PyAny>>,
+        theme_color: Option<u32>,
+        indexed_color: Option<u32>,
+        underline: Option<&str>,
+        strike: bool,
+        vert_align: Option<&str>,
+        family: Option<u32>,
+        scheme: Option<String>,
     ) -> PyResult<PyRefMut<'py, Self>> {
-        let editor = &mut slf.editor;
+        let color = match (color, theme_color, indexed_color) {
+            (None, None, None) => None,
+            (Some(c), None, None) => Some(extract_color(c)?),
+            (None, Some(t), None) => Some(Color::theme(t)),
+            (None, None, Some(i)) => Some(Color::indexed(i)),
+            _ => {
+                return Err(PyRuntimeError::new_err(
+                    "pass only one of color, theme_color, indexed_color",
+                ));
+            }
+        };
+        let underline = underline
+            .map(str::parse::<Underline>)
+            .transpose()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let vert_align = vert_align
+            .map(str::parse::<FontVertAlign>)
+            .transpose()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        let spec = FontSpec {
+            bold,
+            italic,
+            color,
+            underline,
+            strike,
+            vert_align,
+            family,
+            scheme,
+            ..FontSpec::new(name, size)
+        };
 
+        let editor = &mut slf.editor;
+        editor
+            .set_font_spec(range, &spec)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         if let Some(py_align_spec) = align {
             editor
-                .set_font_with_alignment(range, name, size, bold, italic, &py_align_spec.0)
-                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
-        } else {
-            editor
-                .set_font(range, name, size, bold, italic)
+                .set_alignment_spec(range, &py_align_spec.0)
                 .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         }
         Ok(slf)
@@ -498,21 +1262,44 @@ impl Editor {
         spec: PyAlignSpec,
     ) -> PyResult<PyRefMut<'py, Self>> {
         slf.editor
-            .set_alignment(range, &spec.0)
+            .set_alignment_spec(range, &spec.0)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         Ok(slf)
     }
 
+    #[pyo3(signature = (range, replace_overlapping = false, clear_non_anchor = false))]
     fn merge_cells<'py>(
         mut slf: PyRefMut<'py, Self>,
         range: &str,
+        replace_overlapping: bool,
+        clear_non_anchor: bool,
     ) -> PyResult<PyRefMut<'py, Self>> {
+        let opts = MergeOptions {
+            replace_overlapping,
+            clear_non_anchor,
+        };
         slf.editor
-            .merge_cells(range)
+            .merge_cells_with(range, opts)
             .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
         Ok(slf)
     }
 
+    fn unmerge_cells<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .unmerge_cells(range)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn merged_ranges(&self) -> PyResult<Vec<String>> {
+        self.editor
+            .merged_ranges()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
     fn set_border<'py>(
         mut slf: PyRefMut<'py, Self>,
         range: &str,
@@ -524,6 +1311,275 @@ impl Editor {
         Ok(slf)
     }
 
+    fn set_border_spec<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        spec: PyBorderSpec,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .set_border_spec(range, &spec.0)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn add_conditional_format<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        rule: PyConditionalRule,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .add_conditional_format(range, &rule.0)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        range,
+        rule,
+        allow_blank = true,
+        in_cell_dropdown = true,
+        prompt_title = None,
+        prompt = None,
+        error_title = None,
+        error = None,
+        error_style = "stop",
+    ))]
+    fn add_data_validation<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        rule: PyValidationRule,
+        allow_blank: bool,
+        in_cell_dropdown: bool,
+        prompt_title: Option<String>,
+        prompt: Option<String>,
+        error_title: Option<String>,
+        error: Option<String>,
+        error_style: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let spec = DataValidation {
+            rule: rule.0,
+            allow_blank,
+            in_cell_dropdown,
+            prompt_title,
+            prompt,
+            error_title,
+            error,
+            error_style: error_style
+                .parse::<ErrorStyle>()
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?,
+        };
+        slf.editor
+            .add_data_validation(range, &spec)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn remove_data_validations<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .remove_data_validations(range)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn data_validation_ranges(&self) -> Vec<String> {
+        self.editor.data_validation_ranges()
+    }
+
+    #[pyo3(signature = (coord, target, display = None, tooltip = None))]
+    fn set_hyperlink<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        coord: &str,
+        target: &str,
+        display: Option<&str>,
+        tooltip: Option<&str>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .set_hyperlink(coord, target, display, tooltip)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn remove_hyperlink<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .remove_hyperlink(range)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn hyperlink(&self, coord: &str) -> PyResult<Option<String>> {
+        self.editor
+            .hyperlink(coord)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))
+    }
+
+    fn add_comment<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        coord: &str,
+        author: &str,
+        text: &str,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .add_comment(coord, author, text)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    /// Notes of the current sheet as `(cell, author, text)` tuples.
+    fn comments(&self) -> PyResult<Vec<(String, String, String)>> {
+        let list = self
+            .editor
+            .comments()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(list
+            .into_iter()
+            .map(|c| (c.cell, c.author, c.text))
+            .collect())
+    }
+
+    fn set_outline_border<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        side: PyBorderSide,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .set_outline_border(range, &side.0)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (range, horizontal = None, vertical = None))]
+    fn set_inner_borders<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        horizontal: Option<PyBorderSide>,
+        vertical: Option<PyBorderSide>,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .set_inner_borders(
+                range,
+                horizontal.as_ref().map(|s| &s.0),
+                vertical.as_ref().map(|s| &s.0),
+            )
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[pyo3(signature = (range, locked = true, hidden = false))]
+    fn set_cell_protection<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        range: &str,
+        locked: bool,
+        hidden: bool,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .set_cell_protection(range, CellProtection { locked, hidden })
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        password = None,
+        legacy_hash = false,
+        select_locked_cells = true,
+        select_unlocked_cells = true,
+        format_cells = false,
+        format_columns = false,
+        format_rows = false,
+        insert_columns = false,
+        insert_rows = false,
+        insert_hyperlinks = false,
+        delete_columns = false,
+        delete_rows = false,
+        sort = false,
+        auto_filter = false,
+        pivot_tables = false,
+        edit_objects = false,
+        edit_scenarios = false,
+    ))]
+    fn protect_sheet<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        password: Option<&str>,
+        legacy_hash: bool,
+        select_locked_cells: bool,
+        select_unlocked_cells: bool,
+        format_cells: bool,
+        format_columns: bool,
+        format_rows: bool,
+        insert_columns: bool,
+        insert_rows: bool,
+        insert_hyperlinks: bool,
+        delete_columns: bool,
+        delete_rows: bool,
+        sort: bool,
+        auto_filter: bool,
+        pivot_tables: bool,
+        edit_objects: bool,
+        edit_scenarios: bool,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        let opts = SheetProtection {
+            select_locked_cells,
+            select_unlocked_cells,
+            format_cells,
+            format_columns,
+            format_rows,
+            insert_columns,
+            insert_rows,
+            insert_hyperlinks,
+            delete_columns,
+            delete_rows,
+            sort,
+            auto_filter,
+            pivot_tables,
+            edit_objects,
+            edit_scenarios,
+            legacy_hash,
+        };
+        slf.editor
+            .protect_sheet(&opts, password)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn unprotect_sheet<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .unprotect_sheet()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn is_sheet_protected(&self) -> bool {
+        self.editor.is_sheet_protected()
+    }
+
+    #[pyo3(signature = (password = None, lock_structure = true, lock_windows = false, legacy_hash = false))]
+    fn protect_workbook<'py>(
+        mut slf: PyRefMut<'py, Self>,
+        password: Option<&str>,
+        lock_structure: bool,
+        lock_windows: bool,
+        legacy_hash: bool,
+    ) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .protect_workbook(lock_structure, lock_windows, password, legacy_hash)
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
+    fn unprotect_workbook<'py>(mut slf: PyRefMut<'py, Self>) -> PyResult<PyRefMut<'py, Self>> {
+        slf.editor
+            .unprotect_workbook()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        Ok(slf)
+    }
+
     fn set_column_width<'py>(
         mut slf: PyRefMut<'py, Self>,
         col_letter: &str,
@@ -600,6 +1656,46 @@ struct Scanner {
     path: PathBuf,
 }
 
+impl Editor {
+    fn write_arrow(
+        &mut self,
+        reader: arrow_array::ffi_stream::ArrowArrayStreamReader,
+        start: &str,
+    ) -> PyResult<()> {
+        let digits = start
+            .find(|c: char| c.is_ascii_digit())
+            .ok_or_else(|| PyRuntimeError::new_err(format!("Invalid start cell: {start}")))?;
+        let col = index_to_excel_col(excel_col_to_index(&start[..digits])?);
+        let mut row: u32 = start[digits..]
+            .parse()
+            .map_err(|_| PyRuntimeError::new_err(format!("Invalid start cell: {start}")))?;
+
+        let header: Vec<CellValue> = reader
+            .schema()
+            .fields()
+            .iter()
+            .map(|f| CellValue::Text(f.name().clone()))
+            .collect();
+        self.editor
+            .append_table_at(&format!("{col}{row}"), [header])
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+        row += 1;
+
+        for batch in reader {
+            let batch = batch.map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            if batch.num_rows() == 0 {
+                continue;
+            }
+            let columns = arrow::batch_to_columns(&batch)?;
+            self.editor
+                .append_columns_at(&format!("{col}{row}"), &columns)
+                .map_err(|e| PyRuntimeError::new_err(e.to_string()))?;
+            row += batch.num_rows() as u32;
+        }
+        Ok(())
+    }
+}
+
 #[pymethods]
 impl Scanner {
     #[new]
@@ -608,12 +1704,12 @@ impl Scanner {
     }
 
     fn get_sheets(&self) -> PyResult<Vec<String>> {
-        scan_excel(self.path.clone()).map_err(|e| PyRuntimeError::new_err(e.to_string()))
+        scan(&self.path).map_err(|e| PyRuntimeError::new_err(e.to_string()))
     }
 
     #[pyo3(signature = (sheet_name = None))]
     fn open_editor(&self, sheet_name: Option<&str>) -> PyResult<Editor> {
-        open_editor_with_optional_sheet(self.path.clone(), sheet_name)
+        open_editor_with_optional_sheet(WorkbookSource::Path(self.path.clone()), sheet_name)
     }
 }
 
@@ -625,14 +1721,26 @@ fn excelsior(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(create_excel, m)?)?;
 
     m.add_class::<PyAlignSpec>()?;
+    m.add_class::<PyColor>()?;
+    m.add_class::<PyBorderSide>()?;
+    m.add_class::<PyBorderSpec>()?;
+    m.add_class::<PyDxfStyle>()?;
+    m.add_class::<PyConditionalRule>()?;
+    m.add_class::<PyValidationRule>()?;
 
     let horiz_enum = py.import("enum")?.getattr("Enum")?;
     let horiz_members = PyDict::new(py);
+    horiz_members.set_item("General", PyHorizAlignment(HorizAlignment::General))?;
     horiz_members.set_item("Left", PyHorizAlignment(HorizAlignment::Left))?;
     horiz_members.set_item("Center", PyHorizAlignment(HorizAlignment::Center))?;
     horiz_members.set_item("Right", PyHorizAlignment(HorizAlignment::Right))?;
     horiz_members.set_item("Fill", PyHorizAlignment(HorizAlignment::Fill))?;
     horiz_members.set_item("Justify", PyHorizAlignment(HorizAlignment::Justify))?;
+    horiz_members.set_item(
+        "CenterContinuous",
+        PyHorizAlignment(HorizAlignment::CenterContinuous),
+    )?;
+    horiz_members.set_item("Distributed", PyHorizAlignment(HorizAlignment::Distributed))?;
     let horiz_cls = horiz_enum.call1(("HorizAlignment", horiz_members))?;
     m.add("HorizAlignment", horiz_cls)?;
 
@@ -642,6 +1750,7 @@ fn excelsior(py: Python<'_>, m: &Bound<'_, PyModule>) -> PyResult<()> {
     vert_members.set_item("Center", PyVertAlignment(VertAlignment::Center))?;
     vert_members.set_item("Bottom", PyVertAlignment(VertAlignment::Bottom))?;
     vert_members.set_item("Justify", PyVertAlignment(VertAlignment::Justify))?;
+    vert_members.set_item("Distributed", PyVertAlignment(VertAlignment::Distributed))?;
     let vert_cls = vert_enum.call1(("VertAlignment", vert_members))?;
     m.add("VertAlignment", vert_cls)?;
 
@@ -0,0 +1,40 @@
+import io
+from pathlib import Path
+
+from excelsior import Editor, create_excel, scan_excel
+
+from helpers_excel import cell_text, get_sheet
+
+
+def test_open_from_bytes_and_to_bytes(tmp_path: Path) -> None:
+    src = tmp_path / "src.xlsx"
+    out = tmp_path / "out.xlsx"
+    create_excel(str(src), "Data")
+    data = src.read_bytes()
+
+    assert scan_excel(data) == ["Data"]
+    editor = Editor.open(data)
+    editor.set_cell("A1", "in memory")
+    result = editor.to_bytes()
+
+    out.write_bytes(result)
+    ws = get_sheet(out, "Data")
+    assert cell_text(ws["A1"].value) == "in memory"
+
+
+def test_file_like_objects(tmp_path: Path) -> None:
+    src = tmp_path / "src.xlsx"
+    out = tmp_path / "out.xlsx"
+    create_excel(str(src), "Data")
+
+    with open(src, "rb") as fh:
+        editor = Editor(fh, "Data")
+    editor.set_cell("B2", "file-like")
+
+    buf = io.BytesIO()
+    editor.save(buf)
+    out.write_bytes(buf.getvalue())
+
+    ws = get_sheet(out, "Data")
+    assert cell_text(ws["B2"].value) == "file-like"
+    assert Editor(io.BytesIO(buf.getvalue())).to_bytes()[:2] == b"PK"
@@ -0,0 +1,39 @@
+from pathlib import Path
+
+import pytest
+
+from excelsior import Editor
+
+from helpers_excel import get_sheet
+
+
+def test_comments_roundtrip_and_replace(tmp_path: Path) -> None:
+    src = tmp_path / "notes.xlsx"
+    out = tmp_path / "notes_out.xlsx"
+
+    editor = Editor.create(str(src), "Index")
+    editor.add_worksheet("Detail")
+    editor.with_worksheet("Index")
+    editor.append_row(["Tab", "Amount"])
+    editor.add_comment("B2", "Anna", "Checked\nagainst ERP")
+    editor.add_comment("C5", "Boris", "Needs review")
+    editor.add_comment("C5", "Anna", "Reviewed")
+    with pytest.raises(RuntimeError):
+        editor.add_comment("5C", "Anna", "bad")
+    editor.with_worksheet("Detail")
+    editor.add_comment("A1", "Boris", "Source: ERP")
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Index")
+    assert ws["B2"].comment.author == "Anna"
+    assert ws["B2"].comment.text == "Checked\nagainst ERP"
+    assert ws["C5"].comment.text == "Reviewed"
+    assert get_sheet(out, "Detail")["A1"].comment.text == "Source: ERP"
+
+    reopened = Editor(str(out), "Index")
+    assert reopened.comments() == [
+        ("B2", "Anna", "Checked\nagainst ERP"),
+        ("C5", "Anna", "Reviewed"),
+    ]
+    reopened.add_comment("A1", "Boris", "Header")
+    assert len(reopened.comments()) == 3
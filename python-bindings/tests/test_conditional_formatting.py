@@ -0,0 +1,49 @@
+from pathlib import Path
+
+import pytest
+
+from excelsior import Color, ConditionalRule, DxfStyle, Editor
+
+from helpers_excel import get_sheet
+
+
+def test_conditional_formatting_rules(tmp_path: Path) -> None:
+    src = tmp_path / "cf.xlsx"
+    out = tmp_path / "cf_out.xlsx"
+
+    bad = DxfStyle(font_color="9C0006", fill="FFC7CE")
+    editor = Editor.create(str(src), "KPI")
+    editor.append_row(["Region", "Plan", "Fact"])
+    editor.append_row(["North", "10", "12"])
+    editor.append_row(["South", "10", "7"])
+    editor.add_conditional_format("C2:C3", ConditionalRule.cell_is("<", "B2", bad))
+    editor.add_conditional_format("A2:A3", ConditionalRule.expression("=$C2<$B2", bad))
+    editor.add_conditional_format(
+        "C2:C3",
+        ConditionalRule.color_scale([("min", "F8696B"), ("percentile:50", "FFEB84"), ("max", Color(theme=9))]),
+    )
+    editor.add_conditional_format("B2:B3", ConditionalRule.data_bar("638EC6"))
+    editor.add_conditional_format("C2:C3", ConditionalRule.icon_set("3TrafficLights1", ["percent:0", "num:8", "num:11"]))
+    editor.add_conditional_format("B2:C3", ConditionalRule.top(1, DxfStyle(bold=True), bottom=True))
+    editor.add_conditional_format("A2:A3", ConditionalRule.duplicates(bad))
+    with pytest.raises(RuntimeError):
+        editor.add_conditional_format("C2:C3", ConditionalRule.cell_is("between", "1", bad))
+    with pytest.raises(RuntimeError):
+        editor.add_conditional_format("C2", ConditionalRule.icon_set("7Stars"))
+    editor.save(str(out))
+
+    ws = get_sheet(out, "KPI")
+    rules = {r.type: (str(cf.sqref), r) for cf in ws.conditional_formatting for r in cf.rules}
+    assert rules["cellIs"][0] == "C2:C3"
+    assert rules["cellIs"][1].operator == "lessThan"
+    assert rules["cellIs"][1].formula == ["B2"]
+    assert rules["cellIs"][1].dxf.fill.bgColor.rgb == "FFFFC7CE"
+    assert rules["expression"][1].formula == ["$C2<$B2"]
+    assert rules["colorScale"][1].colorScale.cfvo[1].type == "percentile"
+    assert rules["dataBar"][0] == "B2:B3"
+    assert rules["iconSet"][1].iconSet.iconSet == "3TrafficLights1"
+    assert [c.val for c in rules["iconSet"][1].iconSet.cfvo] == [0, 8, 11]
+    assert rules["top10"][1].bottom is True
+    assert rules["top10"][1].dxf.font.b is True
+    assert rules["duplicateValues"][0] == "A2:A3"
+    assert sorted(r.priority for _, r in rules.values()) == list(range(1, 8))
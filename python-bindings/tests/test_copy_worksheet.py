@@ -0,0 +1,60 @@
+from pathlib import Path
+
+from excelsior import Editor, scan_excel
+
+from helpers_excel import cell_text, get_sheet
+
+
+def test_copy_worksheet(tmp_path: Path) -> None:
+    src = tmp_path / "template.xlsx"
+    out = tmp_path / "regions.xlsx"
+
+    editor = Editor.create(str(src), "Template")
+    editor.append_row(["Region", "Sales"])
+    editor.set_cell("C1", "=Template!B2*2")
+    for region in ["North", "South"]:
+        editor.copy_worksheet("Template", region)
+        editor.set_cell("A2", region)
+    editor.copy_worksheet("Template", "First", 0)
+    editor.save(str(out))
+
+    assert scan_excel(str(out)) == ["First", "Template", "North", "South"]
+    ws = get_sheet(out, "South")
+    assert cell_text(ws["A1"].value) == "Region"
+    assert cell_text(ws["A2"].value) == "South"
+    assert ws["C1"].value == "=South!B2*2"
+    assert get_sheet(out, "Template")["A2"].value is None
+
+
+def test_move_worksheet(tmp_path: Path) -> None:
+    src = tmp_path / "move.xlsx"
+    out = tmp_path / "move_out.xlsx"
+
+    editor = Editor.create(str(src), "A")
+    editor.add_worksheet("B").add_worksheet("Summary")
+    editor.set_cell("A1", "total")
+    editor.move_worksheet("Summary", 0)
+    editor.save(str(out))
+
+    assert scan_excel(str(out)) == ["Summary", "A", "B"]
+    assert cell_text(get_sheet(out, "Summary")["A1"].value) == "total"
+
+
+def test_import_worksheet(tmp_path: Path) -> None:
+    other = tmp_path / "other.xlsx"
+    out = tmp_path / "assembled.xlsx"
+
+    src = Editor.create(str(other), "Prices")
+    src.append_row(["Item", "Price"])
+    src.set_fill("A1:B1", "FFFF00")
+    src.save(str(other))
+
+    editor = Editor.create(str(tmp_path / "main.xlsx"), "Main")
+    editor.import_worksheet(str(other), "Prices")
+    editor.import_worksheet(Editor.open(str(other)), "Prices", "Copy", 0)
+    editor.save(str(out))
+
+    assert scan_excel(str(out)) == ["Copy", "Main", "Prices"]
+    ws = get_sheet(out, "Prices")
+    assert cell_text(ws["B1"].value) == "Price"
+    assert ws["A1"].fill.fgColor.rgb.endswith("FFFF00")
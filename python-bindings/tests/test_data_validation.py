@@ -0,0 +1,47 @@
+from pathlib import Path
+
+import pytest
+
+from excelsior import Editor, ValidationRule
+
+from helpers_excel import get_sheet
+
+
+def test_data_validation_dropdowns_and_bounds(tmp_path: Path) -> None:
+    src = tmp_path / "dv.xlsx"
+    out = tmp_path / "dv_out.xlsx"
+
+    editor = Editor.create(str(src), "Input")
+    editor.append_row(["Status", "Currency", "Qty", "Due", "Code"])
+    editor.add_data_validation(
+        "A2:A100",
+        ValidationRule.list(["Open", "In progress", "Done"]),
+        prompt_title="Status",
+        prompt="Pick a status",
+        error_title="Oops",
+        error="Not in the list",
+        error_style="warning",
+    )
+    editor.add_data_validation("B2:B100", ValidationRule.list_range("$J$1:$J$3"))
+    editor.add_data_validation("C2:C100", ValidationRule.compare("whole", "between", "1", "1000"))
+    editor.add_data_validation("D2:D100", ValidationRule.compare("date", ">=", "DATE(2025,1,1)"))
+    editor.add_data_validation("E2:E100", ValidationRule.custom("LEN(E2)<=5"), allow_blank=False)
+    with pytest.raises(RuntimeError):
+        editor.add_data_validation("A10", ValidationRule.list(["x"]))
+    with pytest.raises(RuntimeError):
+        editor.add_data_validation("F2", ValidationRule.compare("whole", "between", "1"))
+    assert editor.data_validation_ranges() == ["A2:A100", "B2:B100", "C2:C100", "D2:D100", "E2:E100"]
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Input")
+    dvs = {str(dv.sqref): dv for dv in ws.data_validations.dataValidation}
+    status = dvs["A2:A100"]
+    assert status.type == "list"
+    assert status.formula1 == '"Open,In progress,Done"'
+    assert status.promptTitle == "Status"
+    assert status.errorStyle == "warning"
+    assert dvs["B2:B100"].formula1 == "$J$1:$J$3"
+    assert dvs["C2:C100"].type == "whole"
+    assert (dvs["C2:C100"].formula1, dvs["C2:C100"].formula2) == ("1", "1000")
+    assert dvs["D2:D100"].operator == "greaterThanOrEqual"
+    assert dvs["E2:E100"].allow_blank is False
@@ -0,0 +1,36 @@
+from pathlib import Path
+
+import pytest
+
+from excelsior import Editor
+
+from helpers_excel import cell_text, get_sheet
+
+
+def test_hyperlinks_external_internal_and_mailto(tmp_path: Path) -> None:
+    src = tmp_path / "links.xlsx"
+    out = tmp_path / "links_out.xlsx"
+
+    editor = Editor.create(str(src), "Index")
+    editor.add_worksheet("Detail 1")
+    editor.with_worksheet("Index")
+    editor.append_row(["Tab", "Ticket", "Contact"])
+    editor.set_hyperlink("A2", "#'Detail 1'!A1", display="Detail 1", tooltip="Open tab")
+    editor.set_hyperlink("B2", "https://tracker.example.com/browse/FIN-1?a=1&b=2", display="FIN-1")
+    editor.set_hyperlink("C2", "mailto:finance@example.com", display="Finance")
+    with pytest.raises(RuntimeError):
+        editor.set_hyperlink("D2", "")
+    assert editor.hyperlink("B2") == "https://tracker.example.com/browse/FIN-1?a=1&b=2"
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Index")
+    assert ws["A2"].hyperlink.location == "'Detail 1'!A1"
+    assert ws["A2"].hyperlink.tooltip == "Open tab"
+    assert ws["B2"].hyperlink.target == "https://tracker.example.com/browse/FIN-1?a=1&b=2"
+    assert ws["C2"].hyperlink.target == "mailto:finance@example.com"
+    assert cell_text(ws["B2"].value) == "FIN-1"
+
+    reopened = Editor(str(out), "Index")
+    reopened.remove_hyperlink("B2:C2")
+    assert reopened.hyperlink("B2") is None
+    assert reopened.hyperlink("A2") == "#'Detail 1'!A1"
@@ -0,0 +1,29 @@
+from pathlib import Path
+
+import pytest
+
+from excelsior import Editor
+
+from helpers_excel import cell_text, get_sheet
+
+
+def test_merge_overlap_and_unmerge(tmp_path: Path) -> None:
+    src = tmp_path / "merges.xlsx"
+    out = tmp_path / "merges_out.xlsx"
+
+    editor = Editor.create(str(src), "Data")
+    editor.append_row(["a", "b", "c"])
+    editor.merge_cells("A1:B1")
+    with pytest.raises(RuntimeError):
+        editor.merge_cells("B1:C1")
+
+    editor.merge_cells("A1:C2", replace_overlapping=True, clear_non_anchor=True)
+    editor.merge_cells("E5:F6")
+    assert editor.merged_ranges() == ["A1:C2", "E5:F6"]
+    editor.unmerge_cells("F6")
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Data")
+    assert [str(r) for r in ws.merged_cells.ranges] == ["A1:C2"]
+    assert cell_text(ws["A1"].value) == "a"
+    assert ws["B1"].value is None
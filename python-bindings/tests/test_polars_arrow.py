@@ -0,0 +1,66 @@
+from datetime import date, datetime
+from pathlib import Path
+
+import polars as pl
+
+from excelsior import Editor
+
+from helpers_excel import get_sheet
+
+
+def test_with_polars_writes_typed_cells(tmp_path: Path) -> None:
+    src = tmp_path / "arrow.xlsx"
+    out = tmp_path / "arrow_out.xlsx"
+
+    df = pl.DataFrame(
+        {
+            "zip": ["00123", None],
+            "qty": [1, 2],
+            "price": [1.5, None],
+            "ok": [True, False],
+            "day": [date(2024, 1, 31), date(2024, 2, 1)],
+            "at": [datetime(2024, 1, 31, 8, 30), None],
+            "kind": pl.Series(["a", "b"], dtype=pl.Categorical),
+        }
+    )
+    editor = Editor.create(str(src), "Data")
+    editor.with_polars(df, "B2")
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Data")
+    assert [c.value for c in ws[2][1:8]] == list(df.columns)
+    assert ws["B3"].value == "00123"
+    assert ws["B4"].value is None
+    assert ws["C3"].value == 1
+    assert ws["D4"].value is None
+    assert ws["E3"].value is True
+    assert ws["F3"].is_date and ws["F3"].value.date() == date(2024, 1, 31)
+    assert ws["G3"].value == datetime(2024, 1, 31, 8, 30)
+    assert ws["H4"].value == "b"
+
+    back = Editor.open(str(out), "Data").to_polars(range="B2:H4")
+    assert back["zip"].to_list() == ["00123", None]
+    assert back["day"].to_list() == df["day"].to_list()
+
+
+def test_with_polars_timezone_aware_columns(tmp_path: Path) -> None:
+    src = tmp_path / "arrow_tz.xlsx"
+    out = tmp_path / "arrow_tz_out.xlsx"
+
+    utc = datetime(2024, 1, 31, 8, 30)
+    df = pl.DataFrame({"at": [utc, None]}).with_columns(
+        pl.col("at").dt.replace_time_zone("UTC").alias("utc"),
+        pl.col("at").dt.replace_time_zone("UTC").dt.convert_time_zone("+03:00").alias("fixed"),
+        pl.col("at").dt.replace_time_zone("UTC").dt.convert_time_zone("Europe/Berlin").alias("named"),
+    )
+    editor = Editor.create(str(src), "Data")
+    editor.with_polars(df)
+    editor.save(str(out))
+
+    # в ячейку пишется местное время зоны столбца
+    ws = get_sheet(out, "Data")
+    assert ws["A2"].value == utc
+    assert ws["B2"].value == utc
+    assert ws["C2"].value == datetime(2024, 1, 31, 11, 30)
+    assert ws["D2"].value == datetime(2024, 1, 31, 9, 30)
+    assert ws["C3"].value is None
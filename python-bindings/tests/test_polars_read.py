@@ -0,0 +1,46 @@
+from datetime import date, datetime
+from pathlib import Path
+
+import polars as pl
+
+from excelsior import Editor
+
+
+def test_to_polars_infers_dtypes(tmp_path: Path) -> None:
+    src = tmp_path / "read.xlsx"
+    out = tmp_path / "read_out.xlsx"
+
+    editor = Editor.create(str(src), "Data")
+    editor.append_table_at(
+        [
+            ["id", "name", "price", "ok", "day", "at"],
+            [1, "alpha", 1.5, True, date(2024, 1, 31), datetime(2024, 1, 31, 8, 30)],
+            [2, "beta", None, False, date(2024, 2, 1), datetime(2024, 2, 1, 17, 0)],
+        ],
+        "A1",
+    )
+    editor.save(str(out))
+
+    df = Editor.open(str(out), "Data").to_polars()
+    assert df.columns == ["id", "name", "price", "ok", "day", "at"]
+    assert df.schema["id"] == pl.Int64
+    assert df.schema["name"] == pl.String
+    assert df.schema["price"] == pl.Float64
+    assert df.schema["ok"] == pl.Boolean
+    assert df.schema["day"] == pl.Date
+    assert df.schema["at"] == pl.Datetime
+    assert df["price"].to_list() == [1.5, None]
+    assert df["day"].to_list() == [date(2024, 1, 31), date(2024, 2, 1)]
+
+
+def test_to_polars_range_without_header(tmp_path: Path) -> None:
+    src = tmp_path / "range.xlsx"
+
+    editor = Editor.create(str(src), "Data")
+    editor.append_table_at([["a", "1"], ["b", "x"]], "B2")
+
+    df = editor.to_polars(range="B2:C3", header=False, dtypes={"column_0": pl.Categorical})
+    assert df.columns == ["column_0", "column_1"]
+    assert df.schema["column_0"] == pl.Categorical
+    # число и текст в одной колонке → String
+    assert df["column_1"].to_list() == ["1", "x"]
@@ -0,0 +1,37 @@
+from pathlib import Path
+
+from excelsior import Editor
+
+from helpers_excel import get_sheet
+
+
+def test_insert_and_delete_rows(tmp_path: Path) -> None:
+    src = tmp_path / "rows.xlsx"
+    out = tmp_path / "rows_out.xlsx"
+
+    editor = Editor.create(str(src), "Data")
+    editor.append_table_at([[1], [2], [3], [4]], "A1")
+    editor.set_cell("B1", "=SUM(A1:A4)")
+    editor.insert_rows(2, 2)
+    editor.delete_rows(6)
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Data")
+    assert [ws.cell(row=r, column=1).value for r in range(1, 6)] == [1, None, None, 2, 3]
+    assert ws["B1"].value == "=SUM(A1:A5)"
+
+
+def test_insert_and_delete_columns(tmp_path: Path) -> None:
+    src = tmp_path / "cols.xlsx"
+    out = tmp_path / "cols_out.xlsx"
+
+    editor = Editor.create(str(src), "Data")
+    editor.append_row([1, 2, 3])
+    editor.set_cell("A2", "=SUM(A1:C1)")
+    editor.insert_columns("B", 2)
+    editor.delete_columns("E")
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Data")
+    assert [ws.cell(row=1, column=c).value for c in range(1, 5)] == [1, None, None, 2]
+    assert ws["A2"].value == "=SUM(A1:D1)"
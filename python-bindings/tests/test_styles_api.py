@@ -1,6 +1,15 @@
 from pathlib import Path
 
-from excelsior import AlignSpec, Editor, HorizAlignment, VertAlignment, create_excel
+from excelsior import (
+    AlignSpec,
+    BorderSide,
+    BorderSpec,
+    Color,
+    Editor,
+    HorizAlignment,
+    VertAlignment,
+    create_excel,
+)
 
 from helpers_excel import assert_with_libreoffice, get_sheet
 
@@ -65,3 +74,109 @@ def test_alignment_open_column_selector_from_cell(tmp_path: Path) -> None:
     assert ws["A1"].alignment.horizontal != "center"
     assert ws["A2"].alignment.horizontal == "center"
     assert ws["A3"].alignment.horizontal == "center"
+
+
+def test_border_sides_outline_and_inner(tmp_path: Path) -> None:
+    src = tmp_path / "borders.xlsx"
+    out = tmp_path / "borders_out.xlsx"
+
+    editor = Editor.create(str(src), "Report")
+    editor.append_table_at([["h1", "h2"], ["1", "2"], ["3", "4"]], "A1")
+    editor.set_border_spec("A1:B1", BorderSpec(bottom=BorderSide("thick")))
+    editor.set_outline_border("A2:B3", BorderSide("medium", "FF0000"))
+    editor.set_inner_borders("A2:B3", horizontal=BorderSide("hair"))
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Report")
+    assert ws["A1"].border.bottom.style == "thick"
+    assert ws["A2"].border.left.style == "medium"
+    assert ws["A2"].border.left.color.rgb == "FFFF0000"
+    assert ws["A2"].border.bottom.style == "hair"
+    assert ws["B3"].border.right.style == "medium"
+    assert ws["A2"].border.right.style is None
+
+
+def test_font_color_underline_strike(tmp_path: Path) -> None:
+    src = tmp_path / "fonts.xlsx"
+    out = tmp_path / "fonts_out.xlsx"
+
+    editor = Editor.create(str(src), "Fonts")
+    editor.append_table_at([["-10", "5", "x2"]], "A1")
+    editor.set_font("A1", "Arial", 10.0, color="FF0000")
+    editor.set_font("B1", "Calibri", 11.0, bold=True, theme_color=4, underline="double")
+    editor.set_font("C1", "Calibri", 11.0, strike=True, vert_align="superscript", family=2, scheme="minor")
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Fonts")
+    assert ws["A1"].font.color.rgb == "FFFF0000"
+    assert ws["B1"].font.color.theme == 4
+    assert ws["B1"].font.underline == "double"
+    assert bool(ws["C1"].font.strike)
+    assert ws["C1"].font.vertAlign == "superscript"
+    assert ws["C1"].font.scheme == "minor"
+
+
+def test_pattern_and_gradient_fills(tmp_path: Path) -> None:
+    src = tmp_path / "fills.xlsx"
+    out = tmp_path / "fills_out.xlsx"
+
+    editor = Editor.create(str(src), "Fills")
+    editor.append_table_at([["1", "2", "3"]], "A1")
+    editor.set_fill("A1", Color(theme=4, tint=-0.25), pattern="lightUp", bg_color="FFFFFF")
+    editor.set_gradient_fill("B1", [(0.0, "FFFFFF"), (1.0, Color(theme=4))], degree=90)
+    editor.set_gradient_fill("C1", [(0.0, "FFFFFF"), (1.0, "FF0000")], path=(0.5, 0.5, 0.5, 0.5))
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Fills")
+    assert ws["A1"].fill.patternType == "lightUp"
+    assert ws["A1"].fill.fgColor.theme == 4
+    assert round(ws["A1"].fill.fgColor.tint, 2) == -0.25
+    assert ws["B1"].fill.type == "linear"
+    assert ws["B1"].fill.degree == 90
+    assert ws["C1"].fill.type == "path"
+    assert ws["C1"].fill.stop[1].color.rgb == "FFFF0000"
+
+
+def test_alignment_indent_rotation_shrink(tmp_path: Path) -> None:
+    src = tmp_path / "align.xlsx"
+    out = tmp_path / "align_out.xlsx"
+
+    editor = Editor.create(str(src), "PnL")
+    editor.append_table_at([["Line", "Q1", "Q2"], ["Services", "1", "2"]], "A1")
+    editor.set_alignment("B1:C1", AlignSpec(horiz=HorizAlignment.CenterContinuous, text_rotation=90))
+    editor.set_alignment("A2", AlignSpec(indent=2, shrink_to_fit=True, reading_order="ltr"))
+    editor.set_alignment("A1", AlignSpec(text_rotation=255))
+    editor.save(str(out))
+
+    ws = get_sheet(out, "PnL")
+    assert ws["B1"].alignment.horizontal == "centerContinuous"
+    assert ws["B1"].alignment.text_rotation == 90
+    assert ws["A2"].alignment.indent == 2
+    assert ws["A2"].alignment.shrink_to_fit is True
+    assert ws["A2"].alignment.reading_order == 1
+    assert ws["A1"].alignment.text_rotation == 255
+
+
+def test_cell_sheet_and_workbook_protection(tmp_path: Path) -> None:
+    src = tmp_path / "protect.xlsx"
+    out = tmp_path / "protect_out.xlsx"
+
+    editor = Editor.create(str(src), "Form")
+    editor.append_table_at([["Name", ""], ["Total", "=1+1"]], "A1")
+    editor.set_cell_protection("B1", locked=False)
+    editor.set_cell_protection("B2", hidden=True)
+    editor.protect_sheet(password="secret", sort=True, format_columns=True)
+    editor.protect_workbook(password="secret")
+    assert editor.is_sheet_protected()
+    editor.save(str(out))
+
+    ws = get_sheet(out, "Form")
+    assert ws.protection.sheet is True
+    assert ws.protection.sort is False
+    assert ws.protection.formatColumns is False
+    assert ws.protection.formatCells is True
+    assert ws.protection.algorithmName == "SHA-512"
+    assert ws["B1"].protection.locked is False
+    assert ws["B2"].protection.hidden is True
+    assert ws.parent.security.lockStructure is True
+    assert ws.parent.security.workbookAlgorithmName == "SHA-512"
@@ -0,0 +1,317 @@
+//! cell.rs – typed cell values
+
+use anyhow::{Result, bail};
+
+/// Typed value of a single worksheet cell.
+///
+/// Returned by the read API and accepted by every write method (`set_cell`, `append_row`,
+/// `append_table*`). Plain numbers, booleans and `Option`s convert directly; `&str` goes
+/// through the legacy type sniffing, see [`From<&str>`](#impl-From%3C%26str%3E-for-CellValue).
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellValue {
+    /// Numeric cell (`t="n"` or no `t` at all).
+    Number(f64),
+    /// Text: shared string (`t="s"`), inline string (`t="inlineStr"`) or string result (`t="str"`).
+    Text(String),
+    /// Boolean cell (`t="b"`).
+    Bool(bool),
+    /// Error value such as `#N/A` or `#DIV/0!` (`t="e"`).
+    Error(String),
+    /// Formula without the leading `=` and its cached result, if the file carries one.
+    Formula {
+        formula: String,
+        cached: Option<Box<CellValue>>,
+    },
+    /// Date, date-time or time of day; written as an Excel serial with a date number format.
+    Date(DateValue),
+    /// Missing cell or a cell without a value.
+    Blank,
+}
+
+impl CellValue {
+    pub fn is_blank(&self) -> bool {
+        matches!(self, CellValue::Blank)
+    }
+
+    /// Numeric value of the cell; for formulas the cached result is used.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            CellValue::Number(n) => Some(*n),
+            CellValue::Formula {
+                cached: Some(c), ..
+            } => c.as_number(),
+            _ => None,
+        }
+    }
+
+    /// Text value of the cell; for formulas the cached result is used.
+    pub fn as_text(&self) -> Option<&str> {
+        match self {
+            CellValue::Text(s) => Some(s),
+            CellValue::Formula {
+                cached: Some(c), ..
+            } => c.as_text(),
+            _ => None,
+        }
+    }
+}
+
+/// Legacy type sniffing: a leading `=` makes a formula, a finite number accepted by
+/// `f64::from_str` becomes a number, anything else is text.
+///
+/// Note that this turns `"00123"` or `"1e5"` into numbers; use [`CellValue::Text`]
+/// to write such values verbatim.
+impl From<&str> for CellValue {
+    fn from(s: &str) -> Self {
+        if let Some(f) = s.strip_prefix('=') {
+            return CellValue::Formula {
+                formula: f.to_owned(),
+                cached: None,
+            };
+        }
+        match s.parse::<f64>() {
+            Ok(n) if n.is_finite() => CellValue::Number(n),
+            _ => CellValue::Text(s.to_owned()),
+        }
+    }
+}
+
+/// Same sniffing as `From<&str>`.
+impl From<String> for CellValue {
+    fn from(s: String) -> Self {
+        CellValue::from(s.as_str())
+    }
+}
+
+/// Same sniffing as `From<&str>`.
+impl From<&String> for CellValue {
+    fn from(s: &String) -> Self {
+        CellValue::from(s.as_str())
+    }
+}
+
+impl From<DateValue> for CellValue {
+    fn from(d: DateValue) -> Self {
+        CellValue::Date(d)
+    }
+}
+
+impl From<bool> for CellValue {
+    fn from(b: bool) -> Self {
+        CellValue::Bool(b)
+    }
+}
+
+macro_rules! cell_value_from_number {
+    ($($t:ty),*) => {$(
+        impl From<$t> for CellValue {
+            fn from(n: $t) -> Self {
+                CellValue::Number(n as f64)
+            }
+        }
+    )*};
+}
+cell_value_from_number!(f64, f32, i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+
+/// `None` is written as a blank cell.
+impl<T: Into<CellValue>> From<Option<T>> for CellValue {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(CellValue::Blank, Into::into)
+    }
+}
+
+/// Date/time value for writing.
+///
+/// Converted to an Excel serial number on write (honouring the workbook's `date1904` flag),
+/// the cell gets [`DateValue::number_format`] unless styled otherwise later.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DateValue {
+    Date {
+        year: i32,
+        month: u32,
+        day: u32,
+    },
+    DateTime {
+        year: i32,
+        month: u32,
+        day: u32,
+        hour: u32,
+        minute: u32,
+        second: f64,
+    },
+    /// Time of day only, serial in `[0, 1)`.
+    Time {
+        hour: u32,
+        minute: u32,
+        second: f64,
+    },
+}
+
+impl DateValue {
+    pub fn date(year: i32, month: u32, day: u32) -> Self {
+        DateValue::Date { year, month, day }
+    }
+
+    pub fn datetime(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: f64) -> Self {
+        DateValue::DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    pub fn time(hour: u32, minute: u32, second: f64) -> Self {
+        DateValue::Time {
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    /// Number format applied to freshly written cells.
+    pub fn number_format(&self) -> &'static str {
+        match self {
+            DateValue::Date { .. } => "yyyy-mm-dd",
+            DateValue::DateTime { .. } => "yyyy-mm-dd hh:mm:ss",
+            DateValue::Time { .. } => "hh:mm:ss",
+        }
+    }
+
+    /// Inverse of [`DateValue::to_serial`]; always returns `DateTime`, rounded to milliseconds.
+    pub fn from_serial(serial: f64, date1904: bool) -> Result<Self> {
+        if !(0.0..2_958_466.0).contains(&serial) {
+            bail!("{serial} is not a valid Excel date serial");
+        }
+        let mut days = serial.floor() as i64;
+        let mut ms = ((serial - serial.floor()) * 86_400_000.0).round() as i64;
+        if ms == 86_400_000 {
+            days += 1;
+            ms = 0;
+        }
+        let epoch_days = if date1904 {
+            days_from_civil(1904, 1, 1) + days
+        } else if days < 61 {
+            // 60 – несуществующее 29.02.1900, отдаём 28.02
+            days_from_civil(1899, 12, 31) + days.min(59)
+        } else {
+            days_from_civil(1899, 12, 30) + days
+        };
+        let (year, month, day) = civil_from_days(epoch_days);
+        Ok(DateValue::DateTime {
+            year,
+            month,
+            day,
+            hour: (ms / 3_600_000) as u32,
+            minute: (ms / 60_000 % 60) as u32,
+            second: (ms % 60_000) as f64 / 1000.0,
+        })
+    }
+
+    /// `DateTime` for a Unix timestamp (UTC, no leap seconds).
+    pub fn from_unix(seconds: i64, nanos: u32) -> Self {
+        let secs = seconds.rem_euclid(86_400);
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+        DateValue::DateTime {
+            year,
+            month,
+            day,
+            hour: (secs / 3600) as u32,
+            minute: (secs / 60 % 60) as u32,
+            second: (secs % 60) as f64 + nanos as f64 / 1e9,
+        }
+    }
+
+    /// Excel serial number: whole days since the epoch plus the fraction of the day.
+    ///
+    /// The 1900 system keeps Excel's phantom 1900-02-29, so serials of dates from
+    /// 1900-03-01 on match what Excel shows. Dates before the epoch are rejected.
+    pub fn to_serial(&self, date1904: bool) -> Result<f64> {
+        match *self {
+            DateValue::Date { year, month, day } => date_serial(year, month, day, date1904),
+            DateValue::DateTime {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            } => {
+                Ok(date_serial(year, month, day, date1904)? + time_fraction(hour, minute, second)?)
+            }
+            DateValue::Time {
+                hour,
+                minute,
+                second,
+            } => time_fraction(hour, minute, second),
+        }
+    }
+}
+
+// дни от 1970-01-01 (proleptic Gregorian), алгоритм Howard Hinnant
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let y = year as i64 - (month <= 2) as i64;
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+// обратное к days_from_civil
+fn civil_from_days(z: i64) -> (i32, u32, u32) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year as i32, month, day)
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        4 | 6 | 9 | 11 => 30,
+        2 if (year % 4 == 0 && year % 100 != 0) || year % 400 == 0 => 29,
+        2 => 28,
+        _ => 31,
+    }
+}
+
+fn date_serial(year: i32, month: u32, day: u32, date1904: bool) -> Result<f64> {
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        bail!("invalid date {year:04}-{month:02}-{day:02}");
+    }
+    if year > 9999 {
+        bail!("date {year:04}-{month:02}-{day:02} is past 9999-12-31");
+    }
+    let days = days_from_civil(year, month, day);
+    let serial = if date1904 {
+        days - days_from_civil(1904, 1, 1)
+    } else if days < days_from_civil(1900, 3, 1) {
+        // до мнимого 29.02.1900: 1900-01-01 = 1
+        days - days_from_civil(1899, 12, 31)
+    } else {
+        days - days_from_civil(1899, 12, 30)
+    };
+    if serial < if date1904 { 0 } else { 1 } {
+        bail!(
+            "date {year:04}-{month:02}-{day:02} is before the {} epoch",
+            if date1904 { 1904 } else { 1900 }
+        );
+    }
+    Ok(serial as f64)
+}
+
+fn time_fraction(hour: u32, minute: u32, second: f64) -> Result<f64> {
+    if hour > 23 || minute > 59 || !(0.0..60.0).contains(&second) {
+        bail!("invalid time {hour:02}:{minute:02}:{second}");
+    }
+    Ok((hour as f64 * 3600.0 + minute as f64 * 60.0 + second) / 86400.0)
+}
@@ -0,0 +1,559 @@
+//! comments.rs – примечания ячеек: `xl/commentsN.xml` + VML-фигуры в `xl/drawings/vmlDrawingN.vml`
+
+use anyhow::{Context, Result, bail};
+use memchr::memmem;
+use quick_xml::{
+    Reader,
+    escape::{escape, partial_escape},
+    events::Event,
+};
+
+use crate::{
+    XlsxEditor,
+    files_part::{ensure_ct_override, worksheet_element_range, worksheet_insert_pos},
+    find_bytes_from,
+    formula::{Axis, Shift},
+    read_part::parse_cell_ref,
+    sheet_copy::{free_part_path, rels_path, resolve_target, split_path},
+    style::util::col_letter,
+};
+
+const COMMENTS_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/comments";
+const VML_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/vmlDrawing";
+const COMMENTS_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.comments+xml";
+const VML_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.vmlDrawing";
+
+// пустой VML с типом фигуры «примечание»; `{data}` – блок id фигур этого листа
+const VML_HEAD: &str = r#"<xml xmlns:v="urn:schemas-microsoft-com:vml" xmlns:o="urn:schemas-microsoft-com:office:office" xmlns:x="urn:schemas-microsoft-com:office:excel"><o:shapelayout v:ext="edit"><o:idmap v:ext="edit" data="{data}"/></o:shapelayout><v:shapetype id="_x0000_t202" coordsize="21600,21600" o:spt="202" path="m,l,21600r21600,l21600,xe"><v:stroke joinstyle="miter"/><v:path gradientshapeok="t" o:connecttype="rect"/></v:shapetype></xml>"#;
+
+/// A cell note.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// Cell reference, e.g. `"B4"`.
+    pub cell: String,
+    pub author: String,
+    /// Plain text of the note; formatting runs are concatenated.
+    pub text: String,
+}
+
+// фигура-примечание для ячейки (col, row – с нуля)
+fn note_shape(id: u32, col: u32, row: u32) -> String {
+    let top = row.saturating_sub(1);
+    format!(
+        concat!(
+            r##"<v:shape id="_x0000_s{id}" type="#_x0000_t202" style="position:absolute;margin-left:59.25pt;margin-top:1.5pt;width:108pt;height:59.25pt;z-index:1;visibility:hidden" fillcolor="#ffffe1" o:insetmode="auto">"##,
+            r##"<v:fill color2="#ffffe1"/><v:shadow on="t" color="black" obscured="t"/><v:path o:connecttype="none"/>"##,
+            r##"<v:textbox style="mso-direction-alt:auto"><div style="text-align:left"></div></v:textbox>"##,
+            r##"<x:ClientData ObjectType="Note"><x:MoveWithCells/><x:SizeWithCells/>"##,
+            r##"<x:Anchor>{c1}, 15, {top}, 10, {c2}, 15, {r2}, 4</x:Anchor><x:AutoFill>False</x:AutoFill>"##,
+            r##"<x:Row>{row}</x:Row><x:Column>{col}</x:Column></x:ClientData></v:shape>"##
+        ),
+        id = id,
+        c1 = col + 1,
+        top = top,
+        c2 = col + 3,
+        r2 = top + 4,
+        row = row,
+        col = col,
+    )
+}
+
+// границы <tag ...>...</tag> (или пустого), содержащего `needle`
+fn element_around(xml: &[u8], tag: &str, needle: &[u8]) -> Option<(usize, usize)> {
+    let mut i = 0;
+    let open = format!("<{tag} ");
+    let close = format!("</{tag}>");
+    while let Some(off) = memmem::find(&xml[i..], open.as_bytes()) {
+        let s = i + off;
+        let head_end = find_bytes_from(xml, b">", s)?;
+        let e = if xml[head_end - 1] == b'/' {
+            head_end + 1
+        } else {
+            find_bytes_from(xml, close.as_bytes(), head_end)? + close.len()
+        };
+        if memmem::find(&xml[s..e], needle).is_some() {
+            return Some((s, e));
+        }
+        i = e;
+    }
+    None
+}
+
+// (тип, путь части) всех связей из rels листа `sheet_path`
+fn rel_targets(rels: &[u8], sheet_path: &str) -> Result<Vec<(String, String)>> {
+    let (dir, _) = split_path(sheet_path);
+    let mut out = Vec::new();
+    let mut rdr = Reader::from_reader(rels);
+    while let Ok(ev) = rdr.read_event() {
+        match ev {
+            Event::Empty(ref e) | Event::Start(ref e) if e.name().as_ref() == b"Relationship" => {
+                let (mut ty, mut target) = (None, None);
+                for a in e.attributes().with_checks(false).flatten() {
+                    match a.key.as_ref() {
+                        b"Type" => ty = Some(a.unescape_value()?.into_owned()),
+                        b"Target" => target = Some(a.unescape_value()?.into_owned()),
+                        _ => {}
+                    }
+                }
+                if let (Some(ty), Some(t)) = (ty, target) {
+                    out.push((ty, resolve_target(dir, &t)));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Comments and VML parts a sheet's relationships (`rels`) point to.
+pub(crate) fn note_parts(rels: &[u8], sheet_path: &str) -> Result<Vec<String>> {
+    Ok(rel_targets(rels, sheet_path)?
+        .into_iter()
+        .filter(|(ty, _)| ty == COMMENTS_REL_TYPE || ty == VML_REL_TYPE)
+        .map(|(_, p)| p)
+        .collect())
+}
+
+impl XlsxEditor {
+    // путь части, на которую лист ссылается связью `rel_type`
+    fn sheet_rel_part(&self, rel_type: &str) -> Result<Option<String>> {
+        let Some(rels) = self.part_bytes(&rels_path(&self.sheet_path))? else {
+            return Ok(None);
+        };
+        Ok(rel_targets(&rels, &self.sheet_path)?
+            .into_iter()
+            .find(|(ty, _)| ty == rel_type)
+            .map(|(_, p)| p))
+    }
+
+    /// Adds a note to a cell of the current sheet, replacing the note it already has.
+    ///
+    /// Creates the sheet's comments part and its VML drawing on first use.
+    ///
+    /// # Arguments
+    /// * `coord` - The cell (e.g. `"B4"`).
+    /// * `author` - Shown in the note header and in the author list.
+    /// * `text` - The note text; line breaks are kept.
+    pub fn add_comment(&mut self, coord: &str, author: &str, text: &str) -> Result<&mut Self> {
+        let (col, row) = parse_cell_ref(coord)?;
+        let cell = format!("{}{row}", col_letter(col));
+        let mut names = self.part_names()?;
+        let mut ct = self
+            .part_bytes("[Content_Types].xml")?
+            .context("[Content_Types].xml not found")?;
+
+        // --- commentsN.xml ---
+        let comments_path = match self.sheet_rel_part(COMMENTS_REL_TYPE)? {
+            Some(p) => p,
+            None => {
+                let p = free_part_path(&mut names, "xl/comments1.xml");
+                let xml = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<comments xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><authors></authors><commentList></commentList></comments>"#;
+                self.new_files.insert(p.clone(), xml.as_bytes().to_vec());
+                // путь мог остаться от удалённого листа
+                self.removed_files.remove(&p);
+                let (dir, _) = split_path(&self.sheet_path);
+                let target = relative_target(dir, &p);
+                self.add_sheet_relationship(COMMENTS_REL_TYPE, &target, false)?;
+                ensure_ct_override(&mut ct, &p, COMMENTS_CONTENT_TYPE);
+                p
+            }
+        };
+        let mut xml = self
+            .part_bytes(&comments_path)?
+            .with_context(|| format!("{comments_path} not found"))?;
+        let author_id = ensure_author(&mut xml, author)?;
+        let comment = format!(
+            r#"<comment ref="{cell}" authorId="{author_id}"><text><r><t xml:space="preserve">{}</t></r></text></comment>"#,
+            partial_escape(text)
+        );
+        let needle = format!(r#" ref="{cell}""#);
+        if let Some((s, e)) = element_around(&xml, "comment", needle.as_bytes()) {
+            xml.splice(s..e, comment.into_bytes());
+        } else if let Some(pos) = memmem::rfind(&xml, b"</commentList>") {
+            xml.splice(pos..pos, comment.into_bytes());
+        } else if let Some(pos) = memmem::find(&xml, b"<commentList/>") {
+            xml.splice(
+                pos..pos + 14,
+                format!("<commentList>{comment}</commentList>").into_bytes(),
+            );
+        } else {
+            bail!("<commentList> not found in {comments_path}");
+        }
+        self.new_files.insert(comments_path, xml);
+
+        // --- vmlDrawingN.vml: фигура-примечание ---
+        let vml_path = match self.sheet_rel_part(VML_REL_TYPE)? {
+            Some(p) => p,
+            None => {
+                let p = free_part_path(&mut names, "xl/drawings/vmlDrawing1.vml");
+                let n: u32 = p
+                    .trim_start_matches("xl/drawings/vmlDrawing")
+                    .trim_end_matches(".vml")
+                    .parse()
+                    .unwrap_or(1);
+                self.new_files.insert(
+                    p.clone(),
+                    VML_HEAD.replace("{data}", &n.to_string()).into_bytes(),
+                );
+                self.removed_files.remove(&p);
+                let (dir, _) = split_path(&self.sheet_path);
+                let rid =
+                    self.add_sheet_relationship(VML_REL_TYPE, &relative_target(dir, &p), false)?;
+                self.ensure_r_namespace()?;
+                if let Some((s, e)) = worksheet_element_range(&self.sheet_xml, "legacyDrawing") {
+                    self.sheet_xml.drain(s..e);
+                }
+                let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "legacyDrawing") else {
+                    bail!("</worksheet> not found");
+                };
+                self.sheet_xml.splice(
+                    pos..pos,
+                    format!(r#"<legacyDrawing r:id="{rid}"/>"#).into_bytes(),
+                );
+                ensure_ct_default(&mut ct, "vml", VML_CONTENT_TYPE);
+                p
+            }
+        };
+        let mut vml = self
+            .part_bytes(&vml_path)?
+            .with_context(|| format!("{vml_path} not found"))?;
+        if let Some((s, e)) = note_shape_range(&vml, col, row - 1) {
+            vml.drain(s..e);
+        }
+        let shape = note_shape(next_shape_id(&vml), col, row - 1);
+        let pos = memmem::rfind(&vml, b"</xml>")
+            .with_context(|| format!("</xml> not found in {vml_path}"))?;
+        vml.splice(pos..pos, shape.into_bytes());
+        self.new_files.insert(vml_path, vml);
+
+        self.new_files.insert("[Content_Types].xml".to_owned(), ct);
+        Ok(self)
+    }
+
+    /// Moves the notes of the current sheet after rows or columns were inserted or
+    /// deleted; notes of deleted cells are dropped together with their shapes.
+    pub(crate) fn shift_comments(&mut self, shift: Shift) -> Result<()> {
+        for (rel_type, is_vml) in [(COMMENTS_REL_TYPE, false), (VML_REL_TYPE, true)] {
+            let Some(path) = self.sheet_rel_part(rel_type)? else {
+                continue;
+            };
+            let Some(xml) = self.part_bytes(&path)? else {
+                continue;
+            };
+            let shifted = if is_vml {
+                shift_vml(&xml, shift)
+            } else {
+                shift_comments_xml(&xml, shift)
+            };
+            if shifted != xml {
+                self.new_files.insert(path, shifted);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the notes of the current sheet in file order.
+    pub fn comments(&self) -> Result<Vec<Comment>> {
+        let Some(path) = self.sheet_rel_part(COMMENTS_REL_TYPE)? else {
+            return Ok(Vec::new());
+        };
+        let Some(xml) = self.part_bytes(&path)? else {
+            return Ok(Vec::new());
+        };
+        let mut rdr = Reader::from_reader(xml.as_slice());
+        let mut authors = Vec::new();
+        let mut out = Vec::new();
+        let (mut in_author, mut in_t) = (false, false);
+        let mut cur: Option<(Comment, usize)> = None;
+        let mut depth_rph = 0; // фонетика (<rPh>) в текст не входит
+        loop {
+            match rdr.read_event()? {
+                Event::Start(e) => match e.name().as_ref() {
+                    b"author" => {
+                        in_author = true;
+                        authors.push(String::new());
+                    }
+                    b"comment" => {
+                        let (mut cell, mut aid) = (String::new(), 0usize);
+                        for a in e.attributes().with_checks(false).flatten() {
+                            match a.key.as_ref() {
+                                b"ref" => cell = a.unescape_value()?.into_owned(),
+                                b"authorId" => {
+                                    aid = a.unescape_value()?.parse().unwrap_or(0);
+                                }
+                                _ => {}
+                            }
+                        }
+                        let author = authors.get(aid).cloned().unwrap_or_default();
+                        cur = Some((
+                            Comment {
+                                cell,
+                                author,
+                                text: String::new(),
+                            },
+                            aid,
+                        ));
+                    }
+                    b"rPh" => depth_rph += 1,
+                    b"t" => in_t = depth_rph == 0,
+                    _ => {}
+                },
+                Event::Text(t) => {
+                    let s = t.decode()?;
+                    let s = quick_xml::escape::unescape(&s)?;
+                    if in_author && let Some(a) = authors.last_mut() {
+                        a.push_str(&s);
+                    } else if in_t && let Some((c, _)) = cur.as_mut() {
+                        c.text.push_str(&s);
+                    }
+                }
+                Event::GeneralRef(r) => {
+                    // &amp; и т. п. приходят отдельными событиями
+                    let ch = r
+                        .resolve_char_ref()?
+                        .map(String::from)
+                        .or_else(|| {
+                            quick_xml::escape::resolve_predefined_entity(&r.decode().ok()?)
+                                .map(str::to_owned)
+                        })
+                        .unwrap_or_default();
+                    if in_author && let Some(a) = authors.last_mut() {
+                        a.push_str(&ch);
+                    } else if in_t && let Some((c, _)) = cur.as_mut() {
+                        c.text.push_str(&ch);
+                    }
+                }
+                Event::End(e) => match e.name().as_ref() {
+                    b"author" => in_author = false,
+                    b"t" => in_t = false,
+                    b"rPh" => depth_rph -= 1,
+                    b"comment" => out.extend(cur.take().map(|(c, _)| c)),
+                    _ => {}
+                },
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
+}
+
+// новое место ячейки (col с нуля, row с единицы); None – ячейка в удалённой полосе
+pub(crate) fn moved_cell(shift: Shift, col: u32, row: u32) -> Option<(u32, u32)> {
+    let moved = match shift.axis {
+        Axis::Row => shift.one(row).map(|row| (col, row)),
+        Axis::Col => shift.one(col).map(|col| (col, row)),
+    };
+    // при вставке за край лист уже отвергнут shift_sheet_xml, так что None – только удаление
+    if moved.is_none() && !shift.delete {
+        return Some((col, row));
+    }
+    moved
+}
+
+// значение числового элемента `<tag>N</tag>` внутри фигуры
+fn tag_number(xml: &[u8], tag: &str) -> Option<(usize, usize, u32)> {
+    let open = format!("<{tag}>");
+    let s = memmem::find(xml, open.as_bytes())? + open.len();
+    let e = find_bytes_from(xml, b"<", s)?;
+    let v = std::str::from_utf8(&xml[s..e]).ok()?.trim().parse().ok()?;
+    Some((s, e, v))
+}
+
+// фигура примечания ячейки (x:Row/x:Column с нуля), как бы ни были записаны теги внутри
+fn note_shape_range(vml: &[u8], col: u32, row: u32) -> Option<(usize, usize)> {
+    let mut i = 0;
+    while let Some(off) = memmem::find(&vml[i..], b"<v:shape ") {
+        let s = i + off;
+        let e = find_bytes_from(vml, b"</v:shape>", s)? + 10;
+        let shape = &vml[s..e];
+        if tag_number(shape, "x:Row").is_some_and(|(_, _, r)| r == row)
+            && tag_number(shape, "x:Column").is_some_and(|(_, _, c)| c == col)
+        {
+            return Some((s, e));
+        }
+        i = e;
+    }
+    None
+}
+
+// <comment ref="..."> по сдвигу; примечания удалённых ячеек выкидываются
+fn shift_comments_xml(xml: &[u8], shift: Shift) -> Vec<u8> {
+    let mut out = Vec::with_capacity(xml.len());
+    let mut i = 0;
+    while let Some(off) = memmem::find(&xml[i..], b"<comment ") {
+        let s = i + off;
+        let Some(head_end) = find_bytes_from(xml, b">", s) else {
+            break;
+        };
+        let e = if xml[head_end - 1] == b'/' {
+            head_end + 1
+        } else {
+            find_bytes_from(xml, b"</comment>", head_end).map_or(xml.len(), |p| p + 10)
+        };
+        out.extend_from_slice(&xml[i..s]);
+        i = e;
+
+        let head = &xml[s..head_end];
+        let Some(a) = memmem::find(head, br#" ref=""#).map(|a| s + a + 6) else {
+            out.extend_from_slice(&xml[s..e]);
+            continue;
+        };
+        let b = find_bytes_from(xml, b"\"", a).unwrap_or(a);
+        let Ok((col, row)) = parse_cell_ref(&String::from_utf8_lossy(&xml[a..b])) else {
+            out.extend_from_slice(&xml[s..e]);
+            continue;
+        };
+        if let Some((col, row)) = moved_cell(shift, col, row) {
+            out.extend_from_slice(&xml[s..a]);
+            out.extend_from_slice(format!("{}{row}", col_letter(col)).as_bytes());
+            out.extend_from_slice(&xml[b..e]);
+        }
+    }
+    out.extend_from_slice(&xml[i..]);
+    out
+}
+
+// <x:Row>/<x:Column> и <x:Anchor> фигур-примечаний; фигуры удалённых ячеек выкидываются
+fn shift_vml(vml: &[u8], shift: Shift) -> Vec<u8> {
+    let mut out = Vec::with_capacity(vml.len());
+    let mut i = 0;
+    while let Some(off) = memmem::find(&vml[i..], b"<v:shape ") {
+        let s = i + off;
+        let Some(e) = find_bytes_from(vml, b"</v:shape>", s).map(|p| p + 10) else {
+            break;
+        };
+        out.extend_from_slice(&vml[i..s]);
+        i = e;
+
+        let mut shape = vml[s..e].to_vec();
+        let (Some((_, _, row)), Some((_, _, col))) =
+            (tag_number(&shape, "x:Row"), tag_number(&shape, "x:Column"))
+        else {
+            out.extend_from_slice(&shape);
+            continue;
+        };
+        // x:Row и x:Column считаются с нуля
+        let Some((new_col, new_row)) = moved_cell(shift, col, row + 1) else {
+            continue;
+        };
+        let new_row = new_row - 1;
+        let (old, new, anchor_idx) = match shift.axis {
+            Axis::Row => (row, new_row, [2, 6]),
+            Axis::Col => (col, new_col, [0, 4]),
+        };
+        if old != new {
+            // рамка примечания едет вместе с ячейкой
+            if let Some(p) = memmem::find(&shape, b"<x:Anchor>")
+                && let Some(b) = find_bytes_from(&shape, b"<", p + 10)
+            {
+                let a = p + 10;
+                let mut parts: Vec<String> = String::from_utf8_lossy(&shape[a..b])
+                    .split(',')
+                    .map(|p| p.trim().to_owned())
+                    .collect();
+                for k in anchor_idx {
+                    if let Some(n) = parts.get(k).and_then(|p| p.parse::<i64>().ok()) {
+                        parts[k] = (n + new as i64 - old as i64).max(0).to_string();
+                    }
+                }
+                shape.splice(a..b, parts.join(", ").into_bytes());
+            }
+            for (tag, v) in [("x:Row", new_row), ("x:Column", new_col)] {
+                if let Some((a, b, _)) = tag_number(&shape, tag) {
+                    shape.splice(a..b, v.to_string().into_bytes());
+                }
+            }
+        }
+        out.extend_from_slice(&shape);
+    }
+    out.extend_from_slice(&vml[i..]);
+    out
+}
+
+// "xl/worksheets" + "xl/comments1.xml" → "../comments1.xml"
+fn relative_target(base_dir: &str, path: &str) -> String {
+    let base: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    let parts: Vec<&str> = path.split('/').collect();
+    let common = base.iter().zip(&parts).take_while(|(a, b)| a == b).count();
+    let mut out = vec![".."; base.len() - common];
+    out.extend(&parts[common..]);
+    out.join("/")
+}
+
+// индекс автора в <authors>, новый дописывается в конец
+fn ensure_author(xml: &mut Vec<u8>, author: &str) -> Result<usize> {
+    let escaped = escape(author);
+    let mut idx = 0;
+    let mut i = 0;
+    while let Some(off) = memmem::find(&xml[i..], b"<author>") {
+        let s = i + off + 8;
+        let e = find_bytes_from(xml, b"</author>", s).context("</author> not closed")?;
+        if xml[s..e] == *escaped.as_bytes() {
+            return Ok(idx);
+        }
+        idx += 1;
+        i = e;
+    }
+    let tag = format!("<author>{escaped}</author>");
+    if let Some(pos) = memmem::find(xml, b"</authors>") {
+        xml.splice(pos..pos, tag.into_bytes());
+    } else if let Some(pos) = memmem::find(xml, b"<authors/>") {
+        xml.splice(
+            pos..pos + 10,
+            format!("<authors>{tag}</authors>").into_bytes(),
+        );
+    } else {
+        bail!("<authors> not found in comments part");
+    }
+    Ok(idx)
+}
+
+// следующий свободный номер фигуры _x0000_sN
+fn next_shape_id(vml: &[u8]) -> u32 {
+    let mut max = None::<u32>;
+    let mut i = 0;
+    while let Some(off) = memmem::find(&vml[i..], b"_x0000_s") {
+        let s = i + off + 8;
+        let n: String = vml[s..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .map(|&b| b as char)
+            .collect();
+        if let Ok(n) = n.parse::<u32>() {
+            max = Some(max.map_or(n, |m: u32| m.max(n)));
+        }
+        i = s;
+    }
+    match max {
+        Some(m) => m + 1,
+        None => {
+            // первый блок id из <o:idmap data="N">
+            let data = memmem::find(vml, b"data=\"")
+                .and_then(|p| {
+                    let v = &vml[p + 6..];
+                    let q = v.iter().position(|&b| b == b'"' || b == b',')?;
+                    std::str::from_utf8(&v[..q]).ok()?.parse::<u32>().ok()
+                })
+                .unwrap_or(1);
+            data * 1024 + 1
+        }
+    }
+}
+
+// <Default Extension="vml" .../> в [Content_Types].xml, если нет
+fn ensure_ct_default(ct: &mut Vec<u8>, ext: &str, content_type: &str) {
+    let needle = format!(r#"Extension="{ext}""#);
+    if memmem::find(ct, needle.as_bytes()).is_some() {
+        return;
+    }
+    if let Some(pos) = memmem::find(ct, b"<Default").or_else(|| memmem::rfind(ct, b"</Types>")) {
+        let tag = format!(r#"<Default Extension="{ext}" ContentType="{content_type}"/>"#);
+        ct.splice(pos..pos, tag.into_bytes());
+    }
+}
@@ -0,0 +1,347 @@
+//! conditional.rs – условное форматирование: `<conditionalFormatting>` в листе + `<dxf>` в styles.xml
+
+use anyhow::{Result, bail};
+use memchr::memmem;
+use quick_xml::escape::escape;
+use std::{fmt, str::FromStr};
+
+use crate::{
+    XlsxEditor,
+    files_part::worksheet_insert_pos,
+    find_bytes_from,
+    read_part::parse_cell_ref,
+    style::{Color, DxfStyle},
+};
+
+/// Comparison of a `cellIs` rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CfOperator {
+    LessThan,
+    LessThanOrEqual,
+    Equal,
+    NotEqual,
+    GreaterThanOrEqual,
+    GreaterThan,
+    Between,
+    NotBetween,
+}
+impl fmt::Display for CfOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CfOperator::LessThan => "lessThan",
+            CfOperator::LessThanOrEqual => "lessThanOrEqual",
+            CfOperator::Equal => "equal",
+            CfOperator::NotEqual => "notEqual",
+            CfOperator::GreaterThanOrEqual => "greaterThanOrEqual",
+            CfOperator::GreaterThan => "greaterThan",
+            CfOperator::Between => "between",
+            CfOperator::NotBetween => "notBetween",
+        })
+    }
+}
+impl FromStr for CfOperator {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "lessThan" | "<" => CfOperator::LessThan,
+            "lessThanOrEqual" | "<=" => CfOperator::LessThanOrEqual,
+            "equal" | "=" | "==" => CfOperator::Equal,
+            "notEqual" | "<>" | "!=" => CfOperator::NotEqual,
+            "greaterThanOrEqual" | ">=" => CfOperator::GreaterThanOrEqual,
+            "greaterThan" | ">" => CfOperator::GreaterThan,
+            "between" => CfOperator::Between,
+            "notBetween" => CfOperator::NotBetween,
+            _ => bail!("Unknown conditional format operator: {s}"),
+        })
+    }
+}
+
+/// Threshold of a color scale, data bar or icon set.
+///
+/// Parses from `"min"`, `"max"`, `"num:10"`, `"percent:50"`,
+/// `"percentile:90"` or `"formula:$B$1"`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cfvo {
+    Min,
+    Max,
+    Number(f64),
+    Percent(f64),
+    Percentile(f64),
+    Formula(String),
+}
+impl FromStr for Cfvo {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let (kind, val) = s.split_once(':').unwrap_or((s, ""));
+        let num = || -> Result<f64> {
+            val.trim()
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Bad threshold value in `{s}`"))
+        };
+        Ok(match kind {
+            "min" => Cfvo::Min,
+            "max" => Cfvo::Max,
+            "num" => Cfvo::Number(num()?),
+            "percent" => Cfvo::Percent(num()?),
+            "percentile" => Cfvo::Percentile(num()?),
+            "formula" => Cfvo::Formula(val.trim_start_matches('=').to_owned()),
+            _ => bail!("Unknown threshold: {s}"),
+        })
+    }
+}
+impl Cfvo {
+    fn to_xml(&self) -> String {
+        let (kind, val) = match self {
+            Cfvo::Min => return r#"<cfvo type="min"/>"#.to_owned(),
+            Cfvo::Max => return r#"<cfvo type="max"/>"#.to_owned(),
+            Cfvo::Number(v) => ("num", v.to_string()),
+            Cfvo::Percent(v) => ("percent", v.to_string()),
+            Cfvo::Percentile(v) => ("percentile", v.to_string()),
+            Cfvo::Formula(f) => ("formula", f.clone()),
+        };
+        format!(r#"<cfvo type="{kind}" val="{}"/>"#, escape(&val))
+    }
+}
+
+// встроенные наборы значков Excel 2007
+const ICON_SETS: [&str; 17] = [
+    "3Arrows",
+    "3ArrowsGray",
+    "3Flags",
+    "3TrafficLights1",
+    "3TrafficLights2",
+    "3Signs",
+    "3Symbols",
+    "3Symbols2",
+    "4Arrows",
+    "4ArrowsGray",
+    "4RedToBlack",
+    "4Rating",
+    "4TrafficLights",
+    "5Arrows",
+    "5ArrowsGray",
+    "5Rating",
+    "5Quarters",
+];
+
+/// A conditional formatting rule. Formulas are written as in Excel (the
+/// leading `=` is optional) and are relative to the top-left cell of the range.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionalRule {
+    /// Compares the cell value with one formula (two for `Between`/`NotBetween`).
+    CellIs {
+        operator: CfOperator,
+        formula: String,
+        formula2: Option<String>,
+        style: DxfStyle,
+    },
+    /// Applies `style` where the formula is true, e.g. `"$C2<0"`.
+    Expression {
+        formula: String,
+        style: DxfStyle,
+    },
+    /// Two or three color stops, from lowest to highest.
+    ColorScale {
+        stops: Vec<(Cfvo, Color)>,
+    },
+    DataBar {
+        min: Cfvo,
+        max: Cfvo,
+        color: Color,
+    },
+    /// `icons` is an Excel icon set name such as `"3TrafficLights1"`. One threshold
+    /// per icon (the first is usually `percent:0`); empty – evenly spaced percents.
+    IconSet {
+        icons: String,
+        thresholds: Vec<Cfvo>,
+        reverse: bool,
+        show_value: bool,
+    },
+    /// Top (or bottom) `rank` values, or `rank` percent of values.
+    Top {
+        rank: u32,
+        bottom: bool,
+        percent: bool,
+        style: DxfStyle,
+    },
+    Duplicates {
+        style: DxfStyle,
+    },
+    Unique {
+        style: DxfStyle,
+    },
+}
+
+fn formula_xml(f: &str) -> String {
+    format!("<formula>{}</formula>", escape(f.trim_start_matches('=')))
+}
+
+impl XlsxEditor {
+    /// Adds a conditional formatting rule to `range` on the current sheet.
+    ///
+    /// Rules added later get a lower priority than the existing ones.
+    ///
+    /// # Arguments
+    /// * `range` - Cells to format; several ranges are separated by spaces (`"A1:A10 C1:C10"`).
+    /// * `rule` - The condition and the style to apply.
+    pub fn add_conditional_format(
+        &mut self,
+        range: &str,
+        rule: &ConditionalRule,
+    ) -> Result<&mut Self> {
+        let sqref = range.split_whitespace().collect::<Vec<_>>().join(" ");
+        if sqref.is_empty() {
+            bail!("conditional format range is empty");
+        }
+        for part in sqref.split(' ') {
+            let (a, b) = part.split_once(':').unwrap_or((part, part));
+            parse_cell_ref(a)?;
+            parse_cell_ref(b)?;
+        }
+
+        let priority = self.next_cf_priority();
+        let dxf = |this: &mut Self, style: &DxfStyle| -> Result<u32> {
+            if style.is_empty() {
+                bail!("conditional format style is empty");
+            }
+            this.ensure_dxf(style)
+        };
+        let cf_rule = match rule {
+            ConditionalRule::CellIs {
+                operator,
+                formula,
+                formula2,
+                style,
+            } => {
+                let two = matches!(operator, CfOperator::Between | CfOperator::NotBetween);
+                let mut body = formula_xml(formula);
+                match (two, formula2) {
+                    (true, Some(f2)) => body.push_str(&formula_xml(f2)),
+                    (true, None) => bail!("`{operator}` needs a second formula"),
+                    _ => {}
+                }
+                format!(
+                    r#"<cfRule type="cellIs" dxfId="{}" priority="{priority}" operator="{operator}">{body}</cfRule>"#,
+                    dxf(self, style)?
+                )
+            }
+            ConditionalRule::Expression { formula, style } => format!(
+                r#"<cfRule type="expression" dxfId="{}" priority="{priority}">{}</cfRule>"#,
+                dxf(self, style)?,
+                formula_xml(formula)
+            ),
+            ConditionalRule::ColorScale { stops } => {
+                if !(2..=3).contains(&stops.len()) {
+                    bail!("color scale needs 2 or 3 stops, got {}", stops.len());
+                }
+                let cfvos: String = stops.iter().map(|(v, _)| v.to_xml()).collect();
+                let colors: String = stops.iter().map(|(_, c)| c.to_xml("color")).collect();
+                format!(
+                    r#"<cfRule type="colorScale" priority="{priority}"><colorScale>{cfvos}{colors}</colorScale></cfRule>"#
+                )
+            }
+            ConditionalRule::DataBar { min, max, color } => format!(
+                r#"<cfRule type="dataBar" priority="{priority}"><dataBar>{}{}{}</dataBar></cfRule>"#,
+                min.to_xml(),
+                max.to_xml(),
+                color.to_xml("color")
+            ),
+            ConditionalRule::IconSet {
+                icons,
+                thresholds,
+                reverse,
+                show_value,
+            } => {
+                if !ICON_SETS.contains(&icons.as_str()) {
+                    bail!("Unknown icon set: {icons}");
+                }
+                // число значков – первая цифра имени
+                let n = (icons.as_bytes()[0] - b'0') as usize;
+                let cfvos: String = if thresholds.is_empty() {
+                    (0..n)
+                        .map(|k| Cfvo::Percent(((100 * k + n / 2) / n) as f64).to_xml())
+                        .collect()
+                } else if thresholds.len() == n {
+                    thresholds.iter().map(Cfvo::to_xml).collect()
+                } else {
+                    bail!(
+                        "icon set `{icons}` needs {n} thresholds, got {}",
+                        thresholds.len()
+                    );
+                };
+                let mut head = format!(r#"<iconSet iconSet="{icons}""#);
+                if !show_value {
+                    head.push_str(r#" showValue="0""#);
+                }
+                if *reverse {
+                    head.push_str(r#" reverse="1""#);
+                }
+                format!(
+                    r#"<cfRule type="iconSet" priority="{priority}">{head}>{cfvos}</iconSet></cfRule>"#
+                )
+            }
+            ConditionalRule::Top {
+                rank,
+                bottom,
+                percent,
+                style,
+            } => {
+                if *rank == 0 || (*percent && *rank > 100) {
+                    bail!("top rule rank {rank} is out of range");
+                }
+                let mut flags = String::new();
+                if *percent {
+                    flags.push_str(r#" percent="1""#);
+                }
+                if *bottom {
+                    flags.push_str(r#" bottom="1""#);
+                }
+                format!(
+                    r#"<cfRule type="top10" dxfId="{}" priority="{priority}"{flags} rank="{rank}"/>"#,
+                    dxf(self, style)?
+                )
+            }
+            ConditionalRule::Duplicates { style } => format!(
+                r#"<cfRule type="duplicateValues" dxfId="{}" priority="{priority}"/>"#,
+                dxf(self, style)?
+            ),
+            ConditionalRule::Unique { style } => format!(
+                r#"<cfRule type="uniqueValues" dxfId="{}" priority="{priority}"/>"#,
+                dxf(self, style)?
+            ),
+        };
+
+        // новый блок встаёт после уже существующих <conditionalFormatting>
+        let block =
+            format!(r#"<conditionalFormatting sqref="{sqref}">{cf_rule}</conditionalFormatting>"#);
+        let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "conditionalFormatting") else {
+            bail!("</worksheet> not found");
+        };
+        self.sheet_xml.splice(pos..pos, block.into_bytes());
+        Ok(self)
+    }
+
+    // priority уникален в пределах листа: max + 1
+    fn next_cf_priority(&self) -> u32 {
+        let mut max = 0u32;
+        let mut i = 0;
+        while let Some(off) = memmem::find(&self.sheet_xml[i..], b"<cfRule ") {
+            let start = i + off;
+            let end = find_bytes_from(&self.sheet_xml, b">", start).unwrap_or(self.sheet_xml.len());
+            let tag = &self.sheet_xml[start..end];
+            if let Some(a) = memmem::find(tag, b" priority=\"") {
+                let v = &tag[a + 11..];
+                if let Some(q) = v.iter().position(|&b| b == b'"') {
+                    max = max.max(
+                        std::str::from_utf8(&v[..q])
+                            .ok()
+                            .and_then(|s| s.parse().ok())
+                            .unwrap_or(0),
+                    );
+                }
+            }
+            i = end;
+        }
+        max + 1
+    }
+}
@@ -1,16 +1,38 @@
 /// files_part.rs
-use crate::{XlsxEditor, find_bytes_from, scan};
+use crate::{
+    XlsxEditor,
+    comments::note_parts,
+    find_bytes_from,
+    formula::RefTransform,
+    scan_reader,
+    shared_strings::{SST_CONTENT_TYPE, StringStorage},
+    sheet_copy::rels_path,
+};
 use ::zip as zip_crate;
 use anyhow::{Context, Result, bail};
 use memchr::memmem;
-use quick_xml::{Reader, events::Event};
+use quick_xml::{Reader, escape::escape, events::Event};
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
-    io::{Read, Write},
-    path::Path,
+    io::{Cursor, Read, Seek, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
 }; // ← понадобится для dimension
 
+/// Исходный архив книги: при сохранении из него копируются неизменённые части.
+pub(crate) enum Source {
+    /// Файл на диске.
+    Path(PathBuf),
+    /// Архив целиком в памяти (`open_from_bytes` / `open_from_reader`).
+    Bytes(Arc<[u8]>),
+    /// Книга создана в памяти (`new_workbook`): все части лежат в редакторе.
+    Empty,
+}
+
+pub(crate) trait ReadSeek: Read + Seek {}
+impl<T: Read + Seek> ReadSeek for T {}
+
 pub(crate) fn needs_xml_space_preserve(s: &str) -> bool {
     let b = s.as_bytes();
     if b.is_empty() {
@@ -43,10 +65,10 @@ fn update_dimension_bytes(xml: &mut Vec<u8>) {
             if cidx > 0 {
                 max_c = max_c.max(cidx - 1);
             }
-            if let Ok(rs) = std::str::from_utf8(&coord[p..]) {
-                if let Ok(rn) = rs.parse::<u32>() {
-                    max_r = max_r.max(rn);
-                }
+            if let Ok(rs) = std::str::from_utf8(&coord[p..])
+                && let Ok(rn) = rs.parse::<u32>()
+            {
+                max_r = max_r.max(rn);
             }
         }
         i = v1 + 1;
@@ -77,48 +99,328 @@ fn update_dimension_bytes(xml: &mut Vec<u8>) {
 }
 // добавляет Override в [Content_Types].xml, если нет
 fn ensure_ct_override_for_sheets(ct_xml: &mut Vec<u8>, sheet_paths: &[String]) {
-    // ищем </Types>
+    for p in sheet_paths {
+        if !p.starts_with("xl/worksheets/") || !p.ends_with(".xml") {
+            continue;
+        }
+        ensure_ct_override(
+            ct_xml,
+            p,
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml",
+        );
+    }
+}
+
+// Override для произвольной части (путь без ведущего '/'), если его ещё нет
+pub(crate) fn ensure_ct_override(ct_xml: &mut Vec<u8>, part_path: &str, content_type: &str) {
     let Some(types_end) = memmem::rfind(ct_xml, b"</Types>") else {
         return;
     };
+    let part = format!("/{}", part_path);
+    let needle = format!(r#"PartName="{part}""#);
+    if memmem::find(ct_xml, needle.as_bytes()).is_some() {
+        return; // уже есть
+    }
+    let override_tag = format!(r#"<Override PartName="{part}" ContentType="{content_type}"/>"#);
+    ct_xml.splice(types_end..types_end, override_tag.bytes());
+}
 
-    for p in sheet_paths {
-        if !p.starts_with("xl/worksheets/") || !p.ends_with(".xml") {
-            continue;
+// Target из workbook.xml.rels → абсолютный путь внутри архива
+pub(crate) fn workbook_target_to_path(target: &str) -> String {
+    if let Some(abs) = target.strip_prefix('/') {
+        abs.to_owned()
+    } else if target.starts_with("xl/") {
+        target.to_owned()
+    } else {
+        format!("xl/{}", target)
+    }
+}
+
+// путь части книги по типу связи (sharedStrings, styles, ...)
+pub(crate) fn workbook_part_path(rels_xml: &[u8], rel_type: &str) -> Option<String> {
+    let mut rdr = Reader::from_reader(rels_xml);
+    rdr.config_mut().trim_text(true);
+    while let Ok(ev) = rdr.read_event() {
+        match ev {
+            Event::Empty(ref e) | Event::Start(ref e) if e.name().as_ref() == b"Relationship" => {
+                let mut ty = None;
+                let mut target = None;
+                for a in e.attributes().with_checks(false).flatten() {
+                    match a.key.as_ref() {
+                        b"Type" => ty = Some(String::from_utf8_lossy(&a.value).into_owned()),
+                        b"Target" => target = Some(String::from_utf8_lossy(&a.value).into_owned()),
+                        _ => {}
+                    }
+                }
+                if ty.as_deref() == Some(rel_type)
+                    && let Some(t) = target
+                {
+                    return Some(workbook_target_to_path(&t));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
         }
-        let part = format!("/{}", p);
-        let needle = format!(r#"PartName="{part}""#);
-        if memmem::find(&ct_xml, needle.as_bytes()).is_some() {
-            continue; // уже есть
+    }
+    None
+}
+
+// путь листа по имени: workbook.xml → r:id → Target в workbook.xml.rels
+pub(crate) fn sheet_part_path(wb_xml: &[u8], rels_xml: &[u8], sheet_name: &str) -> Option<String> {
+    let rid = get_sheet_rid_by_name(wb_xml, sheet_name)?;
+    let (_, _, target) = find_relationship_by_id(rels_xml, &rid)?;
+    Some(workbook_target_to_path(&target))
+}
+
+// индексы листов в workbook.xml (позиции в <sheets>): localSheetId у <definedName>,
+// activeTab/firstSheet у <workbookView>; f: старый индекс → новый
+pub(crate) fn remap_sheet_indices(wb_xml: &mut Vec<u8>, f: impl Fn(usize) -> usize) {
+    for key in [
+        &b" localSheetId=\""[..],
+        &b" activeTab=\""[..],
+        &b" firstSheet=\""[..],
+    ] {
+        let mut i = 0;
+        while let Some(off) = memmem::find(&wb_xml[i..], key) {
+            let v0 = i + off + key.len();
+            let Some(v1) = find_bytes_from(wb_xml, b"\"", v0) else {
+                break;
+            };
+            i = v1;
+            let Some(idx) = std::str::from_utf8(&wb_xml[v0..v1])
+                .ok()
+                .and_then(|v| v.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let new = f(idx).to_string();
+            i = v0 + new.len();
+            wb_xml.splice(v0..v1, new.into_bytes());
         }
-        let override_tag = format!(
-            r#"<Override PartName="{part}" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#
-        );
-        ct_xml.splice(types_end..types_end, override_tag.bytes());
     }
 }
 
+// убирает <definedName localSheetId="idx"> (имена удалённого листа); пустой <definedNames> – тоже
+fn drop_local_names(wb_xml: &mut Vec<u8>, idx: usize) {
+    let key = format!(r#" localSheetId="{idx}""#);
+    let mut i = 0;
+    while let Some(off) = memmem::find(&wb_xml[i..], b"<definedName ") {
+        let start = i + off;
+        let Some(head_end) = find_bytes_from(wb_xml, b">", start) else {
+            return;
+        };
+        let end = if wb_xml[head_end - 1] == b'/' {
+            head_end + 1
+        } else {
+            match find_bytes_from(wb_xml, b"</definedName>", head_end) {
+                Some(p) => p + "</definedName>".len(),
+                None => return,
+            }
+        };
+        if memmem::find(&wb_xml[start..head_end], key.as_bytes()).is_some() {
+            wb_xml.drain(start..end);
+            i = start;
+        } else {
+            i = end;
+        }
+    }
+    if memmem::find(wb_xml, b"<definedName ").is_none()
+        && let Some(start) = memmem::find(wb_xml, b"<definedNames")
+    {
+        let end = memmem::find(&wb_xml[start..], b"</definedNames>")
+            .map(|p| start + p + "</definedNames>".len())
+            .or_else(|| find_bytes_from(wb_xml, b"/>", start).map(|p| p + 2));
+        if let Some(end) = end {
+            wb_xml.drain(start..end);
+        }
+    }
+}
+
+// порядок дочерних элементов <worksheet> по схеме (CT_Worksheet)
+const WORKSHEET_ORDER: [&str; 39] = [
+    "sheetPr",
+    "dimension",
+    "sheetViews",
+    "sheetFormatPr",
+    "cols",
+    "sheetData",
+    "sheetCalcPr",
+    "sheetProtection",
+    "protectedRanges",
+    "scenarios",
+    "autoFilter",
+    "sortState",
+    "dataConsolidate",
+    "customSheetViews",
+    "mergeCells",
+    "phoneticPr",
+    "conditionalFormatting",
+    "dataValidations",
+    "hyperlinks",
+    "printOptions",
+    "pageMargins",
+    "pageSetup",
+    "headerFooter",
+    "rowBreaks",
+    "colBreaks",
+    "customProperties",
+    "cellWatches",
+    "ignoredErrors",
+    "smartTags",
+    "drawing",
+    "legacyDrawing",
+    "legacyDrawingHF",
+    "drawingHF",
+    "picture",
+    "oleObjects",
+    "controls",
+    "webPublishItems",
+    "tableParts",
+    "extLst",
+];
+
+// начало первого элемента `<name>` после `from` (не путая `<col` с `<cols`)
+fn find_element(xml: &[u8], name: &str, from: usize) -> Option<usize> {
+    let open = format!("<{name}");
+    let mut i = from;
+    while let Some(off) = memmem::find(&xml[i..], open.as_bytes()) {
+        let p = i + off;
+        if matches!(xml.get(p + open.len()), Some(b' ' | b'>' | b'/')) {
+            return Some(p);
+        }
+        i = p + open.len();
+    }
+    None
+}
+
+/// Позиция для вставки `<tag>` в лист: перед первым элементом, который по схеме идёт
+/// после него, иначе перед `</worksheet>`.
+pub(crate) fn worksheet_insert_pos(xml: &[u8], tag: &str) -> Option<usize> {
+    let idx = WORKSHEET_ORDER.iter().position(|t| *t == tag)?;
+    // после sheetData ищем только за ней – внутри ячеек таких тегов нет, но так дешевле
+    let from = memmem::find(xml, b"</sheetData>")
+        .or_else(|| memmem::find(xml, b"<sheetData/>"))
+        .filter(|_| idx > 5)
+        .unwrap_or(0);
+    WORKSHEET_ORDER[idx + 1..]
+        .iter()
+        .find_map(|t| find_element(xml, t, from))
+        .or_else(|| memmem::rfind(xml, b"</worksheet>"))
+}
+
+/// Границы элемента `<tag>` верхнего уровня листа (с содержимым или пустого).
+pub(crate) fn worksheet_element_range(xml: &[u8], tag: &str) -> Option<(usize, usize)> {
+    let start = find_element(xml, tag, 0)?;
+    let head_end = find_bytes_from(xml, b">", start)?;
+    if xml[head_end - 1] == b'/' {
+        return Some((start, head_end + 1));
+    }
+    let close = format!("</{tag}>");
+    let end = find_bytes_from(xml, close.as_bytes(), head_end)? + close.len();
+    Some((start, end))
+}
+
+// максимальный числовой rIdN в .rels + 1
+pub(crate) fn next_rel_id(rels_xml: &[u8]) -> u32 {
+    let mut max_rid = 0u32;
+    let mut rdr = Reader::from_reader(rels_xml);
+    rdr.config_mut().trim_text(true);
+    while let Ok(ev) = rdr.read_event() {
+        match ev {
+            Event::Empty(ref e) | Event::Start(ref e) if e.name().as_ref() == b"Relationship" => {
+                if let Some(id) = e.attributes().with_checks(false).flatten().find_map(|a| {
+                    (a.key.as_ref() == b"Id")
+                        .then(|| String::from_utf8_lossy(&a.value).into_owned())
+                }) && let Some(num) = id.strip_prefix("rId")
+                {
+                    max_rid = max_rid.max(num.parse::<u32>().unwrap_or(0));
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    max_rid + 1
+}
+
+// ── части пустой книги для `new_workbook` ─────────────────────────
+const EMPTY_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const EMPTY_ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const EMPTY_WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+const EMPTY_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><dimension ref="A1"/><sheetData></sheetData></worksheet>"#;
+
+const EMPTY_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="1"><font><sz val="11"/><color theme="1"/><name val="Calibri"/><family val="2"/></font></fonts><fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#;
+
+/// Excel's sheet name rules: 1–31 characters, none of `: \ / ? * [ ]`.
+pub(crate) fn validate_sheet_name(name: &str) -> Result<()> {
+    if name.is_empty() {
+        bail!("Sheet name cannot be empty");
+    }
+    if name.chars().count() > 31 {
+        bail!("Sheet name `{name}` is longer than 31 characters");
+    }
+    if name
+        .chars()
+        .any(|c| matches!(c, ':' | '\\' | '/' | '?' | '*' | '[' | ']'))
+    {
+        bail!("Sheet name `{name}` contains forbidden characters (: \\ / ? * [ ])");
+    }
+    Ok(())
+}
+
 /// Work with files
 impl XlsxEditor {
     /// Открывает книгу и подготавливает лист `sheet_id` (1‑based).
     pub fn open_sheet<P: AsRef<Path>>(src: P, sheet_id: usize) -> Result<Self> {
         let src_path = src.as_ref().to_path_buf();
-        let mut zip = zip_crate::ZipArchive::new(File::open(&src_path)?)?;
+        let zip = zip_crate::ZipArchive::new(File::open(&src_path)?)?;
+        Self::from_archive(zip, Source::Path(src_path), sheet_id)
+    }
 
-        // ── sheet#.xml ───────────────────────────────────────────────
-        let sheet_path = format!("xl/worksheets/sheet{sheet_id}.xml");
+    /// Same as [`XlsxEditor::open`], but reads the workbook from memory.
+    ///
+    /// The archive is kept in memory for the editor's lifetime; unchanged parts are
+    /// copied from it on save.
+    ///
+    /// # Arguments
+    /// * `bytes` - Contents of an XLSX file.
+    /// * `sheet_name` - The name of the sheet to open (e.g., "Sheet1").
+    ///
+    /// # Returns
+    /// A `Result` containing an `XlsxEditor` instance if successful, or an `anyhow::Error` otherwise.
+    pub fn open_from_bytes(bytes: impl Into<Vec<u8>>, sheet_name: &str) -> Result<Self> {
+        let bytes: Arc<[u8]> = bytes.into().into();
+        let sheet_names = scan_reader(Cursor::new(&bytes[..]))?;
+        let sheet_id = sheet_names
+            .iter()
+            .position(|n| n == sheet_name)
+            .context(format!("Sheet '{}' not found", sheet_name))?
+            + 1;
+        let zip = zip_crate::ZipArchive::new(Cursor::new(bytes.clone()))?;
+        Self::from_archive(zip, Source::Bytes(bytes), sheet_id)
+    }
 
-        // читаем XML листа в отдельном блоке, чтобы `sheet` дропнулся,
-        // и эксклюзивный займ `zip` освободился
-        let sheet_xml: Vec<u8> = {
-            let mut sheet = zip
-                .by_name(&sheet_path)
-                .with_context(|| format!("{sheet_path} not found"))?;
-            let mut buf = Vec::with_capacity(sheet.size() as usize);
-            sheet.read_to_end(&mut buf)?;
-            buf
-        };
+    /// Reads the whole workbook from `reader` and opens `sheet_name`,
+    /// see [`XlsxEditor::open_from_bytes`].
+    pub fn open_from_reader<R: Read>(mut reader: R, sheet_name: &str) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        Self::open_from_bytes(bytes, sheet_name)
+    }
 
+    fn from_archive<R: Read + Seek>(
+        mut zip: zip_crate::ZipArchive<R>,
+        source: Source,
+        sheet_id: usize,
+    ) -> Result<Self> {
         // ── styles.xml ───────────────────────────────────────────────
         let styles_xml: Vec<u8> = {
             let mut styles = zip
@@ -149,6 +451,29 @@ impl XlsxEditor {
             buf
         };
 
+        // ── sheet#.xml ───────────────────────────────────────────────
+        // sheet_id – позиция в <sheets>; файл листа берём из его связи,
+        // после копирования/перестановки листов номера файлов не совпадают с порядком
+        let sheet_path = parse_sheets_inner(&workbook_xml)
+            .ok()
+            .and_then(|(_, _, tags)| {
+                let rid = &tags.get(sheet_id.checked_sub(1)?)?.rid;
+                let (_, _, target) = find_relationship_by_id(&rels_xml, rid)?;
+                Some(workbook_target_to_path(&target))
+            })
+            .unwrap_or_else(|| format!("xl/worksheets/sheet{sheet_id}.xml"));
+
+        // читаем XML листа в отдельном блоке, чтобы `sheet` дропнулся,
+        // и эксклюзивный займ `zip` освободился
+        let sheet_xml: Vec<u8> = {
+            let mut sheet = zip
+                .by_name(&sheet_path)
+                .with_context(|| format!("{sheet_path} not found"))?;
+            let mut buf = Vec::with_capacity(sheet.size() as usize);
+            sheet.read_to_end(&mut buf)?;
+            buf
+        };
+
         // ── вычисляем last_row ───────────────────────────────────────
         let mut reader = Reader::from_reader(sheet_xml.as_slice());
         // check_utf8(&mut reader)?;
@@ -171,7 +496,7 @@ impl XlsxEditor {
         }
 
         Ok(Self {
-            src_path,
+            source,
             sheet_path,
             sheet_xml,
             last_row,
@@ -182,6 +507,53 @@ impl XlsxEditor {
             styles_index: None,
             loaded_files: std::collections::HashMap::new(), // ← добавлено
             removed_files: HashSet::new(),                  // ← НОВОЕ
+            shared_strings: OnceLock::new(),
+            string_storage: StringStorage::default(),
+        })
+    }
+
+    /// Creates an empty workbook with a single sheet, entirely in memory.
+    ///
+    /// No source file is involved: every part lives in the editor and [`XlsxEditor::save`]
+    /// writes the complete package.
+    ///
+    /// # Arguments
+    /// * `sheet_name` - Name of the first sheet (1–31 characters, none of `: \ / ? * [ ]`).
+    ///
+    /// # Returns
+    /// A `Result` containing an `XlsxEditor` positioned on that sheet.
+    pub fn new_workbook(sheet_name: &str) -> Result<Self> {
+        validate_sheet_name(sheet_name)?;
+        let workbook_xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            quick_xml::escape::escape(sheet_name)
+        );
+
+        let mut new_files = HashMap::new();
+        new_files.insert(
+            "[Content_Types].xml".to_owned(),
+            EMPTY_CONTENT_TYPES.as_bytes().to_vec(),
+        );
+        new_files.insert(
+            "_rels/.rels".to_owned(),
+            EMPTY_ROOT_RELS.as_bytes().to_vec(),
+        );
+
+        Ok(Self {
+            source: Source::Empty,
+            sheet_path: "xl/worksheets/sheet1.xml".to_owned(),
+            sheet_xml: EMPTY_SHEET.as_bytes().to_vec(),
+            last_row: 0,
+            styles_xml: EMPTY_STYLES.as_bytes().to_vec(),
+            workbook_xml: workbook_xml.into_bytes(),
+            rels_xml: EMPTY_WORKBOOK_RELS.as_bytes().to_vec(),
+            new_files,
+            styles_index: None,
+            loaded_files: HashMap::new(),
+            removed_files: HashSet::new(),
+            shared_strings: OnceLock::new(),
+            string_storage: StringStorage::default(),
         })
     }
 
@@ -198,11 +570,25 @@ impl XlsxEditor {
         // }
     }
 
+    /// Writes the workbook to `dst`.
     pub fn save<P: AsRef<Path>>(&mut self, dst: P) -> Result<()> {
+        self.save_to_writer(File::create(dst)?)
+    }
+
+    /// Writes the workbook into memory and returns the XLSX bytes.
+    pub fn to_bytes(&mut self) -> Result<Vec<u8>> {
+        let mut buf = Cursor::new(Vec::new());
+        self.save_to_writer(&mut buf)?;
+        Ok(buf.into_inner())
+    }
+
+    /// Writes the workbook to any seekable writer (a file, a `Cursor<Vec<u8>>`, …).
+    pub fn save_to_writer<W: Write + Seek>(&mut self, writer: W) -> Result<()> {
         self.flush_current_sheet();
+        self.flush_shared_strings()?;
 
-        let mut zin = zip_crate::ZipArchive::new(File::open(&self.src_path)?)?;
-        let mut zout = zip_crate::ZipWriter::new(File::create(dst)?);
+        let mut zin = self.source_archive()?;
+        let mut zout = zip_crate::ZipWriter::new(writer);
 
         let deflated: zip_crate::write::FileOptions<'_, ()> =
             zip_crate::write::FileOptions::default()
@@ -217,16 +603,9 @@ impl XlsxEditor {
         let mut written: HashSet<String> = HashSet::new();
 
         // 0) прочитаем [Content_Types].xml в буфер и допишем Override для новых листов
-        let mut ct_xml_opt: Option<Vec<u8>> = {
-            let mut z = zip_crate::ZipArchive::new(File::open(&self.src_path)?)?;
-            if let Ok(mut f) = z.by_name("[Content_Types].xml") {
-                let mut buf = Vec::with_capacity(f.size() as usize);
-                use std::io::Read;
-                f.read_to_end(&mut buf).ok();
-                Some(buf)
-            } else {
-                None
-            }
+        let mut ct_xml_opt: Option<Vec<u8>> = match self.new_files.get("[Content_Types].xml") {
+            Some(ct) => Some(ct.clone()),
+            None => self.read_source_part("[Content_Types].xml")?,
         };
         if let Some(ct) = ct_xml_opt.as_mut() {
             // собрать список новых листов
@@ -240,23 +619,31 @@ impl XlsxEditor {
             new_sheet_paths.sort();
             new_sheet_paths.dedup();
             ensure_ct_override_for_sheets(ct, &new_sheet_paths);
-            // НОВОЕ: убрать overrides для удалённых листов
+            if let Some(sst) = self.shared_strings.get()
+                && sst.is_dirty()
+            {
+                ensure_ct_override(ct, &sst.path, SST_CONTENT_TYPE);
+            }
+            // НОВОЕ: убрать overrides для удалённых листов и их частей
             for p in &self.removed_files {
-                if p.starts_with("xl/worksheets/") && p.ends_with(".xml") {
-                    remove_ct_override_for_path(ct, p);
-                }
+                remove_ct_override_for_path(ct, p);
             }
         }
 
-        for i in 0..zin.len() {
+        let entries = zin.as_ref().map_or(0, |z| z.len());
+        for i in 0..entries {
+            let zin = zin.as_mut().expect("source archive");
             let name = { zin.by_index_raw(i)?.name().to_string() };
             // НОВОЕ: пропускаем удалённые файлы
             if self.removed_files.contains(&name) {
                 continue;
             }
+            written.insert(name.clone());
 
-            // Новая версия файла?
-            if let Some(content) = self.new_files.get(&name) {
+            // Новая версия файла? ([Content_Types].xml уже пропатчен в ct_xml_opt)
+            if name != "[Content_Types].xml"
+                && let Some(content) = self.new_files.get(&name)
+            {
                 let mut out = content.clone();
 
                 // фиксим dimension для листов
@@ -271,7 +658,6 @@ impl XlsxEditor {
                 };
                 zout.start_file(&name, opt)?;
                 zout.write_all(&out)?;
-                written.insert(name);
                 continue;
             }
 
@@ -344,6 +730,29 @@ impl XlsxEditor {
             }
         }
 
+        // части, которых не было в исходном архиве (книга создана в памяти)
+        let mut styles = self.styles_xml.clone();
+        normalize_styles_root(&mut styles);
+        let own_parts = [
+            ("[Content_Types].xml", ct_xml_opt.take()),
+            ("xl/workbook.xml", Some(self.workbook_xml.clone())),
+            ("xl/_rels/workbook.xml.rels", Some(self.rels_xml.clone())),
+            ("xl/styles.xml", Some(styles)),
+        ];
+        for (name, content) in own_parts {
+            let Some(content) = content else { continue };
+            if !written.insert(name.to_owned()) {
+                continue;
+            }
+            let opt = if should_store_uncompressed(name, content.len()) {
+                stored
+            } else {
+                deflated
+            };
+            zout.start_file(name, opt)?;
+            zout.write_all(&content)?;
+        }
+
         for (path, content) in &self.new_files {
             if self.removed_files.contains(path) {
                 continue; // НОВОЕ: не писать удалённые
@@ -368,6 +777,160 @@ impl XlsxEditor {
     }
 }
 
+impl XlsxEditor {
+    /// `<workbookPr date1904="1">` – даты считаются от 1904-01-01.
+    pub(crate) fn date1904(&self) -> bool {
+        let Some(pr) = memmem::find(&self.workbook_xml, b"<workbookPr") else {
+            return false;
+        };
+        let end = find_bytes_from(&self.workbook_xml, b">", pr).unwrap_or(self.workbook_xml.len());
+        let tag = &self.workbook_xml[pr..end];
+        memmem::find(tag, br#"date1904="1""#).is_some()
+            || memmem::find(tag, br#"date1904="true""#).is_some()
+    }
+
+    /// Открывает исходный архив; `None` для книги, созданной в памяти.
+    pub(crate) fn source_archive(
+        &self,
+    ) -> Result<Option<zip_crate::ZipArchive<Box<dyn ReadSeek>>>> {
+        let reader: Box<dyn ReadSeek> = match &self.source {
+            Source::Path(path) => Box::new(File::open(path)?),
+            Source::Bytes(bytes) => Box::new(Cursor::new(bytes.clone())),
+            Source::Empty => return Ok(None),
+        };
+        Ok(Some(zip_crate::ZipArchive::new(reader)?))
+    }
+
+    /// Имя текущего листа: ищем `<sheet>`, чей Target совпадает с `sheet_path`.
+    pub(crate) fn current_sheet_name(&self) -> Option<String> {
+        let (_, _, tags) = parse_sheets_inner(&self.workbook_xml).ok()?;
+        tags.into_iter()
+            .find(|t| {
+                find_relationship_by_id(&self.rels_xml, &t.rid).is_some_and(|(_, _, target)| {
+                    workbook_target_to_path(&target) == self.sheet_path
+                })
+            })
+            .map(|t| t.name)
+    }
+
+    /// Имена листов в порядке `<sheets>` текущего `workbook.xml`.
+    pub fn sheet_names(&self) -> Result<Vec<String>> {
+        let (_, _, tags) = parse_sheets_inner(&self.workbook_xml)?;
+        Ok(tags.into_iter().map(|t| t.name).collect())
+    }
+
+    /// Пары (имя листа, путь части) в порядке `<sheets>`.
+    pub(crate) fn sheet_parts(&self) -> Result<Vec<(String, String)>> {
+        let (_, _, tags) = parse_sheets_inner(&self.workbook_xml)?;
+        Ok(tags
+            .into_iter()
+            .filter_map(|t| {
+                let (_, _, target) = find_relationship_by_id(&self.rels_xml, &t.rid)?;
+                Some((t.name, workbook_target_to_path(&target)))
+            })
+            .collect())
+    }
+
+    /// Актуальный XML листа: текущий → new_files → loaded_files → zip.
+    pub(crate) fn sheet_part_xml(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        if path == self.sheet_path {
+            return Ok(Some(self.sheet_xml.clone()));
+        }
+        if let Some(buf) = self.new_files.get(path) {
+            return Ok(Some(buf.clone()));
+        }
+        self.read_source_part(path)
+    }
+
+    /// Читает часть исходного архива; `None`, если такой части нет.
+    pub(crate) fn read_source_part(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        if let Some(buf) = self.loaded_files.get(path) {
+            return Ok(Some(buf.clone()));
+        }
+        let Some(mut zin) = self.source_archive()? else {
+            return Ok(None);
+        };
+        let Ok(mut f) = zin.by_name(path) else {
+            return Ok(None);
+        };
+        let mut buf = Vec::with_capacity(f.size() as usize);
+        f.read_to_end(&mut buf)?;
+        Ok(Some(buf))
+    }
+
+    /// Добавляет Relationship в workbook.xml.rels и возвращает его Id.
+    pub(crate) fn add_workbook_relationship(
+        &mut self,
+        rel_type: &str,
+        target: &str,
+    ) -> Result<String> {
+        let rid = format!("rId{}", next_rel_id(&self.rels_xml));
+        let rel_tag = format!(r#"<Relationship Id="{rid}" Type="{rel_type}" Target="{target}"/>"#);
+        let pos = memmem::rfind(&self.rels_xml, b"</Relationships")
+            .context("</Relationships> not found in workbook.xml.rels")?;
+        self.rels_xml.splice(pos..pos, rel_tag.bytes());
+        Ok(rid)
+    }
+
+    /// Добавляет Relationship в `_rels/sheetN.xml.rels` текущего листа (часть создаётся
+    /// при первой связи) и возвращает его Id. `external` – ссылка наружу (URL, mailto).
+    pub(crate) fn add_sheet_relationship(
+        &mut self,
+        rel_type: &str,
+        target: &str,
+        external: bool,
+    ) -> Result<String> {
+        let path = rels_path(&self.sheet_path);
+        let mut rels = self.part_bytes(&path)?.unwrap_or_else(|| {
+            br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"></Relationships>"#
+                .to_vec()
+        });
+        let rid = format!("rId{}", next_rel_id(&rels));
+        let mode = if external {
+            r#" TargetMode="External""#
+        } else {
+            ""
+        };
+        let rel_tag = format!(
+            r#"<Relationship Id="{rid}" Type="{rel_type}" Target="{}"{mode}/>"#,
+            escape(target)
+        );
+        if let Some(pos) = memmem::rfind(&rels, b"</Relationships") {
+            rels.splice(pos..pos, rel_tag.bytes());
+        } else {
+            // пустая часть вида <Relationships .../>
+            let root = memmem::find(&rels, b"<Relationships")
+                .with_context(|| format!("<Relationships> not found in {path}"))?;
+            let end = find_bytes_from(&rels, b"/>", root)
+                .with_context(|| format!("</Relationships> not found in {path}"))?;
+            rels.splice(end..end + 2, format!(">{rel_tag}</Relationships>").bytes());
+        }
+        self.removed_files.remove(&path);
+        self.new_files.insert(path, rels);
+        Ok(rid)
+    }
+
+    /// Убирает Relationship `rid` из rels текущего листа; пустая часть удаляется.
+    pub(crate) fn remove_sheet_relationship(&mut self, rid: &str) -> Result<()> {
+        let path = rels_path(&self.sheet_path);
+        let Some(mut rels) = self.part_bytes(&path)? else {
+            return Ok(());
+        };
+        let Some((start, end, _)) = find_relationship_by_id(&rels, rid) else {
+            return Ok(());
+        };
+        rels.splice(start..end, std::iter::empty());
+        if memmem::find(&rels, b"<Relationship ").is_none() {
+            self.new_files.remove(&path);
+            self.removed_files.insert(path);
+        } else {
+            self.new_files.insert(path, rels);
+        }
+        Ok(())
+    }
+}
+
 impl XlsxEditor {
     /// Считает количество листов по текущему состоянию `workbook_xml`
     fn sheet_count(&self) -> usize {
@@ -412,7 +975,7 @@ impl XlsxEditor {
     pub fn add_worksheet_at(&mut self, sheet_name: &str, mut index: usize) -> Result<&mut Self> {
         // -------- 0) валидации / подготовка ----------
         // 0.1) имя уже существует?
-        let sheet_names = scan(&self.src_path)?;
+        let sheet_names = self.sheet_names()?;
         if sheet_names.contains(&sheet_name.to_owned()) {
             bail!("Sheet {} already exists", sheet_name);
         }
@@ -424,7 +987,7 @@ impl XlsxEditor {
         }
 
         // 0.3) читаем исходный архив (для поиска свободного sheet#.xml)
-        let mut zin = zip::ZipArchive::new(File::open(&self.src_path)?)?;
+        let zin = self.source_archive()?;
 
         // 0.4) локальные (редактируемые) копии XML
         let mut wb_xml = self.workbook_xml.clone();
@@ -435,15 +998,14 @@ impl XlsxEditor {
         let mut rdr = Reader::from_reader(wb_xml.as_slice());
         rdr.config_mut().trim_text(true);
         while let Ok(ev) = rdr.read_event() {
-            if let Event::Empty(ref e) | Event::Start(ref e) = ev {
-                if e.name().as_ref() == b"sheet" {
-                    if let Some(id) = e.attributes().with_checks(false).flatten().find_map(|a| {
-                        (a.key.as_ref() == b"sheetId")
-                            .then(|| String::from_utf8_lossy(&a.value).into_owned())
-                    }) {
-                        max_sheet_id = max_sheet_id.max(id.parse::<u32>().unwrap_or(0));
-                    }
-                }
+            if let Event::Empty(ref e) | Event::Start(ref e) = ev
+                && e.name().as_ref() == b"sheet"
+                && let Some(id) = e.attributes().with_checks(false).flatten().find_map(|a| {
+                    (a.key.as_ref() == b"sheetId")
+                        .then(|| String::from_utf8_lossy(&a.value).into_owned())
+                })
+            {
+                max_sheet_id = max_sheet_id.max(id.parse::<u32>().unwrap_or(0));
             }
             if matches!(ev, Event::Eof) {
                 break;
@@ -452,32 +1014,12 @@ impl XlsxEditor {
         // Новый sheetId нам не особо важен (мы потом все перенумеруем), но пусть будет > max_sheet_id
         let _new_sheet_id = max_sheet_id + 1;
 
-        let mut max_rid = 0u32;
-        let mut rdr = Reader::from_reader(rels_xml.as_slice());
-        rdr.config_mut().trim_text(true);
-        while let Ok(ev) = rdr.read_event() {
-            if let Event::Empty(ref e) | Event::Start(ref e) = ev {
-                if e.name().as_ref() == b"Relationship" {
-                    if let Some(id) = e.attributes().with_checks(false).flatten().find_map(|a| {
-                        (a.key.as_ref() == b"Id")
-                            .then(|| String::from_utf8_lossy(&a.value).into_owned())
-                    }) {
-                        if let Some(num) = id.strip_prefix("rId") {
-                            max_rid = max_rid.max(num.parse::<u32>().unwrap_or(0));
-                        }
-                    }
-                }
-            }
-            if matches!(ev, Event::Eof) {
-                break;
-            }
-        }
-        let new_rid = max_rid + 1;
+        let new_rid = next_rel_id(&rels_xml);
 
         // -------- 2) найти свободный sheet#.xml ----------
         let mut max_sheet_file = 0usize;
-        for i in 0..zin.len() {
-            let name = zin.by_index(i)?.name().to_owned();
+        let source_names = zin.iter().flat_map(|z| z.file_names());
+        for name in source_names {
             if let Some(n) = name
                 .strip_prefix("xl/worksheets/sheet")
                 .and_then(|s| s.strip_suffix(".xml"))
@@ -486,7 +1028,9 @@ impl XlsxEditor {
                 max_sheet_file = max_sheet_file.max(n);
             }
         }
-        for path in self.new_files.keys() {
+        // текущий лист может ещё не лежать ни в архиве, ни в new_files (new_workbook)
+        let own_names = self.new_files.keys().map(String::as_str);
+        for path in own_names.chain([self.sheet_path.as_str()]) {
             if let Some(n) = path
                 .strip_prefix("xl/worksheets/sheet")
                 .and_then(|s| s.strip_suffix(".xml"))
@@ -500,52 +1044,14 @@ impl XlsxEditor {
         let new_sheet_target = format!("worksheets/sheet{new}.xml", new = new_sheet_file);
 
         // -------- 3) распарсим текущие <sheet .../> из workbook.xml ----------
-        #[allow(dead_code)]
-        #[derive(Debug, Clone)]
-        struct SheetTag {
-            name: String,
-            rid: String,  // "rIdNN"
-            path: String, // worksheets/sheet#.xml (нам нужно только для инфы; можно не хранить)
-        }
-        let (sheets_content_start, sheets_content_end) = Self::find_sheets_section(&wb_xml)?;
-        let sheets_slice = &wb_xml[sheets_content_start..sheets_content_end];
-
-        let mut rdr = Reader::from_reader(sheets_slice);
-        rdr.config_mut().trim_text(true);
-        let mut sheets: Vec<SheetTag> = Vec::new();
-
-        while let Ok(ev) = rdr.read_event() {
-            match ev {
-                Event::Empty(ref e) | Event::Start(ref e) if e.name().as_ref() == b"sheet" => {
-                    let mut name = None;
-                    let mut rid = None;
-                    // Target пути тут нет — он в rels, так что просто пустим.
-                    for a in e.attributes().with_checks(false).flatten() {
-                        let k = a.key.as_ref();
-                        let v = String::from_utf8_lossy(&a.value).into_owned();
-                        if k == b"name" {
-                            name = Some(v.clone());
-                        }
-                        if k == b"r:id" {
-                            rid = Some(v);
-                        }
-                    }
-                    sheets.push(SheetTag {
-                        name: name.unwrap_or_default(),
-                        rid: rid.unwrap_or_default(),
-                        path: String::new(),
-                    });
-                }
-                Event::Eof => break,
-                _ => {}
-            }
-        }
+        let (sheets_content_start, sheets_content_end, mut sheets) = parse_sheets_inner(&wb_xml)?;
 
         // -------- 4) формируем новый tag для нового листа ----------
-        let new_sheet = SheetTag {
+        let new_sheet = SheetTagMini {
             name: sheet_name.to_string(),
             rid: format!("rId{}", new_rid),
-            path: new_sheet_target.clone(),
+            sheet_id: String::new(),
+            extra: Vec::new(),
         };
 
         // вставляем по индексу
@@ -556,24 +1062,12 @@ impl XlsxEditor {
         }
 
         // -------- 5) перегенерируем <sheets>...</sheets> с новой нумерацией sheetId ----------
-        let mut new_inner = Vec::new();
-        // Сохраним форматирование: перенос строки + два пробела
-        for (i, sh) in sheets.iter().enumerate() {
-            let sheet_id = (i as u32) + 1; // «естественная» нумерация
-            let line = format!(
-                "\n  <sheet name=\"{}\" sheetId=\"{}\" r:id=\"{}\"/>",
-                xml_escape(&sh.name),
-                sheet_id,
-                sh.rid
-            );
-            new_inner.extend_from_slice(line.as_bytes());
-        }
+        let new_inner = build_sheets_inner(&sheets, /*renumber=*/ true);
 
         // подменяем содержимое между <sheets ...> и </sheets>
-        wb_xml.splice(
-            sheets_content_start..sheets_content_end,
-            new_inner.into_iter(),
-        );
+        wb_xml.splice(sheets_content_start..sheets_content_end, new_inner);
+        // имена и вкладки правее новой съезжают на одну позицию
+        remap_sheet_indices(&mut wb_xml, |i| if i >= index { i + 1 } else { i });
 
         // -------- 6) вставляем Relationship под конец </Relationships> ----------
         let rel_tag = format!(
@@ -586,12 +1080,7 @@ impl XlsxEditor {
             bail!("</Relationships> not found in workbook.xml.rels");
         }
 
-        // -------- 7) минимальный XML нового листа ----------
-        const EMPTY_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-        <worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
-          <sheetData> </sheetData>
-        </worksheet>"#;
-
+        // -------- 7) новый лист – пустая заготовка EMPTY_SHEET ----------
         // Обновляем внутреннее состояние
         self.workbook_xml = wb_xml;
         self.rels_xml = rels_xml;
@@ -650,18 +1139,18 @@ impl XlsxEditor {
                         let k = a.key.as_ref();
                         let v = String::from_utf8_lossy(&a.value).into_owned();
                         if k == b"name" {
-                            name = Some(v.clone());
+                            name = Some(a.unescape_value().map_or(v.clone(), |n| n.into_owned()));
                         }
                         if k == b"r:id" {
                             rid = Some(v);
                         }
                     }
 
-                    if let (Some(n), Some(r)) = (name, rid) {
-                        if n == sheet_name {
-                            target_rid = Some(r);
-                            break;
-                        }
+                    if let (Some(n), Some(r)) = (name, rid)
+                        && n == sheet_name
+                    {
+                        target_rid = Some(r);
+                        break;
                     }
                 }
                 Event::Eof => break,
@@ -696,11 +1185,11 @@ impl XlsxEditor {
                         }
                     }
 
-                    if let (Some(idv), Some(t)) = (id, target) {
-                        if idv == target_rid {
-                            target_rel = Some(t);
-                            break;
-                        }
+                    if let (Some(idv), Some(t)) = (id, target)
+                        && idv == target_rid
+                    {
+                        target_rel = Some(t);
+                        break;
                     }
                 }
                 Event::Eof => break,
@@ -728,12 +1217,9 @@ impl XlsxEditor {
         } else if let Some(buf) = self.loaded_files.get(&new_sheet_path) {
             buf.clone()
         } else {
-            let mut zin = zip_crate::ZipArchive::new(File::open(&self.src_path)?)?;
-            let mut f = zin
-                .by_name(&new_sheet_path)
+            let buf = self
+                .read_source_part(&new_sheet_path)?
                 .with_context(|| format!("{} not found in zip", new_sheet_path))?;
-            let mut buf = Vec::with_capacity(f.size() as usize);
-            f.read_to_end(&mut buf)?;
             self.loaded_files
                 .insert(new_sheet_path.clone(), buf.clone()); // кэшируем
             buf
@@ -752,11 +1238,12 @@ impl XlsxEditor {
 
 impl XlsxEditor {
     pub fn rename_worksheet(&mut self, old_name: &str, new_name: &str) -> Result<&mut Self> {
+        validate_sheet_name(new_name)?;
         if old_name == new_name {
             return Ok(self);
         }
         // запретим коллизию имён
-        let names = scan(&self.src_path)?;
+        let names = self.sheet_names()?;
         if names.iter().any(|n| n == new_name) {
             anyhow::bail!("Sheet `{}` already exists", new_name);
         }
@@ -779,6 +1266,49 @@ impl XlsxEditor {
         let new_inner = build_sheets_inner(&tags, /*renumber=*/ false);
         self.workbook_xml.splice(s_start..s_end, new_inner);
 
+        // ссылки вида 'Old'!A1 во всех листах и именах
+        self.rewrite_workbook_formulas(
+            RefTransform::RenameSheet {
+                from: old_name,
+                to: new_name,
+            },
+            None,
+        )?;
+
+        Ok(self)
+    }
+}
+
+impl XlsxEditor {
+    /// Moves sheet `name` to tab position `new_index` (0-based, clamped to the last position).
+    ///
+    /// Sheet ids and files stay as they are; the active/first visible tab and sheet-scoped
+    /// defined names follow their sheets.
+    ///
+    /// # Arguments
+    /// * `name` - The sheet to move.
+    /// * `new_index` - The new position among the sheet tabs.
+    pub fn move_worksheet(&mut self, name: &str, new_index: usize) -> Result<&mut Self> {
+        let (s_start, s_end, mut tags) = parse_sheets_inner(&self.workbook_xml)?;
+        let from = tags
+            .iter()
+            .position(|t| t.name == name)
+            .with_context(|| format!("Sheet `{name}` not found"))?;
+        let to = new_index.min(tags.len() - 1);
+        if from == to {
+            return Ok(self);
+        }
+        let tag = tags.remove(from);
+        tags.insert(to, tag);
+        let new_inner = build_sheets_inner(&tags, /*renumber=*/ false);
+        self.workbook_xml.splice(s_start..s_end, new_inner);
+
+        remap_sheet_indices(&mut self.workbook_xml, |i| match i {
+            i if i == from => to,
+            i if from < to && (from + 1..=to).contains(&i) => i - 1,
+            i if to < from && (to..from).contains(&i) => i + 1,
+            i => i,
+        });
         Ok(self)
     }
 }
@@ -792,14 +1322,21 @@ impl XlsxEditor {
 
         // 1) удалить из списка <sheets> (и тут же пере-нумеровать sheetId подряд)
         let (s_start, s_end, mut tags) = parse_sheets_inner(&self.workbook_xml)?;
-        let orig_len = tags.len();
-        tags.retain(|t| t.name != name);
-        if tags.len() == orig_len {
+        let Some(index) = tags.iter().position(|t| t.name == name) else {
             anyhow::bail!("Sheet `{}` tag not found in <sheets>", name);
-        }
+        };
+        tags.remove(index);
         let new_inner = build_sheets_inner(&tags, /*renumber=*/ true);
         self.workbook_xml.splice(s_start..s_end, new_inner);
 
+        // локальные имена листа уходят вместе с ним, индексы правее сдвигаются на 1;
+        // activeTab/firstSheet не должны указывать за последний лист
+        drop_local_names(&mut self.workbook_xml, index);
+        let last = tags.len().saturating_sub(1);
+        remap_sheet_indices(&mut self.workbook_xml, |i| {
+            if i > index { i - 1 } else { i }.min(last)
+        });
+
         // 2) убрать Relationship и получить Target
         let Some((rel_start, rel_end, target)) = find_relationship_by_id(&self.rels_xml, &rid)
         else {
@@ -816,6 +1353,18 @@ impl XlsxEditor {
         self.removed_files.insert(abs_path.clone());
         self.new_files.remove(&abs_path);
         self.loaded_files.remove(&abs_path);
+        // и связи листа (гиперссылки, таблицы, …) вместе с его примечаниями
+        let sheet_rels = rels_path(&abs_path);
+        if let Some(rels) = self.part_bytes(&sheet_rels)? {
+            for p in note_parts(&rels, &abs_path)? {
+                self.new_files.remove(&p);
+                self.loaded_files.remove(&p);
+                self.removed_files.insert(p);
+            }
+        }
+        self.new_files.remove(&sheet_rels);
+        self.removed_files.insert(sheet_rels);
+        self.rewrite_workbook_formulas(RefTransform::DeleteSheet { name }, None)?;
 
         // 4) если удалили активный лист — переключиться на первый оставшийся (или создать новый)
         if self.sheet_path == abs_path {
@@ -826,18 +1375,17 @@ impl XlsxEditor {
             while let Ok(ev) = rdr.read_event() {
                 if let quick_xml::events::Event::Empty(ref e)
                 | quick_xml::events::Event::Start(ref e) = ev
+                    && e.name().as_ref() == b"sheet"
                 {
-                    if e.name().as_ref() == b"sheet" {
-                        for a in e.attributes().with_checks(false).flatten() {
-                            if a.key.as_ref() == b"r:id" {
-                                next_rid = Some(String::from_utf8_lossy(&a.value).into_owned());
-                                break;
-                            }
-                        }
-                        if next_rid.is_some() {
+                    for a in e.attributes().with_checks(false).flatten() {
+                        if a.key.as_ref() == b"r:id" {
+                            next_rid = Some(String::from_utf8_lossy(&a.value).into_owned());
                             break;
                         }
                     }
+                    if next_rid.is_some() {
+                        break;
+                    }
                 }
                 if matches!(ev, quick_xml::events::Event::Eof) {
                     break;
@@ -856,13 +1404,9 @@ impl XlsxEditor {
                     } else if let Some(buf) = self.loaded_files.get(&p) {
                         buf.clone()
                     } else {
-                        let mut zin = zip::ZipArchive::new(std::fs::File::open(&self.src_path)?)?;
-                        let mut f = zin
-                            .by_name(&p)
+                        let buf = self
+                            .read_source_part(&p)?
                             .with_context(|| format!("{} not found in zip", p))?;
-                        let mut buf = Vec::with_capacity(f.size() as usize);
-                        use std::io::Read;
-                        f.read_to_end(&mut buf)?;
                         self.loaded_files.insert(p.clone(), buf.clone());
                         buf
                     };
@@ -883,7 +1427,7 @@ impl XlsxEditor {
 }
 
 // маленький хелпер
-fn calc_last_row(sheet_xml: &[u8]) -> u32 {
+pub(crate) fn calc_last_row(sheet_xml: &[u8]) -> u32 {
     let mut rdr = Reader::from_reader(sheet_xml);
     rdr.config_mut().trim_text(true);
 
@@ -918,26 +1462,25 @@ fn should_store_uncompressed(name: &str, content_len: usize) -> bool {
 fn normalize_styles_root(xml: &mut Vec<u8>) {
     if let Some(end_root) = memmem::rfind(xml, b"</styleSheet>") {
         let tail_start = end_root + "</styleSheet>".len();
-        if tail_start < xml.len() {
-            if let Some(nf) = memmem::find(&xml[tail_start..], b"<numFmts") {
-                // вырезаем хвост <numFmts>...</numFmts>
-                let nf_abs = tail_start + nf;
-                let close = memmem::find(&xml[nf_abs..], b"</numFmts>")
-                    .map(|p| nf_abs + p + "</numFmts>".len());
-                if let Some(nf_end) = close {
-                    let chunk: Vec<u8> = xml[nf_abs..nf_end].to_vec();
-                    xml.splice(nf_abs..nf_end, std::iter::empty());
-                    // вставляем внутрь корня (см. логику из пункта 1/2)
-                    let root = memmem::find(xml, b"<styleSheet").unwrap();
-                    let insert = find_bytes_from(xml, b">", root).unwrap() + 1;
-                    xml.splice(insert..insert, chunk.into_iter());
-                }
+        if tail_start < xml.len()
+            && let Some(nf) = memmem::find(&xml[tail_start..], b"<numFmts")
+        {
+            // вырезаем хвост <numFmts>...</numFmts>
+            let nf_abs = tail_start + nf;
+            let close = memmem::find(&xml[nf_abs..], b"</numFmts>")
+                .map(|p| nf_abs + p + "</numFmts>".len());
+            if let Some(nf_end) = close {
+                let chunk: Vec<u8> = xml[nf_abs..nf_end].to_vec();
+                xml.splice(nf_abs..nf_end, std::iter::empty());
+                // вставляем внутрь корня (см. логику из пункта 1/2)
+                let root = memmem::find(xml, b"<styleSheet").unwrap();
+                let insert = find_bytes_from(xml, b">", root).unwrap() + 1;
+                xml.splice(insert..insert, chunk);
             }
         }
     }
 }
 
-
 // вытащить r:id листа по его имени
 fn get_sheet_rid_by_name(wb_xml: &[u8], target_name: &str) -> Option<String> {
     let (s_start, s_end) = XlsxEditor::find_sheets_section(wb_xml).ok()?;
@@ -945,26 +1488,26 @@ fn get_sheet_rid_by_name(wb_xml: &[u8], target_name: &str) -> Option<String> {
     let mut rdr = Reader::from_reader(slice);
     rdr.config_mut().trim_text(true);
     while let Ok(ev) = rdr.read_event() {
-        if let Event::Empty(ref e) | Event::Start(ref e) = ev {
-            if e.name().as_ref() == b"sheet" {
-                let mut name = None;
-                let mut rid = None;
-                for a in e.attributes().with_checks(false).flatten() {
-                    let k = a.key.as_ref();
-                    let v = String::from_utf8_lossy(&a.value).into_owned();
-                    if k == b"name" {
-                        name = Some(v.clone());
-                    }
-                    if k == b"r:id" {
-                        rid = Some(v);
-                    }
+        if let Event::Empty(ref e) | Event::Start(ref e) = ev
+            && e.name().as_ref() == b"sheet"
+        {
+            let mut name = None;
+            let mut rid = None;
+            for a in e.attributes().with_checks(false).flatten() {
+                let k = a.key.as_ref();
+                let v = String::from_utf8_lossy(&a.value).into_owned();
+                if k == b"name" {
+                    name = Some(a.unescape_value().map_or(v.clone(), |n| n.into_owned()));
                 }
-                if let (Some(n), Some(r)) = (name, rid) {
-                    if n == target_name {
-                        return Some(r);
-                    }
+                if k == b"r:id" {
+                    rid = Some(v);
                 }
             }
+            if let (Some(n), Some(r)) = (name, rid)
+                && n == target_name
+            {
+                return Some(r);
+            }
         }
         if matches!(ev, Event::Eof) {
             break;
@@ -1000,11 +1543,11 @@ fn remove_ct_override_for_path(ct_xml: &mut Vec<u8>, part_abs_path: &str) {
     // part_abs_path должен начинаться с "xl/..."
     let part = format!("/{}", part_abs_path);
     let needle = format!(r#"PartName="{}""#, part);
-    while let Some(attr_pos) = memmem::find(&ct_xml, needle.as_bytes()) {
+    while let Some(attr_pos) = memmem::find(ct_xml, needle.as_bytes()) {
         // откатимся к началу тега "<Override"
         let tag_start = memmem::rfind(&ct_xml[..attr_pos], b"<Override").unwrap_or(attr_pos);
         // и найдём конец '>'
-        let tag_end = find_bytes_from(&ct_xml, b">", attr_pos)
+        let tag_end = find_bytes_from(ct_xml, b">", attr_pos)
             .map(|p| p + 1)
             .unwrap_or(attr_pos);
         ct_xml.splice(tag_start..tag_end, std::iter::empty());
@@ -1015,6 +1558,7 @@ struct SheetTagMini {
     name: String,
     rid: String,
     sheet_id: String, // сохраняем как строку: Excel не обязан идти по порядку; при delete можно перенумеровать
+    extra: Vec<(String, String)>, // прочие атрибуты (state, …) как есть, уже экранированные
 }
 
 // распарсить содержимое между <sheets>...</sheets> в вектор SheetTagMini
@@ -1034,21 +1578,28 @@ fn parse_sheets_inner(wb_xml: &[u8]) -> anyhow::Result<(usize, usize, Vec<SheetT
                 let mut name = None;
                 let mut rid = None;
                 let mut sid = None;
+                let mut extra = Vec::new();
                 for a in e.attributes().with_checks(false).flatten() {
                     let k = a.key.as_ref();
                     let v = String::from_utf8_lossy(&a.value).into_owned();
                     if k == b"name" {
-                        name = Some(v.clone());
+                        name = Some(a.unescape_value().map_or(v, |n| n.into_owned()));
                     } else if k == b"r:id" {
                         rid = Some(v.clone());
                     } else if k == b"sheetId" {
                         sid = Some(v.clone());
+                    } else {
+                        extra.push((
+                            String::from_utf8_lossy(k).into_owned(),
+                            v.replace('"', "&quot;"),
+                        ));
                     }
                 }
                 out.push(SheetTagMini {
                     name: name.unwrap_or_default(),
                     rid: rid.unwrap_or_default(),
                     sheet_id: sid.unwrap_or_else(|| "0".to_string()),
+                    extra,
                 });
             }
             quick_xml::events::Event::Eof => break,
@@ -1067,10 +1618,16 @@ fn build_sheets_inner(tags: &[SheetTagMini], renumber: bool) -> Vec<u8> {
         } else {
             t.sheet_id.clone()
         };
+        let extra: String = t
+            .extra
+            .iter()
+            .map(|(k, v)| format!(" {k}=\"{v}\""))
+            .collect();
         let line = format!(
-            "\n  <sheet name=\"{}\" sheetId=\"{}\" r:id=\"{}\"/>",
+            "\n  <sheet name=\"{}\" sheetId=\"{}\"{} r:id=\"{}\"/>",
             xml_escape(&t.name),
             sid,
+            extra,
             t.rid
         );
         buf.extend_from_slice(line.as_bytes());
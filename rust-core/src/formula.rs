@@ -0,0 +1,686 @@
+//! formula.rs – ссылки A1 внутри формул: разбор и переписывание
+//!
+//! [`rewrite_references`] walks a formula and lets a callback change every cell/range
+//! reference; [`transform_formula`] applies the structural edits the editor itself performs
+//! (row/column insertion and deletion, sheet rename and deletion).
+//!
+//! ```
+//! use rust_core::formula::{RefTransform, transform_formula};
+//!
+//! let f = transform_formula(
+//!     "SUM(A1:A10)+'Old name'!B2",
+//!     "Data",
+//!     RefTransform::RenameSheet { from: "Old name", to: "Totals" },
+//! );
+//! assert_eq!(f, "SUM(A1:A10)+Totals!B2");
+//! ```
+
+use std::fmt;
+
+use crate::style::util::col_letter;
+
+/// Last valid row (1-based).
+pub const MAX_ROW: u32 = 1_048_576;
+/// Last valid column (0-based, `XFD`).
+pub const MAX_COL: u32 = 16_383;
+
+/// One end of a reference: a cell (`$B$7`), a whole column (`B`) or a whole row (`7`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RefPart {
+    /// 0-based column; `None` for a whole-row part.
+    pub col: Option<u32>,
+    /// 1-based row; `None` for a whole-column part.
+    pub row: Option<u32>,
+    /// `$` before the column.
+    pub col_abs: bool,
+    /// `$` before the row.
+    pub row_abs: bool,
+}
+
+impl RefPart {
+    fn parse(s: &str) -> Option<Self> {
+        let b = s.as_bytes();
+        let mut i = 0;
+        let col_abs = b.first() == Some(&b'$');
+        if col_abs {
+            i += 1;
+        }
+        let letters_start = i;
+        while i < b.len() && b[i].is_ascii_alphabetic() {
+            i += 1;
+        }
+        let letters = &s[letters_start..i];
+        let mut row_abs = false;
+        if i < b.len() && b[i] == b'$' {
+            row_abs = true;
+            i += 1;
+        }
+        let digits_start = i;
+        while i < b.len() && b[i].is_ascii_digit() {
+            i += 1;
+        }
+        let digits = &s[digits_start..i];
+        if i != b.len() || (letters.is_empty() && digits.is_empty()) {
+            return None;
+        }
+
+        let col = if letters.is_empty() {
+            None
+        } else {
+            if letters.len() > 3 {
+                return None;
+            }
+            let c = letters.bytes().fold(0u32, |acc, c| {
+                acc * 26 + (c.to_ascii_uppercase() - b'A' + 1) as u32
+            }) - 1;
+            if c > MAX_COL {
+                return None;
+            }
+            Some(c)
+        };
+        let row = if digits.is_empty() {
+            None
+        } else {
+            let r: u32 = digits.parse().ok()?;
+            if r == 0 || r > MAX_ROW {
+                return None;
+            }
+            Some(r)
+        };
+        // "$A$" / "A$" без строки и "$1" с маркером столбца не бывают
+        if (row.is_none() && row_abs) || (col.is_none() && col_abs && row_abs) {
+            return None;
+        }
+        // у строки без столбца маркер стоит перед числом: "$7" разобран как col_abs
+        let (col_abs, row_abs) = if col.is_none() {
+            (false, col_abs || row_abs)
+        } else {
+            (col_abs, row_abs)
+        };
+        Some(RefPart {
+            col,
+            row,
+            col_abs,
+            row_abs,
+        })
+    }
+
+    fn kind(&self) -> (bool, bool) {
+        (self.col.is_some(), self.row.is_some())
+    }
+
+    fn write(&self, out: &mut String) {
+        if let Some(c) = self.col {
+            if self.col_abs {
+                out.push('$');
+            }
+            out.push_str(&col_letter(c));
+        }
+        if let Some(r) = self.row {
+            if self.row_abs {
+                out.push('$');
+            }
+            out.push_str(&r.to_string());
+        }
+    }
+}
+
+/// A cell, range, whole-row or whole-column reference, optionally qualified with a sheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    /// Sheet name without quotes, `Jan:Mar` for a 3-D reference; `None` for a reference to
+    /// the formula's own sheet.
+    pub sheet: Option<String>,
+    pub start: RefPart,
+    /// Second corner of a range (`A1:B2`, `A:C`, `3:5`).
+    pub end: Option<RefPart>,
+}
+
+impl Reference {
+    /// Parses `B2`, `$A$1:C3`, `A:A`, `2:4`, optionally prefixed with `Sheet!` or `'My sheet'!`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let Some(bang) = s.rfind('!') else {
+            return parse_area(s);
+        };
+        let (prefix, area) = (&s[..bang], &s[bang + 1..]);
+        let sheet = match prefix.strip_prefix('\'').and_then(|p| p.strip_suffix('\'')) {
+            Some(quoted) => quoted.replace("''", "'"),
+            None if !prefix.is_empty() && !prefix.contains(['\'', ' ', '!']) => prefix.to_owned(),
+            None => return None,
+        };
+        let mut r = parse_area(area)?;
+        r.sheet = Some(sheet);
+        Some(r)
+    }
+
+    fn write_area(&self, out: &mut String) {
+        self.start.write(out);
+        if let Some(end) = &self.end {
+            out.push(':');
+            end.write(out);
+        }
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut out = String::new();
+        if let Some(sheet) = &self.sheet {
+            out.push_str(&quote_sheet_name(sheet));
+            out.push('!');
+        }
+        self.write_area(&mut out);
+        f.write_str(&out)
+    }
+}
+
+/// Parses a bare area such as `B2`, `$A$1:C3`, `A:A` or `2:4` (no sheet prefix).
+pub(crate) fn parse_area(s: &str) -> Option<Reference> {
+    let (start, end) = match s.split_once(':') {
+        Some((a, b)) => (RefPart::parse(a)?, Some(RefPart::parse(b)?)),
+        None => (RefPart::parse(s)?, None),
+    };
+    match end {
+        Some(e) if e.kind() != start.kind() => None,
+        None if start.kind() != (true, true) => None,
+        _ => Some(Reference {
+            sheet: None,
+            start,
+            end,
+        }),
+    }
+}
+
+/// Inverse of [`parse_area`].
+pub(crate) fn format_area(r: &Reference) -> String {
+    let mut out = String::new();
+    r.write_area(&mut out);
+    out
+}
+
+/// `'My Sheet'` for names that need quoting, the name itself otherwise.
+///
+/// A 3-D span `Jan:Mar` is quoted as a whole (`'Jan:Mar 2'`) when either end needs it.
+pub fn quote_sheet_name(name: &str) -> String {
+    let plain = |name: &str| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '_' || c == '.')
+            && parse_area(name).is_none()
+            && !looks_like_r1c1(name)
+    };
+    let plain = match name.split_once(':') {
+        Some((first, last)) => plain(first) && plain(last),
+        None => plain(name),
+    };
+    if plain {
+        name.to_owned()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+fn looks_like_r1c1(name: &str) -> bool {
+    let up = name.to_ascii_uppercase();
+    let Some(rest) = up.strip_prefix('R') else {
+        return false;
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.is_empty()
+        || rest
+            .strip_prefix('C')
+            .is_some_and(|r| r.chars().all(|c| c.is_ascii_digit()))
+}
+
+// символы имени/токена (для границ ссылки)
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'_' | b'.' | b'$' | b'\\' | b'?') || b >= 0x80
+}
+
+// конец ссылки "A1", "$A$1:B2", "A:A", "1:3", начиная с `p`
+fn scan_area(s: &str, p: usize) -> Option<(Reference, usize)> {
+    let b = s.as_bytes();
+    let token_end = |mut i: usize| {
+        while i < b.len() && (b[i].is_ascii_alphanumeric() || b[i] == b'$') {
+            i += 1;
+        }
+        i
+    };
+    let e1 = token_end(p);
+    let mut end = e1;
+    if e1 < b.len() && b[e1] == b':' {
+        let e2 = token_end(e1 + 1);
+        // справа от `:` может быть и не ссылка (A1:INDEX(B:B,5)) – тогда берём одну левую
+        if e2 > e1 + 1
+            && parse_area(&s[p..e2]).is_some()
+            && !(e2 < b.len() && (is_ident_byte(b[e2]) || matches!(b[e2], b'(' | b'!' | b'[')))
+        {
+            end = e2;
+        }
+    }
+    // за ссылкой не должно продолжаться имя, вызов функции, лист или таблица
+    if end < b.len() && (is_ident_byte(b[end]) || matches!(b[end], b'(' | b'!' | b'[')) {
+        return None;
+    }
+    parse_area(&s[p..end]).map(|r| (r, end))
+}
+
+// конец строкового литерала "..." (с "" внутри), начиная с открывающей кавычки
+fn skip_quoted(b: &[u8], p: usize, q: u8) -> usize {
+    let mut i = p + 1;
+    while i < b.len() {
+        if b[i] == q {
+            if i + 1 < b.len() && b[i + 1] == q {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    b.len()
+}
+
+fn skip_brackets(b: &[u8], p: usize) -> usize {
+    let mut depth = 0usize;
+    let mut i = p;
+    while i < b.len() {
+        match b[i] {
+            b'[' => depth += 1,
+            b']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    b.len()
+}
+
+/// Calls `f` for every cell/range reference in `formula` (without the leading `=`) and
+/// returns the formula with the references re-rendered.
+///
+/// `f` may change the reference in place; returning `false` replaces it with `#REF!`
+/// (keeping the sheet prefix unless `f` cleared [`Reference::sheet`]).
+/// A 3-D reference (`Jan:Mar!A1`) arrives with both sheet names in [`Reference::sheet`]
+/// (`Jan:Mar`). String literals, function names, defined names, structured and external
+/// (`[1]Sheet!A1`) references are left untouched.
+pub fn rewrite_references(formula: &str, mut f: impl FnMut(&mut Reference) -> bool) -> String {
+    let b = formula.as_bytes();
+    let mut out = String::with_capacity(formula.len() + 8);
+    let mut i = 0;
+    let mut external = false;
+
+    // ссылка после (возможного) префикса листа, начиная с `p`
+    let mut emit_ref =
+        |out: &mut String, prefix: &str, sheet: Option<String>, p: usize, external: bool| {
+            let (mut r, end) = scan_area(formula, p)?;
+            if external {
+                out.push_str(prefix);
+                out.push_str(&formula[p..end]);
+                return Some(end);
+            }
+            r.sheet = sheet.clone();
+            if f(&mut r) {
+                if r.sheet == sheet {
+                    out.push_str(prefix);
+                } else if let Some(name) = &r.sheet {
+                    out.push_str(&quote_sheet_name(name));
+                    out.push('!');
+                }
+                r.write_area(out);
+            } else {
+                // лист убран колбэком – `#REF!` без префикса
+                if r.sheet == sheet {
+                    out.push_str(prefix);
+                }
+                out.push_str("#REF!");
+            }
+            Some(end)
+        };
+
+    while i < b.len() {
+        let c = b[i];
+        let was_external = std::mem::take(&mut external);
+        match c {
+            b'"' => {
+                let j = skip_quoted(b, i, b'"');
+                out.push_str(&formula[i..j]);
+                i = j;
+            }
+            b'[' => {
+                let j = skip_brackets(b, i);
+                out.push_str(&formula[i..j]);
+                // "[1]Sheet1!A1" – внешняя книга
+                external = j < b.len() && (is_ident_byte(b[j]) || b[j] == b'\'');
+                i = j;
+            }
+            b'#' => {
+                let mut j = i + 1;
+                while j < b.len()
+                    && (b[j].is_ascii_alphanumeric() || matches!(b[j], b'/' | b'!' | b'?'))
+                {
+                    j += 1;
+                    if b[j - 1] == b'!' || b[j - 1] == b'?' {
+                        break;
+                    }
+                }
+                out.push_str(&formula[i..j]);
+                i = j;
+            }
+            b'\'' => {
+                let j = skip_quoted(b, i, b'\'');
+                if j < b.len() && b[j] == b'!' {
+                    let quoted = &formula[i + 1..j - 1];
+                    let name = quoted.replace("''", "'");
+                    let prefix = &formula[i..=j];
+                    let start = out.len();
+                    match emit_ref(&mut out, prefix, Some(name), j + 1, was_external) {
+                        Some(end) => i = end,
+                        None => {
+                            out.truncate(start);
+                            out.push_str(prefix);
+                            i = j + 1;
+                        }
+                    }
+                } else {
+                    out.push_str(&formula[i..j]);
+                    i = j;
+                }
+            }
+            c if is_ident_byte(c) => {
+                let mut j = i;
+                while j < b.len() && is_ident_byte(b[j]) {
+                    j += 1;
+                }
+                // Jan:Mar!A1
+                let mut k = j;
+                if k < b.len() && b[k] == b':' {
+                    let mut m = k + 1;
+                    while m < b.len() && is_ident_byte(b[m]) {
+                        m += 1;
+                    }
+                    if m > k + 1 && m < b.len() && b[m] == b'!' {
+                        k = m;
+                    }
+                }
+                if k < b.len() && b[k] == b'!' {
+                    let prefix = &formula[i..=k];
+                    let name = formula[i..k].to_owned();
+                    let start = out.len();
+                    match emit_ref(&mut out, prefix, Some(name), k + 1, was_external) {
+                        Some(end) => i = end,
+                        None => {
+                            out.truncate(start);
+                            out.push_str(prefix);
+                            i = k + 1;
+                        }
+                    }
+                } else if let Some(end) = emit_ref(&mut out, "", None, i, was_external) {
+                    i = end;
+                } else {
+                    out.push_str(&formula[i..j]);
+                    i = j;
+                }
+            }
+            _ => {
+                let ch = formula[i..].chars().next().unwrap_or_default();
+                out.push(ch);
+                i += ch.len_utf8().max(1);
+            }
+        }
+    }
+    out
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Axis {
+    /// 1-based rows.
+    Row,
+    /// 0-based columns.
+    Col,
+}
+
+/// Insertion or deletion of `n` rows/columns starting at `at`.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Shift {
+    pub(crate) axis: Axis,
+    pub(crate) at: u32,
+    pub(crate) n: u32,
+    pub(crate) delete: bool,
+}
+
+impl Shift {
+    fn max(&self) -> u32 {
+        match self.axis {
+            Axis::Row => MAX_ROW,
+            Axis::Col => MAX_COL,
+        }
+    }
+
+    fn coord<'a>(&self, p: &'a mut RefPart) -> &'a mut Option<u32> {
+        match self.axis {
+            Axis::Row => &mut p.row,
+            Axis::Col => &mut p.col,
+        }
+    }
+
+    /// New position of a single row/column; `None` if it was deleted or pushed past the end.
+    pub(crate) fn one(&self, v: u32) -> Option<u32> {
+        if v < self.at {
+            Some(v)
+        } else if !self.delete {
+            Some(v + self.n).filter(|&v| v <= self.max())
+        } else if v < self.at + self.n {
+            None
+        } else {
+            Some(v - self.n)
+        }
+    }
+
+    /// New bounds of the span `lo..=hi`; a deletion trims the span, `None` if nothing is left.
+    fn span(&self, lo: u32, hi: u32) -> Option<(u32, u32)> {
+        if !self.delete {
+            let hi = self.one(hi).unwrap_or(self.max());
+            return Some((self.one(lo)?, hi));
+        }
+        let end = self.at + self.n;
+        let lo = if lo < self.at {
+            lo
+        } else if lo < end {
+            self.at
+        } else {
+            lo - self.n
+        };
+        let hi = if hi < self.at {
+            hi
+        } else if hi < end {
+            self.at.checked_sub(1)?
+        } else {
+            hi - self.n
+        };
+        (lo <= hi).then_some((lo, hi))
+    }
+
+    /// Moves a reference that points into the shifted sheet; `false` means `#REF!`.
+    pub(crate) fn reference(&self, r: &mut Reference) -> bool {
+        let start = *self.coord(&mut r.start);
+        let end = r.end.as_mut().map(|e| *self.coord(e));
+        match (start, end) {
+            // A:C не затрагивается вставкой строк, 3:5 – вставкой столбцов
+            (None, _) | (Some(_), Some(None)) => true,
+            (Some(v), None) => match self.one(v) {
+                Some(v) => {
+                    *self.coord(&mut r.start) = Some(v);
+                    true
+                }
+                None => false,
+            },
+            (Some(a), Some(Some(b))) => {
+                let Some((lo, hi)) = self.span(a.min(b), a.max(b)) else {
+                    return false;
+                };
+                let (first, second) = if a <= b { (lo, hi) } else { (hi, lo) };
+                *self.coord(&mut r.start) = Some(first);
+                if let Some(end) = r.end.as_mut() {
+                    *self.coord(end) = Some(second);
+                }
+                true
+            }
+        }
+    }
+
+    /// Space-separated list of areas (`sqref`); areas that vanished are dropped.
+    pub(crate) fn sqref(&self, s: &str) -> String {
+        let mut out: Vec<String> = Vec::new();
+        for part in s.split_whitespace() {
+            match parse_area(part) {
+                Some(mut r) => {
+                    if self.reference(&mut r) {
+                        out.push(format_area(&r));
+                    }
+                }
+                None => out.push(part.to_owned()),
+            }
+        }
+        out.join(" ")
+    }
+
+    /// The same edit as a [`RefTransform`] of `sheet`.
+    pub(crate) fn transform<'a>(&self, sheet: &'a str) -> RefTransform<'a> {
+        let (at, n) = (self.at, self.n);
+        match (self.axis, self.delete) {
+            (Axis::Row, false) => RefTransform::InsertRows { sheet, at, n },
+            (Axis::Row, true) => RefTransform::DeleteRows { sheet, at, n },
+            (Axis::Col, false) => RefTransform::InsertColumns { sheet, at, n },
+            (Axis::Col, true) => RefTransform::DeleteColumns { sheet, at, n },
+        }
+    }
+
+    pub(crate) fn formula(&self, formula: &str, sheet_name: Option<&str>) -> String {
+        rewrite_references(formula, |r| match &r.sheet {
+            None => self.reference(r),
+            Some(s) if sheet_name.is_some_and(|n| same_sheet(s, n)) => self.reference(r),
+            Some(_) => true,
+        })
+    }
+}
+
+/// Moves the relative parts of every reference by `rows`/`cols`, as copying the formula
+/// to another cell does; a reference pushed off the sheet becomes `#REF!`.
+pub(crate) fn offset_formula(formula: &str, rows: i64, cols: i64) -> String {
+    let moved = |v: u32, d: i64, lo: u32, hi: u32| {
+        u32::try_from(v as i64 + d)
+            .ok()
+            .filter(|v| (lo..=hi).contains(v))
+    };
+    rewrite_references(formula, |r| {
+        for p in std::iter::once(&mut r.start).chain(r.end.as_mut()) {
+            if let Some(c) = p.col.filter(|_| !p.col_abs) {
+                let Some(c) = moved(c, cols, 0, MAX_COL) else {
+                    return false;
+                };
+                p.col = Some(c);
+            }
+            if let Some(row) = p.row.filter(|_| !p.row_abs) {
+                let Some(row) = moved(row, rows, 1, MAX_ROW) else {
+                    return false;
+                };
+                p.row = Some(row);
+            }
+        }
+        true
+    })
+}
+
+// Excel сравнивает имена листов без учёта регистра
+pub(crate) fn same_sheet(a: &str, b: &str) -> bool {
+    a == b || a.to_lowercase() == b.to_lowercase()
+}
+
+/// Structural edit that formulas have to follow, see [`transform_formula`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefTransform<'a> {
+    /// `n` rows inserted before row `at` (1-based) on `sheet`.
+    InsertRows { sheet: &'a str, at: u32, n: u32 },
+    /// Rows `at..at + n` (1-based) deleted from `sheet`.
+    DeleteRows { sheet: &'a str, at: u32, n: u32 },
+    /// `n` columns inserted before column `at` (0-based) on `sheet`.
+    InsertColumns { sheet: &'a str, at: u32, n: u32 },
+    /// Columns `at..at + n` (0-based) deleted from `sheet`.
+    DeleteColumns { sheet: &'a str, at: u32, n: u32 },
+    /// Sheet `from` renamed to `to`.
+    RenameSheet { from: &'a str, to: &'a str },
+    /// Sheet `name` removed; every reference into it becomes `#REF!`.
+    DeleteSheet { name: &'a str },
+}
+
+impl RefTransform<'_> {
+    fn shift(&self) -> Option<(&str, Shift)> {
+        let (sheet, axis, at, n, delete) = match *self {
+            RefTransform::InsertRows { sheet, at, n } => (sheet, Axis::Row, at, n, false),
+            RefTransform::DeleteRows { sheet, at, n } => (sheet, Axis::Row, at, n, true),
+            RefTransform::InsertColumns { sheet, at, n } => (sheet, Axis::Col, at, n, false),
+            RefTransform::DeleteColumns { sheet, at, n } => (sheet, Axis::Col, at, n, true),
+            _ => return None,
+        };
+        Some((
+            sheet,
+            Shift {
+                axis,
+                at,
+                n,
+                delete,
+            },
+        ))
+    }
+}
+
+/// Rewrites the references of `formula` (without the leading `=`) that lives on
+/// `formula_sheet` according to `transform`.
+///
+/// Unqualified references belong to `formula_sheet`; sheet names are compared
+/// case-insensitively, as Excel does.
+pub fn transform_formula(formula: &str, formula_sheet: &str, transform: RefTransform) -> String {
+    if let Some((sheet, shift)) = transform.shift() {
+        return rewrite_references(formula, |r| {
+            let target = r.sheet.as_deref().unwrap_or(formula_sheet);
+            if same_sheet(target, sheet) {
+                shift.reference(r)
+            } else {
+                true
+            }
+        });
+    }
+    rewrite_references(formula, |r| {
+        let Some(sheet) = r.sheet.as_deref() else {
+            return true;
+        };
+        // у 3D-ссылки Jan:Mar меняются оба конца
+        let (first, last) = sheet.split_once(':').unwrap_or((sheet, sheet));
+        match transform {
+            RefTransform::RenameSheet { from, to }
+                if same_sheet(first, from) || same_sheet(last, from) =>
+            {
+                let rename = |s: &str| if same_sheet(s, from) { to } else { s }.to_owned();
+                r.sheet = Some(match sheet.split_once(':') {
+                    Some(_) => format!("{}:{}", rename(first), rename(last)),
+                    None => to.to_owned(),
+                });
+                true
+            }
+            RefTransform::DeleteSheet { name }
+                if same_sheet(first, name) || same_sheet(last, name) =>
+            {
+                r.sheet = None;
+                false
+            }
+            _ => true,
+        }
+    })
+}
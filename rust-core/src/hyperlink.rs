@@ -0,0 +1,207 @@
+//! hyperlink.rs – гиперссылки ячеек: `<hyperlinks>` листа + внешние адреса в `_rels/sheetN.xml.rels`
+
+use anyhow::{Result, bail};
+use memchr::memmem;
+use quick_xml::escape::{escape, unescape};
+use std::collections::HashSet;
+
+use crate::{
+    CellValue, XlsxEditor,
+    files_part::{worksheet_element_range, worksheet_insert_pos},
+    find_bytes_from,
+    merge::{overlaps, parse_rect},
+    read_part::parse_cell_ref,
+    sheet_copy::rels_path,
+    style::util::col_letter,
+};
+
+const HYPERLINK_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+const R_NS: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+
+// значение атрибута сырого тега (с раскрытыми &amp; и т. п.)
+fn tag_attr(tag: &[u8], key: &str) -> Option<String> {
+    let needle = format!(" {key}=\"");
+    let a = memmem::find(tag, needle.as_bytes())? + needle.len();
+    let q = tag[a..].iter().position(|&b| b == b'"')?;
+    let raw = String::from_utf8_lossy(&tag[a..a + q]);
+    Some(unescape(&raw).map_or_else(|_| raw.to_string(), |v| v.into_owned()))
+}
+
+// сырые <hyperlink> листа: (начало, конец) в sheet_xml
+fn hyperlink_spans(xml: &[u8]) -> Vec<(usize, usize)> {
+    let Some((start, end)) = worksheet_element_range(xml, "hyperlinks") else {
+        return Vec::new();
+    };
+    let mut out = Vec::new();
+    let mut i = start;
+    while let Some(off) = memmem::find(&xml[i..end], b"<hyperlink ") {
+        let s = i + off;
+        let Some(head_end) = find_bytes_from(xml, b">", s) else {
+            break;
+        };
+        let e = if xml[head_end - 1] == b'/' {
+            head_end + 1
+        } else {
+            find_bytes_from(xml, b"</hyperlink>", head_end).map_or(head_end + 1, |p| p + 12)
+        };
+        out.push((s, e));
+        i = e;
+    }
+    out
+}
+
+// r:id внешних ссылок листа
+pub(crate) fn hyperlink_rids(xml: &[u8]) -> HashSet<String> {
+    hyperlink_spans(xml)
+        .into_iter()
+        .filter_map(|(s, e)| tag_attr(&xml[s..e], "r:id"))
+        .collect()
+}
+
+impl XlsxEditor {
+    /// Puts a hyperlink on a cell, replacing any link it already has.
+    ///
+    /// External targets (`https://…`, `mailto:…`, file paths) are stored in the
+    /// sheet's relationships; targets starting with `#` (`"#'Detail 1'!A1"`)
+    /// jump inside the workbook. The cell style is left as it is.
+    ///
+    /// # Arguments
+    /// * `coord` - The cell (e.g. `"B4"`).
+    /// * `target` - URL, `mailto:` address or `#Sheet!A1` location.
+    /// * `display` - Text written into the cell; `None` keeps the current value.
+    /// * `tooltip` - Text shown when hovering over the link.
+    pub fn set_hyperlink(
+        &mut self,
+        coord: &str,
+        target: &str,
+        display: Option<&str>,
+        tooltip: Option<&str>,
+    ) -> Result<&mut Self> {
+        let (col, row) = parse_cell_ref(coord)?;
+        let cell = format!("{}{row}", col_letter(col));
+        if target.is_empty() || target == "#" {
+            bail!("hyperlink target for {cell} is empty");
+        }
+        if tooltip.is_some_and(|t| t.chars().count() > 255) {
+            bail!("hyperlink tooltip is longer than 255 characters");
+        }
+
+        self.remove_hyperlink(&cell)?;
+        let mut tag = format!(r#"<hyperlink ref="{cell}""#);
+        if let Some(location) = target.strip_prefix('#') {
+            tag.push_str(&format!(r#" location="{}""#, escape(location)));
+        } else {
+            let rid = self.add_sheet_relationship(HYPERLINK_REL_TYPE, target, true)?;
+            self.ensure_r_namespace()?;
+            tag.push_str(&format!(r#" r:id="{rid}""#));
+        }
+        if let Some(t) = tooltip {
+            tag.push_str(&format!(r#" tooltip="{}""#, escape(t)));
+        }
+        if let Some(d) = display {
+            tag.push_str(&format!(r#" display="{}""#, escape(d)));
+        }
+        tag.push_str("/>");
+
+        if let Some((_, end)) = worksheet_element_range(&self.sheet_xml, "hyperlinks") {
+            let block = &self.sheet_xml[..end];
+            if block.ends_with(b"/>") {
+                // <hyperlinks/>
+                self.sheet_xml
+                    .splice(end - 2..end, format!(">{tag}</hyperlinks>").into_bytes());
+            } else {
+                let pos = end - "</hyperlinks>".len();
+                self.sheet_xml.splice(pos..pos, tag.into_bytes());
+            }
+        } else {
+            let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "hyperlinks") else {
+                bail!("</worksheet> not found");
+            };
+            self.sheet_xml.splice(
+                pos..pos,
+                format!("<hyperlinks>{tag}</hyperlinks>").into_bytes(),
+            );
+        }
+
+        if let Some(d) = display {
+            // подпись всегда текст: "2024" или "=x" не должны стать числом или формулой
+            self.set_cell(&cell, CellValue::Text(d.to_owned()))?;
+        }
+        Ok(self)
+    }
+
+    /// Removes the hyperlinks covering `range` on the current sheet. Cell values stay.
+    pub fn remove_hyperlink(&mut self, range: &str) -> Result<&mut Self> {
+        let rect = parse_rect(range)?;
+        let mut rids = Vec::new();
+        // с конца, чтобы смещения не поехали
+        for (s, e) in hyperlink_spans(&self.sheet_xml).into_iter().rev() {
+            let tag = &self.sheet_xml[s..e];
+            if tag_attr(tag, "ref")
+                .and_then(|r| parse_rect(&r).ok())
+                .is_some_and(|r| overlaps(r, rect))
+            {
+                rids.extend(tag_attr(tag, "r:id"));
+                self.sheet_xml.drain(s..e);
+            }
+        }
+        if hyperlink_spans(&self.sheet_xml).is_empty()
+            && let Some((s, e)) = worksheet_element_range(&self.sheet_xml, "hyperlinks")
+        {
+            self.sheet_xml.drain(s..e);
+        }
+        for rid in rids {
+            self.remove_sheet_relationship(&rid)?;
+        }
+        Ok(self)
+    }
+
+    /// Returns the target of the hyperlink on `coord`: the URL for external links,
+    /// `#location` for links inside the workbook.
+    pub fn hyperlink(&self, coord: &str) -> Result<Option<String>> {
+        let (col, row) = parse_cell_ref(coord)?;
+        let rect = (col, row, col, row);
+        for (s, e) in hyperlink_spans(&self.sheet_xml) {
+            let tag = &self.sheet_xml[s..e];
+            if !tag_attr(tag, "ref")
+                .and_then(|r| parse_rect(&r).ok())
+                .is_some_and(|r| overlaps(r, rect))
+            {
+                continue;
+            }
+            if let Some(loc) = tag_attr(tag, "location") {
+                return Ok(Some(format!("#{loc}")));
+            }
+            let Some(rid) = tag_attr(tag, "r:id") else {
+                return Ok(None);
+            };
+            let rels = self
+                .part_bytes(&rels_path(&self.sheet_path))?
+                .unwrap_or_default();
+            let needle = format!(r#"Id="{rid}""#);
+            let target = memmem::find(&rels, needle.as_bytes()).and_then(|p| {
+                let start = memmem::rfind(&rels[..p], b"<Relationship")?;
+                let end = find_bytes_from(&rels, b">", start)?;
+                tag_attr(&rels[start..end], "Target")
+            });
+            return Ok(target);
+        }
+        Ok(None)
+    }
+
+    // r:id требует объявленного префикса r на корне листа
+    pub(crate) fn ensure_r_namespace(&mut self) -> Result<()> {
+        let Some(root) = memmem::find(&self.sheet_xml, b"<worksheet") else {
+            bail!("<worksheet> not found");
+        };
+        let Some(end) = find_bytes_from(&self.sheet_xml, b">", root) else {
+            bail!("<worksheet> start tag is not closed");
+        };
+        if memmem::find(&self.sheet_xml[root..end], b"xmlns:r=").is_none() {
+            let decl = format!(r#" xmlns:r="{R_NS}""#);
+            self.sheet_xml.splice(end..end, decl.into_bytes());
+        }
+        Ok(())
+    }
+}
@@ -2,24 +2,45 @@
 
 // #[global_allocator]
 // static GLOBAL: MiMalloc = MiMalloc;
+pub mod cell;
+mod comments;
+mod conditional;
 pub mod files_part;
+pub mod formula;
+mod hyperlink;
+mod merge;
+mod protection;
 use memchr::memmem;
 mod read_part;
+mod shared_strings;
+mod sheet_copy;
+mod shift;
 pub mod style;
 mod test;
+mod validation;
 use std::{
     collections::{HashMap, HashSet},
     fs::File,
-    io::Read,
-    path::{Path, PathBuf},
+    io::{Read, Seek},
+    path::Path,
+    sync::OnceLock,
 };
 
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use quick_xml::{Reader, Writer, events::Event};
 
+pub use crate::cell::{CellValue, DateValue};
+pub use crate::comments::Comment;
+pub use crate::conditional::{CfOperator, Cfvo, ConditionalRule};
+pub use crate::merge::MergeOptions;
+pub use crate::protection::SheetProtection;
+pub use crate::read_part::{SheetReader, SheetRow, for_each_row};
+pub use crate::shared_strings::StringStorage;
+pub use crate::validation::{DataValidation, ErrorStyle, ValidationRule, ValidationType};
 use crate::{
-    files_part::needs_xml_space_preserve,
-    style::{AlignSpec, HorizAlignment, VertAlignment},
+    files_part::{Source, needs_xml_space_preserve},
+    shared_strings::SharedStrings,
+    style::{AlignmentSpec, BorderSpec, CellProtection, FontVertAlign, Underline},
 };
 // use tempfile::NamedTempFile;
 // use zip::{ZipArchive, ZipWriter, write::FileOptions};
@@ -33,6 +54,12 @@ struct FontKey {
     size_100: u32,
     bold: bool,
     italic: bool,
+    color: Option<String>, // <color .../> как ключ
+    underline: Option<Underline>,
+    strike: bool,
+    vert_align: Option<FontVertAlign>,
+    family: Option<u32>,
+    scheme: Option<String>,
 }
 #[derive(Hash, Eq, PartialEq, Clone)]
 struct StyleKey {
@@ -40,7 +67,8 @@ struct StyleKey {
     font_id: Option<u32>,
     fill_id: Option<u32>,
     border_id: Option<u32>,
-    align: Option<(Option<HorizAlignment>, Option<VertAlignment>, bool)>, // wrap
+    align: Option<AlignmentSpec>,
+    protection: Option<CellProtection>,
 }
 #[allow(dead_code)]
 struct XfParts {
@@ -48,7 +76,8 @@ struct XfParts {
     font_id: Option<u32>,
     fill_id: Option<u32>,
     border_id: Option<u32>,
-    align: Option<AlignSpec>,
+    align: Option<AlignmentSpec>,
+    protection: Option<CellProtection>,
 }
 
 struct StyleIndex {
@@ -58,18 +87,21 @@ struct StyleIndex {
     next_custom_numfmt: u32, // >=164
 
     font_by_key: HashMap<FontKey, u32>,
-    fill_by_rgb: HashMap<String, u32>,   // RGB в верхнем регистре
-    border_by_key: HashMap<String, u32>, // единый style для всех сторон
+    fill_by_key: HashMap<String, u32>, // каноничный <fill>...</fill>
+    border_by_key: HashMap<String, u32>, // каноничный <border>...</border>
+    borders: Vec<BorderSpec>,          // index == borderId
 
     xf_by_key: HashMap<StyleKey, u32>,
+    dxf_by_key: HashMap<String, u32>, // сырой <dxf>...</dxf>
 
     fonts_count: u32,
     fills_count: u32,
     borders_count: u32,
+    dxfs_count: u32,
 }
 
 pub struct XlsxEditor {
-    src_path: PathBuf,
+    source: Source, // исходный архив, из которого копируются неизменённые части
     sheet_path: String,
     sheet_xml: Vec<u8>,
     last_row: u32,
@@ -80,10 +112,11 @@ pub struct XlsxEditor {
     styles_index: Option<StyleIndex>,
     loaded_files: std::collections::HashMap<String, Vec<u8>>,
     removed_files: HashSet<String>, // ← НОВОЕ: пути внутри ZIP, которые надо выкинуть
-
+    shared_strings: OnceLock<SharedStrings>, // грузится лениво при первом обращении
+    string_storage: StringStorage,
 }
 
-/// Polars
+// Polars
 
 /// Main
 impl XlsxEditor {
@@ -105,70 +138,36 @@ impl XlsxEditor {
             .position(|n| n == sheet_name)
             .context(format!("Sheet '{}' not found", sheet_name))?
             + 1;
-        println!("Sheet ID: {} with name {}", sheet_id, sheet_name);
         Self::open_sheet(src, sheet_id)
     }
 
     /// Appends a single row of cells to the end of the current sheet.
     ///
-    /// Each item in the `cells` iterator will be converted to a string and written as a cell.
-    /// The cell type (number or inline string) is inferred based on whether the value can be parsed as a float.
+    /// Each item in the `cells` iterator is converted into a [`CellValue`] and written as a cell.
+    /// `&str` items keep the legacy type sniffing (see `From<&str> for CellValue`).
     ///
     /// # Arguments
-    /// * `cells` - An iterator over values that can be converted to strings, representing the cells in the new row.
+    /// * `cells` - An iterator over values convertible into [`CellValue`], representing the cells in the new row.
     ///
     /// # Returns
     /// A `Result` indicating success or an `anyhow::Error` if the operation fails.
-    pub fn append_row<I, S>(&mut self, cells: I) -> anyhow::Result<()>
+    pub fn append_row<I, V>(&mut self, cells: I) -> anyhow::Result<()>
     where
-        I: IntoIterator<Item = S>,
-        S: ToString,
+        I: IntoIterator<Item = V>,
+        V: Into<CellValue>,
     {
+        ensure_sheetdata_open_close(&mut self.sheet_xml)?;
+
+        let mut cache = WriteCache::default();
         self.last_row += 1;
         let row_num = self.last_row;
-        let mut writer = quick_xml::Writer::new(Vec::new());
-
-        writer
-            .create_element("row")
-            .with_attribute(("r", row_num.to_string().as_str()))
-            .write_inner_content(|w| {
-                let mut col = b'A';
-                for val in cells {
-                    let coord = format!("{}{}", col as char, row_num);
-                    let val_str = val.to_string();
-                    let is_formula = val_str.starts_with('=');
-                    let is_number = !is_formula && val_str.parse::<f64>().is_ok();
-
-                    let mut c_elem = w.create_element("c").with_attribute(("r", coord.as_str()));
-                    if !is_number && !is_formula {
-                        c_elem = c_elem.with_attribute(("t", "inlineStr"));
-                    }
-                    c_elem.write_inner_content(|w2| {
-                        use quick_xml::events::BytesText;
-                        if is_formula {
-                            w2.create_element("f")
-                                .write_text_content(BytesText::new(&val_str[1..]))?;
-                        } else if !is_number {
-                            w2.create_element("is").write_inner_content(|w3| {
-                                let mut t = w3.create_element("t");
-                                if needs_xml_space_preserve(&val_str) {
-                                    t = t.with_attribute(("xml:space", "preserve"));
-                                }
-                                t.write_text_content(BytesText::new(&val_str))?;
-                                Ok(())
-                            })?;
-                        } else {
-                            w2.create_element("v")
-                                .write_text_content(BytesText::new(&val_str))?;
-                        }
-                        Ok(())
-                    })?;
-                    col += 1;
-                }
-                Ok(())
-            })?;
-
-        let new_row_xml = writer.into_inner();
+        let mut new_row_xml = format!(r#"<row r="{row_num}">"#).into_bytes();
+        for (col_idx, val) in cells.into_iter().enumerate() {
+            let coord = format!("{}{}", style::col_letter(col_idx as u32), row_num);
+            let cell = self.cell_xml(&coord, &val.into(), &mut cache)?;
+            new_row_xml.extend_from_slice(&cell);
+        }
+        new_row_xml.extend_from_slice(b"</row>");
 
         // ИСПРАВЛЕНО: не делаем mem::take; вставляем по позиции из текущего буфера
         let pos = memchr::memmem::rfind(&self.sheet_xml, b"</sheetData>")
@@ -181,82 +180,36 @@ impl XlsxEditor {
     /// Appends multiple rows (a table) to the end of the current sheet.
     ///
     /// This function iterates through the provided rows, and for each row, it iterates through its cells.
-    /// Each cell's value is converted to a string, and its type (number or inline string) is inferred.
+    /// Each cell's value is converted into a [`CellValue`].
     /// The new rows are then appended to the sheet's XML content.
     ///
     /// # Arguments
-    /// * `rows` - An iterator over iterators of values that can be converted to strings, representing the rows and cells of the table.
+    /// * `rows` - An iterator over iterators of values convertible into [`CellValue`], representing the rows and cells of the table.
     ///
     /// # Returns
     /// A `Result` indicating success or an `anyhow::Error` if the operation fails.
-    pub fn append_table<R, I, S>(&mut self, rows: R) -> anyhow::Result<()>
+    pub fn append_table<R, I, V>(&mut self, rows: R) -> anyhow::Result<()>
     where
         R: IntoIterator<Item = I>,
-        I: IntoIterator<Item = S>,
-        S: ToString,
+        I: IntoIterator<Item = V>,
+        V: Into<CellValue>,
     {
         ensure_sheetdata_open_close(&mut self.sheet_xml)?;
 
-        fn col_idx_to_letters(mut idx: usize) -> String {
-            let mut s = String::new();
-            loop {
-                let rem = idx % 26;
-                s.insert(0, (b'A' + rem as u8) as char);
-                if idx < 26 {
-                    break;
-                }
-                idx = idx / 26 - 1;
-            }
-            s
-        }
-
         let mut bulk_rows_xml = Vec::<u8>::new();
+        let mut cache = WriteCache::default();
 
         for row in rows {
             self.last_row += 1;
             let row_num = self.last_row;
 
-            let mut writer = quick_xml::Writer::new(Vec::new());
-            writer
-                .create_element("row")
-                .with_attribute(("r", row_num.to_string().as_str()))
-                .write_inner_content(|w| {
-                    for (col_idx, val) in row.into_iter().enumerate() {
-                        let coord = format!("{}{}", col_idx_to_letters(col_idx), row_num);
-                        let val_str = val.to_string();
-                        let is_formula = val_str.starts_with('=');
-                        let is_number = !is_formula && val_str.parse::<f64>().is_ok();
-
-                        let mut c_elem =
-                            w.create_element("c").with_attribute(("r", coord.as_str()));
-                        if !is_number && !is_formula {
-                            c_elem = c_elem.with_attribute(("t", "inlineStr"));
-                        }
-                        c_elem.write_inner_content(|w2| {
-                            use quick_xml::events::BytesText;
-                            if is_formula {
-                                w2.create_element("f")
-                                    .write_text_content(BytesText::new(&val_str[1..]))?;
-                            } else if !is_number {
-                                w2.create_element("is").write_inner_content(|w3| {
-                                    let mut t = w3.create_element("t");
-                                    if needs_xml_space_preserve(&val_str) {
-                                        t = t.with_attribute(("xml:space", "preserve"));
-                                    }
-                                    t.write_text_content(BytesText::new(&val_str))?;
-                                    Ok(())
-                                })?;
-                            } else {
-                                w2.create_element("v")
-                                    .write_text_content(BytesText::new(&val_str))?;
-                            }
-                            Ok(())
-                        })?;
-                    }
-                    Ok(())
-                })?;
-
-            bulk_rows_xml.extend_from_slice(&writer.into_inner());
+            bulk_rows_xml.extend_from_slice(format!(r#"<row r="{row_num}">"#).as_bytes());
+            for (col_idx, val) in row.into_iter().enumerate() {
+                let coord = format!("{}{}", style::col_letter(col_idx as u32), row_num);
+                let cell = self.cell_xml(&coord, &val.into(), &mut cache)?;
+                bulk_rows_xml.extend_from_slice(&cell);
+            }
+            bulk_rows_xml.extend_from_slice(b"</row>");
         }
 
         // ИСПРАВЛЕНО: считаем pos по self.sheet_xml и туда же вставляем
@@ -275,46 +228,29 @@ impl XlsxEditor {
     ///
     /// # Arguments
     /// * `start_coord` - The starting cell coordinate (e.g., "A1") where the table should begin.
-    /// * `rows` - An iterator over iterators of values that can be converted to strings, representing the rows and cells of the table.
+    /// * `rows` - An iterator over iterators of values convertible into [`CellValue`], representing the rows and cells of the table.
     ///
     /// # Returns
     /// A `Result` indicating success or an `anyhow::Error` if the operation fails.
-    pub fn append_table_at<R, I, S>(&mut self, start_coord: &str, rows: R) -> anyhow::Result<()>
+    pub fn append_table_at<R, I, V>(&mut self, start_coord: &str, rows: R) -> anyhow::Result<()>
     where
         R: IntoIterator<Item = I>,
-        I: IntoIterator<Item = S>,
-        S: ToString,
+        I: IntoIterator<Item = V>,
+        V: Into<CellValue>,
     {
         ensure_sheetdata_open_close(&mut self.sheet_xml)?;
 
-        fn col_idx_to_letters(mut idx: usize) -> String {
-            let mut s = String::new();
-            loop {
-                let rem = idx % 26;
-                s.insert(0, (b'A' + rem as u8) as char);
-                if idx < 26 {
-                    break;
-                }
-                idx = idx / 26 - 1;
-            }
-            s
-        }
-        fn letters_to_col_idx(s: &str) -> usize {
-            s.bytes().fold(0, |acc, b| {
-                acc * 26 + (b.to_ascii_uppercase() - b'A' + 1) as usize
-            }) - 1
-        }
-
         let row_start_pos = start_coord
             .find(|c: char| c.is_ascii_digit())
             .context("invalid start coordinate – no digits")?;
         let col_letters = &start_coord[..row_start_pos];
-        let start_col_idx = letters_to_col_idx(col_letters);
+        let start_col_idx = style::util::col_index(col_letters) as u32;
         let current_row_num: u32 = start_coord[row_start_pos..]
             .parse()
             .context("invalid row in start coordinate")?;
 
         let mut bulk_rows_xml = Vec::<u8>::new();
+        let mut cache = WriteCache::default();
 
         for (row_offset, row) in rows.into_iter().enumerate() {
             let abs_row = current_row_num + row_offset as u32;
@@ -322,57 +258,24 @@ impl XlsxEditor {
                 for (col_offset, val) in row.into_iter().enumerate() {
                     let coord = format!(
                         "{}{}",
-                        col_idx_to_letters(start_col_idx + col_offset),
+                        style::col_letter(start_col_idx + col_offset as u32),
                         abs_row
                     );
-                    self.set_cell(&coord, val)?;
+                    let cell = self.cell_xml(&coord, &val.into(), &mut cache)?;
+                    self.put_cell(&coord, &cell)?;
                 }
             } else {
-                let mut writer = quick_xml::Writer::new(Vec::new());
-                writer
-                    .create_element("row")
-                    .with_attribute(("r", abs_row.to_string().as_str()))
-                    .write_inner_content(|w| {
-                        for (col_offset, val) in row.into_iter().enumerate() {
-                            let coord = format!(
-                                "{}{}",
-                                col_idx_to_letters(start_col_idx + col_offset),
-                                abs_row
-                            );
-                            let val_str = val.to_string();
-                            let is_formula = val_str.starts_with('=');
-                            let is_number = !is_formula && val_str.parse::<f64>().is_ok();
-
-                            let mut c_elem =
-                                w.create_element("c").with_attribute(("r", coord.as_str()));
-                            if !is_number && !is_formula {
-                                c_elem = c_elem.with_attribute(("t", "inlineStr"));
-                            }
-                            c_elem.write_inner_content(|w2| {
-                                use quick_xml::events::BytesText;
-                                if is_formula {
-                                    w2.create_element("f")
-                                        .write_text_content(BytesText::new(&val_str[1..]))?;
-                                } else if !is_number {
-                                    w2.create_element("is").write_inner_content(|w3| {
-                                        let mut t = w3.create_element("t");
-                                        if needs_xml_space_preserve(&val_str) {
-                                            t = t.with_attribute(("xml:space", "preserve"));
-                                        }
-                                        t.write_text_content(BytesText::new(&val_str))?;
-                                        Ok(())
-                                    })?;
-                                } else {
-                                    w2.create_element("v")
-                                        .write_text_content(BytesText::new(&val_str))?;
-                                }
-                                Ok(())
-                            })?;
-                        }
-                        Ok(())
-                    })?;
-
-                bulk_rows_xml.extend_from_slice(&writer.into_inner());
+                bulk_rows_xml.extend_from_slice(format!(r#"<row r="{abs_row}">"#).as_bytes());
+                for (col_offset, val) in row.into_iter().enumerate() {
+                    let coord = format!(
+                        "{}{}",
+                        style::col_letter(start_col_idx + col_offset as u32),
+                        abs_row
+                    );
+                    let cell = self.cell_xml(&coord, &val.into(), &mut cache)?;
+                    bulk_rows_xml.extend_from_slice(&cell);
+                }
+                bulk_rows_xml.extend_from_slice(b"</row>");
                 self.last_row = abs_row;
             }
         }
@@ -385,19 +288,80 @@ impl XlsxEditor {
         Ok(())
     }
 
+    /// Writes a block given column by column, starting at a specified coordinate.
+    ///
+    /// Behaves like [`append_table_at`](Self::append_table_at), but the rows are read
+    /// straight from the columns, so column-oriented data (Arrow batches, DataFrames)
+    /// does not have to be transposed first.
+    ///
+    /// # Arguments
+    /// * `start_coord` - The starting cell coordinate (e.g., "A1") of the block.
+    /// * `columns` - The columns of the block, left to right; all must have the same length.
+    pub fn append_columns_at<C: AsRef<[CellValue]>>(
+        &mut self,
+        start_coord: &str,
+        columns: &[C],
+    ) -> Result<()> {
+        let height = columns.first().map_or(0, |c| c.as_ref().len());
+        if columns.iter().any(|c| c.as_ref().len() != height) {
+            bail!("columns passed to append_columns_at differ in length");
+        }
+        ensure_sheetdata_open_close(&mut self.sheet_xml)?;
+
+        let (start_col_idx, current_row_num) = read_part::parse_cell_ref(start_coord)?;
+        let col_names: Vec<String> = (0..columns.len() as u32)
+            .map(|c| style::col_letter(start_col_idx + c))
+            .collect();
+
+        let mut bulk_rows_xml = Vec::<u8>::new();
+        let mut cache = WriteCache::default();
+
+        for row_offset in 0..height {
+            let abs_row = current_row_num + row_offset as u32;
+            let existing = abs_row <= self.last_row;
+            if !existing {
+                bulk_rows_xml.extend_from_slice(format!(r#"<row r="{abs_row}">"#).as_bytes());
+            }
+            for (name, column) in col_names.iter().zip(columns) {
+                let coord = format!("{name}{abs_row}");
+                let cell = self.cell_xml(&coord, &column.as_ref()[row_offset], &mut cache)?;
+                if existing {
+                    self.put_cell(&coord, &cell)?;
+                } else {
+                    bulk_rows_xml.extend_from_slice(&cell);
+                }
+            }
+            if !existing {
+                bulk_rows_xml.extend_from_slice(b"</row>");
+                self.last_row = abs_row;
+            }
+        }
+
+        let pos = memchr::memmem::rfind(&self.sheet_xml, b"</sheetData>")
+            .context("</sheetData> tag not found")?;
+        self.sheet_xml.splice(pos..pos, bulk_rows_xml);
+
+        Ok(())
+    }
+
     /// Sets the value of a specific cell in the sheet.
     ///
     /// This function allows updating an existing cell or creating a new one if it doesn't exist.
-    /// The cell type (number or inline string) is inferred based on whether the value can be parsed as a float.
+    /// The cell type follows the [`CellValue`] the value converts into.
     ///
     /// # Arguments
     /// * `coord` - The cell coordinate (e.g., "A1", "B2").
-    /// * `value` - The value to set for the cell, which can be converted to a string.
+    /// * `value` - The value to set for the cell, anything convertible into [`CellValue`].
     ///
     /// # Returns
     /// A `Result` indicating success or an `anyhow::Error` if the operation fails.
-    pub fn set_cell<S: ToString>(&mut self, coord: &str, value: S) -> Result<()> {
-        use crate::files_part::needs_xml_space_preserve;
+    pub fn set_cell<V: Into<CellValue>>(&mut self, coord: &str, value: V) -> Result<()> {
+        let new_cell_xml = self.cell_xml(coord, &value.into(), &mut WriteCache::default())?;
+        self.put_cell(coord, &new_cell_xml)
+    }
+
+    // вставляет готовый <c> на место ячейки `coord` (или в новый ряд)
+    fn put_cell(&mut self, coord: &str, new_cell_xml: &[u8]) -> Result<()> {
         // row number
         let row_start = coord
             .find(|c: char| c.is_ascii_digit())
@@ -406,40 +370,6 @@ impl XlsxEditor {
             .parse()
             .context("invalid row number in cell coordinate")?;
 
-        let val_str = value.to_string();
-        let is_formula = val_str.starts_with('=');
-        let is_number = !is_formula && val_str.parse::<f64>().is_ok();
-
-        // → собрать XML ячейки
-        let mut cell_writer = Writer::new(Vec::new());
-        {
-            let mut c = cell_writer.create_element("c").with_attribute(("r", coord));
-            if !is_number && !is_formula {
-                c = c.with_attribute(("t", "inlineStr"));
-            }
-            c.write_inner_content(|w2| {
-                use quick_xml::events::BytesText;
-                if is_formula {
-                    w2.create_element("f")
-                        .write_text_content(BytesText::new(&val_str[1..]))?;
-                } else if !is_number {
-                    w2.create_element("is").write_inner_content(|w3| {
-                        let mut t = w3.create_element("t");
-                        if needs_xml_space_preserve(&val_str) {
-                            t = t.with_attribute(("xml:space", "preserve"));
-                        }
-                        t.write_text_content(BytesText::new(&val_str))?;
-                        Ok(())
-                    })?;
-                } else {
-                    w2.create_element("v")
-                        .write_text_content(BytesText::new(&val_str))?;
-                }
-                Ok(())
-            })?;
-        }
-        let new_cell_xml = cell_writer.into_inner();
-
         // ——— устойчивый поиск ряда r="row_num"
         let src = &self.sheet_xml;
         let find_row = memmem::Finder::new(b"<row ");
@@ -457,27 +387,26 @@ impl XlsxEditor {
                 None => break,
             };
             // r=".."
-            if let Some(rpos) = find_bytes_from(src, b" r=\"", rs) {
-                if rpos < tag_end {
-                    let v0 = rpos + 4;
-                    if let Some(v1) = find_bytes_from(src, b"\"", v0) {
-                        if let Ok(r) = std::str::from_utf8(&src[v0..v1])
-                            .unwrap_or("")
-                            .parse::<u32>()
-                        {
-                            if r == row_num {
-                                let close_rel = match find_row_close.find(&src[tag_end + 1..]) {
-                                    Some(p) => p,
-                                    None => break,
-                                };
-                                let close_end = tag_end + 1 + close_rel + "</row>".len();
-                                target_row = Some((rs, tag_end, close_end));
-                                break;
-                            } else if insert_before_row_with_r_gt.is_none() && r > row_num {
-                                insert_before_row_with_r_gt = Some(rs);
-                                // не break — возможно ещё встретим точное совпадение роу выше
-                            }
-                        }
+            if let Some(rpos) = find_bytes_from(src, b" r=\"", rs)
+                && rpos < tag_end
+            {
+                let v0 = rpos + 4;
+                if let Some(v1) = find_bytes_from(src, b"\"", v0)
+                    && let Ok(r) = std::str::from_utf8(&src[v0..v1])
+                        .unwrap_or("")
+                        .parse::<u32>()
+                {
+                    if r == row_num {
+                        let close_rel = match find_row_close.find(&src[tag_end + 1..]) {
+                            Some(p) => p,
+                            None => break,
+                        };
+                        let close_end = tag_end + 1 + close_rel + "</row>".len();
+                        target_row = Some((rs, tag_end, close_end));
+                        break;
+                    } else if insert_before_row_with_r_gt.is_none() && r > row_num {
+                        insert_before_row_with_r_gt = Some(rs);
+                        // не break — возможно ещё встретим точное совпадение роу выше
                     }
                 }
             }
@@ -519,25 +448,25 @@ impl XlsxEditor {
                 }
 
                 // r="..."
-                if let Some(rpos) = find_bytes_from(&row_slice, b" r=\"", cpos) {
-                    if rpos < tag_end {
-                        let v0 = rpos + 4;
-                        if let Some(v1) = find_bytes_from(&row_slice, b"\"", v0) {
-                            if &row_slice[v0..v1] == coord.as_bytes() {
-                                // полные границы ячейки: self-closing или с </c>
-                                let self_closing = tag_end > cpos && row_slice[tag_end - 1] == b'/';
-                                let cell_end = if self_closing {
-                                    tag_end + 1
-                                } else {
-                                    let cc = find_bytes_from(&row_slice, b"</c>", tag_end + 1)
-                                        .context("</c> missing")?;
-                                    cc + 4
-                                };
-                                row_slice.splice(cpos..cell_end, new_cell_xml.iter().copied());
-                                cell_found = true;
-                                break;
-                            }
-                        }
+                if let Some(rpos) = find_bytes_from(&row_slice, b" r=\"", cpos)
+                    && rpos < tag_end
+                {
+                    let v0 = rpos + 4;
+                    if let Some(v1) = find_bytes_from(&row_slice, b"\"", v0)
+                        && &row_slice[v0..v1] == coord.as_bytes()
+                    {
+                        // полные границы ячейки: self-closing или с </c>
+                        let self_closing = tag_end > cpos && row_slice[tag_end - 1] == b'/';
+                        let cell_end = if self_closing {
+                            tag_end + 1
+                        } else {
+                            let cc = find_bytes_from(&row_slice, b"</c>", tag_end + 1)
+                                .context("</c> missing")?;
+                            cc + 4
+                        };
+                        row_slice.splice(cpos..cell_end, new_cell_xml.iter().copied());
+                        cell_found = true;
+                        break;
                     }
                 }
                 j = tag_end + 1;
@@ -560,7 +489,7 @@ impl XlsxEditor {
             new_row.extend_from_slice(b"<row r=\"");
             new_row.extend_from_slice(row_num.to_string().as_bytes());
             new_row.extend_from_slice(b"\">");
-            new_row.extend_from_slice(&new_cell_xml);
+            new_row.extend_from_slice(new_cell_xml);
             new_row.extend_from_slice(b"</row>");
 
             let pos = insert_before_row_with_r_gt.unwrap_or_else(|| {
@@ -577,8 +506,142 @@ impl XlsxEditor {
     }
 }
 
+// date1904 и стили дат – один раз на вызов записи, а не на каждую ячейку
+#[derive(Default)]
+struct WriteCache {
+    date1904: Option<bool>,
+    date_styles: HashMap<&'static str, u32>,
+}
+
+impl XlsxEditor {
+    fn cached_date1904(&self, cache: &mut WriteCache) -> bool {
+        *cache.date1904.get_or_insert_with(|| self.date1904())
+    }
+
+    /// Serialises a single `<c>` element. Text goes inline or into the shared strings
+    /// table according to `string_storage`; non-finite numbers become `#NUM!`.
+    fn cell_xml(
+        &mut self,
+        coord: &str,
+        value: &CellValue,
+        cache: &mut WriteCache,
+    ) -> Result<Vec<u8>> {
+        use quick_xml::events::BytesText;
+
+        let mut writer = Writer::new(Vec::new());
+        let c = writer.create_element("c").with_attribute(("r", coord));
+        match value {
+            CellValue::Blank => {
+                c.write_empty()?;
+            }
+            CellValue::Number(n) if !n.is_finite() => {
+                c.with_attribute(("t", "e")).write_inner_content(|w2| {
+                    w2.create_element("v")
+                        .write_text_content(BytesText::new("#NUM!"))?;
+                    Ok(())
+                })?;
+            }
+            CellValue::Number(n) => {
+                c.write_inner_content(|w2| {
+                    w2.create_element("v")
+                        .write_text_content(BytesText::new(&n.to_string()))?;
+                    Ok(())
+                })?;
+            }
+            CellValue::Date(d) => {
+                let serial = d
+                    .to_serial(self.cached_date1904(cache))
+                    .with_context(|| format!("cell {coord}"))?;
+                let sid = match cache.date_styles.get(d.number_format()) {
+                    Some(&sid) => sid,
+                    None => {
+                        let sid = self.number_format_style(d.number_format())?;
+                        cache.date_styles.insert(d.number_format(), sid);
+                        sid
+                    }
+                };
+                c.with_attribute(("s", sid.to_string().as_str()))
+                    .write_inner_content(|w2| {
+                        w2.create_element("v")
+                            .write_text_content(BytesText::new(&serial.to_string()))?;
+                        Ok(())
+                    })?;
+            }
+            CellValue::Bool(b) => {
+                c.with_attribute(("t", "b")).write_inner_content(|w2| {
+                    w2.create_element("v")
+                        .write_text_content(BytesText::new(if *b { "1" } else { "0" }))?;
+                    Ok(())
+                })?;
+            }
+            CellValue::Error(e) => {
+                c.with_attribute(("t", "e")).write_inner_content(|w2| {
+                    w2.create_element("v")
+                        .write_text_content(BytesText::new(e))?;
+                    Ok(())
+                })?;
+            }
+            CellValue::Text(s) if self.string_storage == StringStorage::Shared => {
+                let idx = self.shared_strings_mut()?.intern(s);
+                c.with_attribute(("t", "s")).write_inner_content(|w2| {
+                    w2.create_element("v")
+                        .write_text_content(BytesText::new(&idx.to_string()))?;
+                    Ok(())
+                })?;
+            }
+            CellValue::Text(s) => {
+                c.with_attribute(("t", "inlineStr"))
+                    .write_inner_content(|w2| {
+                        w2.create_element("is").write_inner_content(|w3| {
+                            let mut t = w3.create_element("t");
+                            if needs_xml_space_preserve(s) {
+                                t = t.with_attribute(("xml:space", "preserve"));
+                            }
+                            t.write_text_content(BytesText::new(s))?;
+                            Ok(())
+                        })?;
+                        Ok(())
+                    })?;
+            }
+            CellValue::Formula { formula, cached } => {
+                // тип ячейки задаётся закэшированным результатом
+                let (t, v) = match cached.as_deref() {
+                    Some(CellValue::Number(n)) if n.is_finite() => (None, Some(n.to_string())),
+                    Some(CellValue::Date(d)) => (
+                        None,
+                        Some(d.to_serial(self.cached_date1904(cache))?.to_string()),
+                    ),
+                    Some(CellValue::Text(s)) => (Some("str"), Some(s.clone())),
+                    Some(CellValue::Bool(b)) => (Some("b"), Some((*b as u8).to_string())),
+                    Some(CellValue::Error(e)) => (Some("e"), Some(e.clone())),
+                    _ => (None, None),
+                };
+                let c = match t {
+                    Some(t) => c.with_attribute(("t", t)),
+                    None => c,
+                };
+                c.write_inner_content(|w2| {
+                    w2.create_element("f")
+                        .write_text_content(BytesText::new(formula))?;
+                    if let Some(v) = &v {
+                        w2.create_element("v")
+                            .write_text_content(BytesText::new(v))?;
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+        Ok(writer.into_inner())
+    }
+}
+
 pub fn scan<P: AsRef<Path>>(src: P) -> Result<Vec<String>> {
-    let mut zip = zip::ZipArchive::new(File::open(src)?)?;
+    scan_reader(File::open(src)?)
+}
+
+/// Sheet names of a workbook read from any seekable reader, e.g. `Cursor<&[u8]>`.
+pub fn scan_reader<R: Read + Seek>(reader: R) -> Result<Vec<String>> {
+    let mut zip = zip::ZipArchive::new(reader)?;
     let mut wb = zip
         .by_name("xl/workbook.xml")
         .context("workbook.xml not found")?;
@@ -595,8 +658,12 @@ pub fn scan<P: AsRef<Path>>(src: P) -> Result<Vec<String>> {
         match ev {
             Event::Empty(ref e) | Event::Start(ref e) if e.name().as_ref() == b"sheet" => {
                 if let Some(n) = e.attributes().with_checks(false).flatten().find_map(|a| {
-                    (a.key.as_ref() == b"name")
-                        .then(|| String::from_utf8_lossy(&a.value).into_owned())
+                    (a.key.as_ref() == b"name").then(|| {
+                        a.unescape_value().map_or_else(
+                            |_| String::from_utf8_lossy(&a.value).into_owned(),
+                            |n| n.into_owned(),
+                        )
+                    })
                 }) {
                     names.push(n);
                 }
@@ -608,42 +675,6 @@ pub fn scan<P: AsRef<Path>>(src: P) -> Result<Vec<String>> {
     Ok(names)
 }
 
-impl XlsxEditor {
-    pub fn merge_cells(&mut self, range: &str) -> Result<()> {
-        // 1. позиция после </sheetData>
-        let sd_end = find_bytes(&self.sheet_xml, b"</sheetData>")
-            .context("</sheetData> not found")?
-            + "</sheetData>".len();
-
-        let (insert_pos, created) = if let Some(pos) = find_bytes(&self.sheet_xml, b"<mergeCells") {
-            // уже есть блок
-            bump_count(&mut self.sheet_xml, b"<mergeCells", b"count=\"")?;
-            let end = find_bytes_from(&self.sheet_xml, b"</mergeCells>", pos)
-                .context("</mergeCells> not found")?;
-            (end, false)
-        } else {
-            // нет блока – создаём
-            let tpl = br#"<mergeCells count="0"></mergeCells>"#;
-            self.sheet_xml.splice(sd_end..sd_end, tpl.iter().copied());
-            (sd_end + tpl.len() - "</mergeCells>".len(), true)
-        };
-
-        // 2. сам <mergeCell>
-        let tag = format!(r#"<mergeCell ref="{}"/>"#, range);
-        self.sheet_xml
-            .splice(insert_pos..insert_pos, tag.as_bytes().iter().copied());
-
-        // 3. правим count (если блок создан только что)
-        if created {
-            bump_count(&mut self.sheet_xml, b"<mergeCells", b"count=\"")?;
-        }
-        Ok(())
-    }
-}
-
-fn find_bytes(hay: &[u8], needle: &[u8]) -> Option<usize> {
-    hay.windows(needle.len()).position(|w| w == needle)
-}
 fn find_bytes_from(hay: &[u8], needle: &[u8], start: usize) -> Option<usize> {
     hay[start..]
         .windows(needle.len())
@@ -651,20 +682,6 @@ fn find_bytes_from(hay: &[u8], needle: &[u8], start: usize) -> Option<usize> {
         .map(|p| p + start)
 }
 
-fn bump_count(xml: &mut Vec<u8>, tag: &[u8], attr: &[u8]) -> Result<()> {
-    if let Some(pos) = find_bytes(xml, tag) {
-        if let Some(a) = find_bytes_from(xml, attr, pos) {
-            let start = a + attr.len();
-            let end = find_bytes_from(xml, b"\"", start).unwrap();
-            let mut num: u32 = std::str::from_utf8(&xml[start..end])?.parse()?;
-            num += 1;
-            xml.splice(start..end, num.to_string().as_bytes().iter().copied());
-            return Ok(());
-        }
-    }
-    Err(anyhow::anyhow!("attribute count not found"))
-}
-
 fn ensure_sheetdata_open_close(xml: &mut Vec<u8>) -> Result<()> {
     const SELF_CLOSING: &[u8] = b"<sheetData/>";
     if let Some(pos) = memchr::memmem::find(xml, SELF_CLOSING) {
@@ -0,0 +1,180 @@
+//! merge.rs – объединённые ячейки: список, объединение с проверкой пересечений, разъединение
+
+use anyhow::{Result, bail};
+use memchr::memmem;
+use quick_xml::{
+    Reader, Writer,
+    events::{BytesStart, Event},
+};
+
+use crate::{
+    XlsxEditor,
+    files_part::{worksheet_element_range, worksheet_insert_pos},
+    find_bytes_from,
+    read_part::parse_cell_ref,
+    shift::{attr, skip_element},
+    style::util::col_letter,
+};
+
+/// Options for [`XlsxEditor::merge_cells_with`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MergeOptions {
+    /// Remove existing merges that overlap the new range instead of failing.
+    pub replace_overlapping: bool,
+    /// Clear the values of all cells except the top-left one, as Excel does.
+    /// Cell styles are kept.
+    pub clear_non_anchor: bool,
+}
+
+// (c0, r0, c1, r1): столбцы 0-based, строки 1-based
+pub(crate) type Rect = (u32, u32, u32, u32);
+
+pub(crate) fn parse_rect(range: &str) -> Result<Rect> {
+    let (a, b) = range.split_once(':').unwrap_or((range, range));
+    let (c0, r0) = parse_cell_ref(a)?;
+    let (c1, r1) = parse_cell_ref(b)?;
+    Ok((c0.min(c1), r0.min(r1), c0.max(c1), r0.max(r1)))
+}
+
+fn format_rect((c0, r0, c1, r1): Rect) -> String {
+    format!("{}{r0}:{}{r1}", col_letter(c0), col_letter(c1))
+}
+
+pub(crate) fn overlaps(a: Rect, b: Rect) -> bool {
+    a.0 <= b.2 && b.0 <= a.2 && a.1 <= b.3 && b.1 <= a.3
+}
+
+impl XlsxEditor {
+    /// Returns the merged ranges of the current sheet (`"A1:C3"`) in file order.
+    pub fn merged_ranges(&self) -> Result<Vec<String>> {
+        let mut out = Vec::new();
+        let needle = b"<mergeCell ";
+        let mut i = 0;
+        while let Some(off) = memmem::find(&self.sheet_xml[i..], needle) {
+            let start = i + off;
+            let end = find_bytes_from(&self.sheet_xml, b">", start).unwrap_or(self.sheet_xml.len());
+            let tag = &self.sheet_xml[start..end];
+            if let Some(a) = memmem::find(tag, b" ref=\"") {
+                let v = &tag[a + 6..];
+                if let Some(q) = v.iter().position(|&b| b == b'"') {
+                    out.push(String::from_utf8_lossy(&v[..q]).into_owned());
+                }
+            }
+            i = end;
+        }
+        Ok(out)
+    }
+
+    /// Merges `range` (e.g. `"A1:C3"`) on the current sheet.
+    ///
+    /// Fails if the range overlaps an existing merge; use
+    /// [`XlsxEditor::merge_cells_with`] to replace overlapping merges instead.
+    pub fn merge_cells(&mut self, range: &str) -> Result<()> {
+        self.merge_cells_with(range, MergeOptions::default())
+    }
+
+    /// Merges `range` on the current sheet.
+    ///
+    /// # Arguments
+    /// * `range` - The range to merge, at least two cells (e.g. `"A1:C3"`).
+    /// * `opts` - How to treat overlapping merges and the values under the merge.
+    pub fn merge_cells_with(&mut self, range: &str, opts: MergeOptions) -> Result<()> {
+        let rect = parse_rect(range)?;
+        if rect.0 == rect.2 && rect.1 == rect.3 {
+            bail!("merge range `{range}` must span at least two cells");
+        }
+        let mut merges = self.merged_ranges()?;
+        let hit: Vec<String> = merges
+            .iter()
+            .filter(|m| parse_rect(m).is_ok_and(|m| overlaps(m, rect)))
+            .cloned()
+            .collect();
+        if !hit.is_empty() && !opts.replace_overlapping {
+            bail!(
+                "merge range `{range}` overlaps existing merge(s) {}",
+                hit.join(", ")
+            );
+        }
+        merges.retain(|m| !hit.contains(m));
+        merges.push(format_rect(rect));
+        self.write_merges(&merges)?;
+
+        if opts.clear_non_anchor {
+            self.sheet_xml = clear_cells(&self.sheet_xml, rect, (rect.0, rect.1))?;
+        }
+        Ok(())
+    }
+
+    /// Removes every merge on the current sheet that intersects `range`.
+    /// Cell values are left as they are.
+    pub fn unmerge_cells(&mut self, range: &str) -> Result<&mut Self> {
+        let rect = parse_rect(range)?;
+        let mut merges = self.merged_ranges()?;
+        let before = merges.len();
+        merges.retain(|m| !parse_rect(m).is_ok_and(|m| overlaps(m, rect)));
+        if merges.len() != before {
+            self.write_merges(&merges)?;
+        }
+        Ok(self)
+    }
+
+    // пересобирает <mergeCells>; пустой список – блок удаляется
+    fn write_merges(&mut self, merges: &[String]) -> Result<()> {
+        if let Some((start, end)) = worksheet_element_range(&self.sheet_xml, "mergeCells") {
+            self.sheet_xml.drain(start..end);
+        }
+        if merges.is_empty() {
+            return Ok(());
+        }
+        let mut block = format!(r#"<mergeCells count="{}">"#, merges.len());
+        for m in merges {
+            block.push_str(&format!(r#"<mergeCell ref="{m}"/>"#));
+        }
+        block.push_str("</mergeCells>");
+        let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "mergeCells") else {
+            bail!("</worksheet> not found");
+        };
+        self.sheet_xml.splice(pos..pos, block.into_bytes());
+        Ok(())
+    }
+}
+
+// очищает значения ячеек прямоугольника (кроме `keep`), оставляя стиль
+fn clear_cells(xml: &[u8], rect: Rect, keep: (u32, u32)) -> Result<Vec<u8>> {
+    let mut rdr = Reader::from_reader(xml);
+    let mut wr = Writer::new(Vec::with_capacity(xml.len()));
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        let ev = rdr.read_event_into(&mut buf)?;
+        let (e, empty) = match &ev {
+            Event::Start(e) if e.name().as_ref() == b"c" => (e, false),
+            Event::Empty(e) if e.name().as_ref() == b"c" => (e, true),
+            Event::Eof => break,
+            _ => {
+                wr.write_event(ev.borrow())?;
+                continue;
+            }
+        };
+        let pos = attr(e, b"r").and_then(|r| parse_cell_ref(&r).ok());
+        let inside = pos.is_some_and(|(c, r)| {
+            c >= rect.0 && c <= rect.2 && r >= rect.1 && r <= rect.3 && (c, r) != keep
+        });
+        if !inside {
+            wr.write_event(ev.borrow())?;
+            continue;
+        }
+        let mut tag = BytesStart::new("c");
+        tag.extend_attributes(
+            e.attributes()
+                .with_checks(false)
+                .flatten()
+                .filter(|a| matches!(a.key.as_ref(), b"r" | b"s")),
+        );
+        if !empty {
+            skip_element(&mut rdr, &mut buf)?;
+        }
+        wr.write_event(Event::Empty(tag))?;
+    }
+    Ok(wr.into_inner())
+}
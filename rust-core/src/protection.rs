@@ -0,0 +1,281 @@
+//! protection.rs – защита листа (`<sheetProtection>`) и книги (`<workbookProtection>`)
+
+use anyhow::{Result, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use memchr::memmem;
+use sha2::{Digest, Sha512};
+
+use crate::{
+    XlsxEditor,
+    files_part::{worksheet_element_range, worksheet_insert_pos},
+    find_bytes_from,
+};
+
+// как у Excel
+const SPIN_COUNT: u32 = 100_000;
+
+/// What users may still do on a protected sheet.
+///
+/// The default matches Excel's "Protect Sheet" dialog: only selecting cells
+/// is allowed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SheetProtection {
+    pub select_locked_cells: bool,
+    pub select_unlocked_cells: bool,
+    pub format_cells: bool,
+    pub format_columns: bool,
+    pub format_rows: bool,
+    pub insert_columns: bool,
+    pub insert_rows: bool,
+    pub insert_hyperlinks: bool,
+    pub delete_columns: bool,
+    pub delete_rows: bool,
+    pub sort: bool,
+    pub auto_filter: bool,
+    pub pivot_tables: bool,
+    pub edit_objects: bool,
+    pub edit_scenarios: bool,
+    /// Store the password as the legacy 16-bit hash (`password="XXXX"`)
+    /// instead of salted SHA-512. Only for very old readers.
+    pub legacy_hash: bool,
+}
+
+impl Default for SheetProtection {
+    fn default() -> Self {
+        Self {
+            select_locked_cells: true,
+            select_unlocked_cells: true,
+            format_cells: false,
+            format_columns: false,
+            format_rows: false,
+            insert_columns: false,
+            insert_rows: false,
+            insert_hyperlinks: false,
+            delete_columns: false,
+            delete_rows: false,
+            sort: false,
+            auto_filter: false,
+            pivot_tables: false,
+            edit_objects: false,
+            edit_scenarios: false,
+            legacy_hash: false,
+        }
+    }
+}
+
+impl SheetProtection {
+    // в схеме атрибут = «действие запрещено»; пишем только отличия от умолчаний
+    fn to_xml(self, password: Option<&str>) -> Result<String> {
+        let mut s = String::from("<sheetProtection");
+        if let Some(pw) = password {
+            s.push_str(&password_attrs(pw, self.legacy_hash, "")?);
+        }
+        s.push_str(r#" sheet="1""#);
+        // по умолчанию false = разрешено
+        for (name, allowed) in [
+            ("objects", self.edit_objects),
+            ("scenarios", self.edit_scenarios),
+        ] {
+            if !allowed {
+                s.push_str(&format!(r#" {name}="1""#));
+            }
+        }
+        // по умолчанию true = запрещено
+        for (name, allowed) in [
+            ("formatCells", self.format_cells),
+            ("formatColumns", self.format_columns),
+            ("formatRows", self.format_rows),
+            ("insertColumns", self.insert_columns),
+            ("insertRows", self.insert_rows),
+            ("insertHyperlinks", self.insert_hyperlinks),
+            ("deleteColumns", self.delete_columns),
+            ("deleteRows", self.delete_rows),
+        ] {
+            if allowed {
+                s.push_str(&format!(r#" {name}="0""#));
+            }
+        }
+        if !self.select_locked_cells {
+            s.push_str(r#" selectLockedCells="1""#);
+        }
+        for (name, allowed) in [
+            ("sort", self.sort),
+            ("autoFilter", self.auto_filter),
+            ("pivotTables", self.pivot_tables),
+        ] {
+            if allowed {
+                s.push_str(&format!(r#" {name}="0""#));
+            }
+        }
+        if !self.select_unlocked_cells {
+            s.push_str(r#" selectUnlockedCells="1""#);
+        }
+        s.push_str("/>");
+        Ok(s)
+    }
+}
+
+/// Legacy 16-bit Excel password hash, as uppercase hex (`"83AF"` for `"password"`).
+///
+/// Like Excel, only the first 15 characters count; each contributes its low byte
+/// (the high byte if the low one is zero).
+pub(crate) fn legacy_password_hash(password: &str) -> String {
+    // циклический сдвиг влево в 15 битах
+    let rotl = |v: u16| ((v >> 14) & 1) | ((v << 1) & 0x7fff);
+    let bytes: Vec<u16> = password
+        .encode_utf16()
+        .take(15)
+        .map(|u| if u & 0xff != 0 { u & 0xff } else { u >> 8 })
+        .collect();
+    let mut hash = 0u16;
+    for &b in bytes.iter().rev() {
+        hash = rotl(hash) ^ b;
+    }
+    hash = rotl(hash) ^ bytes.len() as u16 ^ 0xCE4B;
+    format!("{hash:X}")
+}
+
+// ECMA-376 Agile: H0 = SHA512(salt + UTF-16LE(pw)), Hn = SHA512(Hn-1 + LE32(n))
+pub(crate) fn sha512_password_hash(password: &str, salt: &[u8], spin: u32) -> Vec<u8> {
+    let mut h = Sha512::new();
+    h.update(salt);
+    for u in password.encode_utf16() {
+        h.update(u.to_le_bytes());
+    }
+    let mut hash = h.finalize();
+    for i in 0..spin {
+        let mut h = Sha512::new();
+        h.update(hash);
+        h.update(i.to_le_bytes());
+        hash = h.finalize();
+    }
+    hash.to_vec()
+}
+
+// атрибуты пароля; `prefix` = "workbook" для книги (workbookHashValue и т. п.)
+fn password_attrs(password: &str, legacy: bool, prefix: &str) -> Result<String> {
+    if legacy {
+        let name = if prefix.is_empty() {
+            "password"
+        } else {
+            "workbookPassword"
+        };
+        return Ok(format!(r#" {name}="{}""#, legacy_password_hash(password)));
+    }
+    let mut salt = [0u8; 16];
+    if let Err(e) = getrandom::fill(&mut salt) {
+        bail!("failed to generate password salt: {e}");
+    }
+    let hash = sha512_password_hash(password, &salt, SPIN_COUNT);
+    let attr = |name: &str| {
+        if prefix.is_empty() {
+            // algorithmName / hashValue / ...
+            let mut c = name.chars();
+            c.next()
+                .map(|f| f.to_lowercase().chain(c).collect())
+                .unwrap_or_default()
+        } else {
+            format!("{prefix}{name}")
+        }
+    };
+    Ok(format!(
+        r#" {}="SHA-512" {}="{}" {}="{}" {}="{SPIN_COUNT}""#,
+        attr("AlgorithmName"),
+        attr("HashValue"),
+        STANDARD.encode(hash),
+        attr("SaltValue"),
+        STANDARD.encode(salt),
+        attr("SpinCount"),
+    ))
+}
+
+impl XlsxEditor {
+    /// Protects the current sheet, replacing any existing protection.
+    ///
+    /// # Arguments
+    /// * `options` - Which actions stay allowed on the protected sheet.
+    /// * `password` - Optional password; hashed with salted SHA-512 unless
+    ///   `options.legacy_hash` is set.
+    pub fn protect_sheet(
+        &mut self,
+        options: &SheetProtection,
+        password: Option<&str>,
+    ) -> Result<&mut Self> {
+        let tag = options.to_xml(password)?;
+        self.unprotect_sheet()?;
+        let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "sheetProtection") else {
+            bail!("</worksheet> not found");
+        };
+        self.sheet_xml.splice(pos..pos, tag.into_bytes());
+        Ok(self)
+    }
+
+    /// Removes the protection of the current sheet (no-op if it is not protected).
+    pub fn unprotect_sheet(&mut self) -> Result<&mut Self> {
+        if let Some((start, end)) = worksheet_element_range(&self.sheet_xml, "sheetProtection") {
+            self.sheet_xml.drain(start..end);
+        }
+        Ok(self)
+    }
+
+    /// Returns `true` if the current sheet has a `<sheetProtection>` element.
+    pub fn is_sheet_protected(&self) -> bool {
+        worksheet_element_range(&self.sheet_xml, "sheetProtection").is_some()
+    }
+
+    /// Locks the workbook structure and/or windows, replacing any existing
+    /// workbook protection.
+    ///
+    /// # Arguments
+    /// * `lock_structure` - Forbid adding, deleting, renaming and moving sheets.
+    /// * `lock_windows` - Forbid resizing and moving workbook windows.
+    /// * `password` - Optional password (salted SHA-512).
+    /// * `legacy_hash` - Store the password as the legacy 16-bit hash instead.
+    pub fn protect_workbook(
+        &mut self,
+        lock_structure: bool,
+        lock_windows: bool,
+        password: Option<&str>,
+        legacy_hash: bool,
+    ) -> Result<&mut Self> {
+        let mut tag = String::from("<workbookProtection");
+        if let Some(pw) = password {
+            tag.push_str(&password_attrs(pw, legacy_hash, "workbook")?);
+        }
+        if lock_structure {
+            tag.push_str(r#" lockStructure="1""#);
+        }
+        if lock_windows {
+            tag.push_str(r#" lockWindows="1""#);
+        }
+        tag.push_str("/>");
+
+        self.unprotect_workbook()?;
+        // CT_Workbook: fileVersion, fileSharing, workbookPr, workbookProtection, bookViews, sheets…
+        let xml = &self.workbook_xml;
+        let pos = ["<bookViews", "<sheets"]
+            .iter()
+            .find_map(|t| memmem::find(xml, t.as_bytes()));
+        let Some(pos) = pos else {
+            bail!("<sheets> not found in workbook.xml");
+        };
+        self.workbook_xml.splice(pos..pos, tag.into_bytes());
+        Ok(self)
+    }
+
+    /// Removes workbook structure/window protection (no-op if there is none).
+    pub fn unprotect_workbook(&mut self) -> Result<&mut Self> {
+        if let Some(start) = memmem::find(&self.workbook_xml, b"<workbookProtection")
+            && let Some(head_end) = find_bytes_from(&self.workbook_xml, b">", start)
+        {
+            let end = if self.workbook_xml[head_end - 1] == b'/' {
+                head_end + 1
+            } else {
+                find_bytes_from(&self.workbook_xml, b"</workbookProtection>", head_end)
+                    .map_or(head_end + 1, |p| p + "</workbookProtection>".len())
+            };
+            self.workbook_xml.drain(start..end);
+        }
+        Ok(self)
+    }
+}
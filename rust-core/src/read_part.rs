@@ -1,6 +1,19 @@
-use crate::XlsxEditor;
-use anyhow::{Result, bail};
-use quick_xml::{Reader, events::Event};
+use crate::{
+    CellValue, DateValue, XlsxEditor,
+    files_part::{sheet_part_path, workbook_part_path},
+    shared_strings::SST_REL_TYPE,
+    style::{DateFormatKind, col_letter, util::col_index},
+};
+use anyhow::{Context, Result, bail};
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
 
 impl XlsxEditor {
     /// Returns the last non-empty row index for the specified column or columns.
@@ -115,3 +128,546 @@ impl XlsxEditor {
         Ok(per_col_last)
     }
 }
+
+/// Typed read API
+impl XlsxEditor {
+    /// Returns the typed value of a single cell such as "B7".
+    ///
+    /// Shared strings (`t="s"`) are resolved through `xl/sharedStrings.xml`; a missing cell
+    /// yields `CellValue::Blank`.
+    pub fn get_cell(&self, coord: &str) -> Result<CellValue> {
+        let mut grid = self.get_range(coord)?;
+        Ok(grid
+            .pop()
+            .and_then(|mut row| row.pop())
+            .unwrap_or(CellValue::Blank))
+    }
+
+    /// Returns the typed values of a rectangular range like "A1:D100" in row-major order.
+    ///
+    /// The result always has `rows × columns` entries, `grid[0][0]` being the top-left
+    /// corner; cells absent from the sheet are reported as `CellValue::Blank`. The whole
+    /// rectangle is allocated, so size whole-sheet reads with [`used_range`](Self::used_range).
+    pub fn get_range(&self, range: &str) -> Result<Vec<Vec<CellValue>>> {
+        self.read_grid(range, false)
+    }
+
+    /// Same as [`get_range`](Self::get_range), but numbers whose cell number format is a
+    /// date or time format come back as `CellValue::Date`.
+    pub fn get_range_with_dates(&self, range: &str) -> Result<Vec<Vec<CellValue>>> {
+        self.read_grid(range, true)
+    }
+
+    /// Smallest range like "B2:F40" covering every non-blank cell, `None` for an empty sheet.
+    pub fn used_range(&self) -> Result<Option<String>> {
+        let mut bounds: Option<(u32, u32, u32, u32)> = None;
+        for row in self.rows()? {
+            let (r, cells) = row?;
+            for (c, _) in cells {
+                bounds = Some(match bounds {
+                    None => (c, r, c, r),
+                    Some((c0, r0, c1, r1)) => (c0.min(c), r0.min(r), c1.max(c), r1.max(r)),
+                });
+            }
+        }
+        Ok(bounds
+            .map(|(c0, r0, c1, r1)| format!("{}{}:{}{}", col_letter(c0), r0, col_letter(c1), r1)))
+    }
+
+    fn read_grid(&self, range: &str, dates: bool) -> Result<Vec<Vec<CellValue>>> {
+        let (c0, r0, c1, r1) = match range.split_once(':') {
+            Some((a, b)) => {
+                let (ca, ra) = parse_cell_ref(a)?;
+                let (cb, rb) = parse_cell_ref(b)?;
+                (ca.min(cb), ra.min(rb), ca.max(cb), ra.max(rb))
+            }
+            None => {
+                let (c, r) = parse_cell_ref(range)?;
+                (c, r, c, r)
+            }
+        };
+        let (width, height) = ((c1 - c0 + 1) as usize, (r1 - r0 + 1) as usize);
+        let mut grid = vec![vec![CellValue::Blank; width]; height];
+
+        let sst: &[String] = if memchr::memmem::find(&self.sheet_xml, b" t=\"s\"").is_some() {
+            self.shared_strings()?.strings()
+        } else {
+            &[]
+        };
+        let date_styles = if dates {
+            self.date_style_kinds()?
+        } else {
+            Vec::new()
+        };
+        let date1904 = self.date1904();
+
+        let mut rdr = Reader::from_reader(self.sheet_xml.as_slice());
+        let mut buf = Vec::new();
+        let mut cur_row = 0u32;
+        let mut next_col = 0u32;
+
+        loop {
+            let (cell, has_body) = match rdr.read_event_into(&mut buf)? {
+                Event::Start(ref e) | Event::Empty(ref e) if e.name().as_ref() == b"row" => {
+                    cur_row = attr_value(e, b"r")
+                        .and_then(|v| v.parse().ok())
+                        .unwrap_or(cur_row + 1);
+                    next_col = 0;
+                    if cur_row > r1 {
+                        break;
+                    }
+                    buf.clear();
+                    continue;
+                }
+                Event::Start(ref e) if e.name().as_ref() == b"c" => (cell_head(e), true),
+                Event::Empty(ref e) if e.name().as_ref() == b"c" => (cell_head(e), false),
+                Event::Eof => break,
+                _ => {
+                    buf.clear();
+                    continue;
+                }
+            };
+            buf.clear();
+
+            let (col, row) = cell.coord.unwrap_or((next_col, cur_row));
+            next_col = col + 1;
+            let inside = (c0..=c1).contains(&col) && (r0..=r1).contains(&row);
+
+            if !inside {
+                if has_body {
+                    rdr.read_to_end_into(quick_xml::name::QName(b"c"), &mut buf)?;
+                    buf.clear();
+                }
+                continue;
+            }
+            let (ri, ci) = ((row - r0) as usize, (col - c0) as usize);
+            if !has_body {
+                continue;
+            }
+            let mut value = read_cell_body(&mut rdr, &mut buf, cell.kind, sst)?;
+            if let Some(&Some(kind)) = cell.style.and_then(|s| date_styles.get(s as usize)) {
+                value = serial_to_date(value, kind, date1904);
+            }
+            grid[ri][ci] = value;
+        }
+        Ok(grid)
+    }
+}
+
+/// Number (or cached formula number) → `CellValue::Date` of the kind the format shows.
+fn serial_to_date(value: CellValue, kind: DateFormatKind, date1904: bool) -> CellValue {
+    match value {
+        CellValue::Number(n) => match DateValue::from_serial(n, date1904) {
+            Ok(DateValue::DateTime {
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                second,
+            }) => CellValue::Date(match kind {
+                DateFormatKind::Date => DateValue::date(year, month, day),
+                DateFormatKind::Time => DateValue::time(hour, minute, second),
+                DateFormatKind::DateTime => {
+                    DateValue::datetime(year, month, day, hour, minute, second)
+                }
+            }),
+            _ => CellValue::Number(n),
+        },
+        CellValue::Formula {
+            formula,
+            cached: Some(c),
+        } => CellValue::Formula {
+            formula,
+            cached: Some(Box::new(serial_to_date(*c, kind, date1904))),
+        },
+        other => other,
+    }
+}
+
+/// One worksheet row: 1-based row number and its non-blank cells as `(0-based column, value)`.
+pub type SheetRow = (u32, Vec<(u32, CellValue)>);
+
+/// Streaming reader over the `<row>` elements of a worksheet.
+///
+/// Only the current row is held in memory. Rows come in file order; rows missing from
+/// the sheet are not reported, and cells without a value (e.g. styled `<c r="B2" s="1"/>`)
+/// are left out of the row.
+pub struct SheetReader<'a, R: BufRead> {
+    rdr: Reader<R>,
+    buf: Vec<u8>,
+    sst: &'a [String],
+    cur_row: u32,
+    done: bool,
+}
+
+impl<'a, R: BufRead> SheetReader<'a, R> {
+    /// Wraps worksheet XML; `shared_strings` resolves `t="s"` cells.
+    pub fn new(xml: R, shared_strings: &'a [String]) -> Self {
+        Self {
+            rdr: Reader::from_reader(xml),
+            buf: Vec::new(),
+            sst: shared_strings,
+            cur_row: 0,
+            done: false,
+        }
+    }
+
+    fn next_row(&mut self) -> Result<Option<SheetRow>> {
+        loop {
+            let (row, has_body) = match self.rdr.read_event_into(&mut self.buf)? {
+                Event::Start(ref e) if e.name().as_ref() == b"row" => (attr_value(e, b"r"), true),
+                Event::Empty(ref e) if e.name().as_ref() == b"row" => (attr_value(e, b"r"), false),
+                Event::End(ref e) if e.name().as_ref() == b"sheetData" => return Ok(None),
+                Event::Eof => return Ok(None),
+                _ => {
+                    self.buf.clear();
+                    continue;
+                }
+            };
+            self.buf.clear();
+            self.cur_row = row.and_then(|v| v.parse().ok()).unwrap_or(self.cur_row + 1);
+            let cells = if has_body {
+                self.read_row_cells()?
+            } else {
+                Vec::new()
+            };
+            return Ok(Some((self.cur_row, cells)));
+        }
+    }
+
+    fn read_row_cells(&mut self) -> Result<Vec<(u32, CellValue)>> {
+        let mut cells = Vec::new();
+        let mut next_col = 0u32;
+        loop {
+            let (cell, has_body) = match self.rdr.read_event_into(&mut self.buf)? {
+                Event::Start(ref e) if e.name().as_ref() == b"c" => (cell_head(e), true),
+                Event::Empty(ref e) if e.name().as_ref() == b"c" => (cell_head(e), false),
+                Event::End(ref e) if e.name().as_ref() == b"row" => break,
+                Event::Eof => bail!("unexpected end of sheet inside <row>"),
+                _ => {
+                    self.buf.clear();
+                    continue;
+                }
+            };
+            self.buf.clear();
+
+            let col = cell.coord.map_or(next_col, |(c, _)| c);
+            next_col = col + 1;
+            if !has_body {
+                continue;
+            }
+            let value = read_cell_body(&mut self.rdr, &mut self.buf, cell.kind, self.sst)?;
+            if !value.is_blank() {
+                cells.push((col, value));
+            }
+        }
+        self.buf.clear();
+        Ok(cells)
+    }
+}
+
+impl<R: BufRead> Iterator for SheetReader<'_, R> {
+    type Item = Result<SheetRow>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_row() {
+            Ok(Some(row)) => Some(Ok(row)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+impl XlsxEditor {
+    /// Iterates over the rows of the current sheet, including unsaved edits.
+    ///
+    /// # Returns
+    /// A [`SheetReader`] yielding `(row, cells)` pairs, see [`SheetRow`].
+    pub fn rows(&self) -> Result<SheetReader<'_, &[u8]>> {
+        let sst: &[String] = if memchr::memmem::find(&self.sheet_xml, b" t=\"s\"").is_some() {
+            self.shared_strings()?.strings()
+        } else {
+            &[]
+        };
+        Ok(SheetReader::new(self.sheet_xml.as_slice(), sst))
+    }
+}
+
+/// Streams the rows of `sheet_name` straight from the archive without loading the sheet
+/// into memory, calling `f` for every row (see [`SheetReader`]).
+///
+/// Meant for large exports; use [`XlsxEditor::rows`] to read a sheet that is being edited.
+pub fn for_each_row<P, F>(src: P, sheet_name: &str, mut f: F) -> Result<()>
+where
+    P: AsRef<Path>,
+    F: FnMut(u32, Vec<(u32, CellValue)>) -> Result<()>,
+{
+    let mut zip = zip::ZipArchive::new(File::open(src)?)?;
+    let mut read_part = |path: &str| -> Result<Option<Vec<u8>>> {
+        let Ok(mut f) = zip.by_name(path) else {
+            return Ok(None);
+        };
+        let mut buf = Vec::with_capacity(f.size() as usize);
+        f.read_to_end(&mut buf)?;
+        Ok(Some(buf))
+    };
+    let workbook_xml = read_part("xl/workbook.xml")?.context("xl/workbook.xml not found")?;
+    let rels_xml =
+        read_part("xl/_rels/workbook.xml.rels")?.context("xl/_rels/workbook.xml.rels not found")?;
+    let sheet_path = sheet_part_path(&workbook_xml, &rels_xml, sheet_name)
+        .with_context(|| format!("Sheet '{}' not found", sheet_name))?;
+    let sst = match workbook_part_path(&rels_xml, SST_REL_TYPE) {
+        Some(path) => match read_part(&path)? {
+            Some(xml) => parse_shared_strings(&xml)?,
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+
+    let sheet = zip
+        .by_name(&sheet_path)
+        .with_context(|| format!("{sheet_path} not found"))?;
+    for row in SheetReader::new(BufReader::new(sheet), &sst) {
+        let (r, cells) = row?;
+        f(r, cells)?;
+    }
+    Ok(())
+}
+
+/// Value type of a cell as declared by its `t` attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CellKind {
+    Number,
+    Shared,
+    Inline,
+    Str,
+    Bool,
+    Error,
+    Date,
+}
+
+pub(crate) struct CellHead {
+    /// (0-based column, 1-based row); `None` when the `r` attribute is omitted.
+    pub(crate) coord: Option<(u32, u32)>,
+    pub(crate) kind: CellKind,
+    /// `s` attribute – index into `cellXfs`.
+    pub(crate) style: Option<u32>,
+}
+
+pub(crate) fn cell_head(e: &BytesStart) -> CellHead {
+    let mut head = CellHead {
+        coord: None,
+        kind: CellKind::Number,
+        style: None,
+    };
+    for a in e.attributes().with_checks(false).flatten() {
+        match a.key.as_ref() {
+            b"r" => {
+                head.coord = std::str::from_utf8(&a.value)
+                    .ok()
+                    .and_then(|s| parse_cell_ref(s).ok())
+            }
+            b"s" => head.style = lexical_core::parse(&a.value).ok(),
+            b"t" => {
+                head.kind = match &*a.value {
+                    b"s" => CellKind::Shared,
+                    b"inlineStr" => CellKind::Inline,
+                    b"str" => CellKind::Str,
+                    b"b" => CellKind::Bool,
+                    b"e" => CellKind::Error,
+                    b"d" => CellKind::Date,
+                    _ => CellKind::Number,
+                }
+            }
+            _ => {}
+        }
+    }
+    head
+}
+
+/// Reads the children of an open `<c>` up to and including `</c>` and builds the typed value.
+pub(crate) fn read_cell_body<R: BufRead>(
+    rdr: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+    kind: CellKind,
+    sst: &[String],
+) -> Result<CellValue> {
+    let mut raw: Option<String> = None;
+    let mut formula: Option<String> = None;
+
+    loop {
+        match rdr.read_event_into(buf)? {
+            Event::Start(ref e) => match e.name().as_ref() {
+                b"v" => {
+                    buf.clear();
+                    raw = Some(read_text(rdr, buf, b"v")?);
+                }
+                b"f" => {
+                    buf.clear();
+                    formula = Some(read_text(rdr, buf, b"f")?);
+                }
+                b"is" => {
+                    buf.clear();
+                    raw = Some(read_rich_text(rdr, buf, b"is")?);
+                }
+                _ => {
+                    let name = e.name().as_ref().to_vec();
+                    buf.clear();
+                    rdr.read_to_end_into(quick_xml::name::QName(&name), buf)?;
+                }
+            },
+            Event::Empty(ref e) if e.name().as_ref() == b"f" => formula = Some(String::new()),
+            Event::End(ref e) if e.name().as_ref() == b"c" => break,
+            Event::Eof => bail!("unexpected end of sheet inside <c>"),
+            _ => {}
+        }
+        buf.clear();
+    }
+    buf.clear();
+
+    let value = match raw {
+        None => None,
+        Some(v) => Some(match kind {
+            CellKind::Shared => {
+                let idx: usize = v
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("invalid shared string index `{v}`"))?;
+                let s = sst
+                    .get(idx)
+                    .with_context(|| format!("shared string #{idx} not found"))?;
+                CellValue::Text(s.clone())
+            }
+            CellKind::Inline | CellKind::Str | CellKind::Date => CellValue::Text(v),
+            CellKind::Bool => CellValue::Bool(matches!(v.trim(), "1" | "true")),
+            CellKind::Error => CellValue::Error(v),
+            CellKind::Number => CellValue::Number(
+                lexical_core::parse(v.trim().as_bytes())
+                    .map_err(|_| anyhow::anyhow!("invalid numeric value `{v}`"))?,
+            ),
+        }),
+    };
+
+    Ok(match formula {
+        Some(f) => CellValue::Formula {
+            formula: f,
+            cached: value.map(Box::new),
+        },
+        None => value.unwrap_or(CellValue::Blank),
+    })
+}
+
+/// Collects the unescaped text content up to the closing `end` tag.
+fn read_text<R: BufRead>(rdr: &mut Reader<R>, buf: &mut Vec<u8>, end: &[u8]) -> Result<String> {
+    let mut raw = String::new();
+    loop {
+        match rdr.read_event_into(buf)? {
+            Event::Text(ref t) => raw.push_str(&t.decode()?),
+            Event::GeneralRef(ref r) => {
+                raw.push('&');
+                raw.push_str(&r.decode()?);
+                raw.push(';');
+            }
+            Event::CData(ref c) => raw.push_str(&quick_xml::escape::escape(c.decode()?)),
+            Event::End(ref e) if e.name().as_ref() == end => break,
+            Event::Eof => bail!(
+                "unexpected end of XML inside <{}>",
+                String::from_utf8_lossy(end)
+            ),
+            _ => {}
+        }
+        buf.clear();
+    }
+    buf.clear();
+    Ok(quick_xml::escape::unescape(&raw)?.into_owned())
+}
+
+/// Concatenates every `<t>` inside `<si>`/`<is>` (plain or rich-text runs), skipping
+/// phonetic hints in `<rPh>`.
+fn read_rich_text<R: BufRead>(
+    rdr: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+    end: &[u8],
+) -> Result<String> {
+    let mut out = String::new();
+    loop {
+        match rdr.read_event_into(buf)? {
+            Event::Start(ref e) if e.name().as_ref() == b"t" => {
+                buf.clear();
+                out.push_str(&read_text(rdr, buf, b"t")?);
+            }
+            Event::Start(ref e) if e.name().as_ref() == b"rPh" => {
+                buf.clear();
+                rdr.read_to_end_into(quick_xml::name::QName(b"rPh"), buf)?;
+            }
+            Event::End(ref e) if e.name().as_ref() == end => break,
+            Event::Eof => bail!(
+                "unexpected end of XML inside <{}>",
+                String::from_utf8_lossy(end)
+            ),
+            _ => {}
+        }
+        buf.clear();
+    }
+    buf.clear();
+    Ok(out)
+}
+
+/// Parses `sharedStrings.xml` into a vector indexed by the `t="s"` value.
+pub(crate) fn parse_shared_strings(xml: &[u8]) -> Result<Vec<String>> {
+    let mut rdr = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut out = Vec::new();
+    loop {
+        match rdr.read_event_into(&mut buf)? {
+            Event::Start(ref e) if e.name().as_ref() == b"si" => {
+                buf.clear();
+                out.push(read_rich_text(&mut rdr, &mut buf, b"si")?);
+            }
+            Event::Empty(ref e) if e.name().as_ref() == b"si" => out.push(String::new()),
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    Ok(out)
+}
+
+/// "B7" / "$B$7" → (1, 7)
+pub(crate) fn parse_cell_ref(s: &str) -> Result<(u32, u32)> {
+    let s = s.trim();
+    let cleaned: String = s.chars().filter(|&c| c != '$').collect();
+    let p = cleaned
+        .find(|c: char| c.is_ascii_digit())
+        .with_context(|| format!("invalid cell reference `{s}`"))?;
+    let (letters, digits) = cleaned.split_at(p);
+    if letters.is_empty()
+        || !letters.bytes().all(|b| b.is_ascii_alphabetic())
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        bail!("invalid cell reference `{s}`");
+    }
+    let row: u32 = digits.parse()?;
+    if row == 0 {
+        bail!("invalid cell reference `{s}`: rows start at 1");
+    }
+    Ok((col_index(letters) as u32, row))
+}
+
+fn attr_value(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .flatten()
+        .find(|a| a.key.as_ref() == key)
+        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+}
@@ -0,0 +1,228 @@
+//! shared_strings.rs – таблица общих строк (xl/sharedStrings.xml)
+
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use memchr::memmem;
+
+use crate::{
+    XlsxEditor,
+    files_part::{needs_xml_space_preserve, workbook_part_path},
+    read_part::parse_shared_strings,
+    style::util::find_bytes_from,
+};
+
+pub(crate) const SST_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml";
+pub(crate) const SST_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings";
+
+/// How `append_row`, `append_table*` and `set_cell` store text cells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StringStorage {
+    /// `t="inlineStr"` with the text inside the cell (default).
+    #[default]
+    Inline,
+    /// `t="s"` pointing into `xl/sharedStrings.xml`; repeated values are stored once.
+    Shared,
+}
+
+/// Loaded shared strings table.
+///
+/// The original part is kept as-is and new `<si>` entries are appended to it on save,
+/// so rich-text runs of existing strings survive untouched.
+pub(crate) struct SharedStrings {
+    /// Path inside the archive, e.g. `xl/sharedStrings.xml`.
+    pub(crate) path: String,
+    /// Original XML; `None` when the workbook had no shared strings part.
+    source: Option<Vec<u8>>,
+    strings: Vec<String>,
+    /// text → index, built on the first `intern`.
+    index: Option<HashMap<String, u32>>,
+    /// Number of strings that came from `source`.
+    loaded: usize,
+    count: u32,
+    dirty: bool,
+}
+
+impl SharedStrings {
+    pub(crate) fn load(path: String, source: Option<Vec<u8>>) -> Result<Self> {
+        let (strings, count) = match &source {
+            Some(xml) => {
+                let strings = parse_shared_strings(xml)?;
+                let count = sst_attr(xml, b" count=\"").unwrap_or(strings.len() as u32);
+                (strings, count)
+            }
+            None => (Vec::new(), 0),
+        };
+        Ok(Self {
+            path,
+            source,
+            loaded: strings.len(),
+            strings,
+            index: None,
+            count,
+            dirty: false,
+        })
+    }
+
+    pub(crate) fn strings(&self) -> &[String] {
+        &self.strings
+    }
+
+    pub(crate) fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the index of `s`, appending it to the table if needed.
+    pub(crate) fn intern(&mut self, s: &str) -> u32 {
+        let strings = &self.strings;
+        let index = self.index.get_or_insert_with(|| {
+            let mut m = HashMap::with_capacity(strings.len());
+            for (i, v) in strings.iter().enumerate() {
+                m.entry(v.clone()).or_insert(i as u32);
+            }
+            m
+        });
+        let idx = match index.get(s) {
+            Some(&i) => i,
+            None => {
+                let i = self.strings.len() as u32;
+                self.strings.push(s.to_owned());
+                index.insert(s.to_owned(), i);
+                i
+            }
+        };
+        self.count += 1;
+        self.dirty = true;
+        idx
+    }
+
+    /// Serialises the table: the original XML plus the new `<si>` entries,
+    /// with `count`/`uniqueCount` brought up to date.
+    pub(crate) fn to_xml(&self) -> Vec<u8> {
+        let mut tail = Vec::new();
+        for s in &self.strings[self.loaded..] {
+            tail.extend_from_slice(b"<si><t");
+            if needs_xml_space_preserve(s) {
+                tail.extend_from_slice(br#" xml:space="preserve""#);
+            }
+            tail.push(b'>');
+            tail.extend_from_slice(quick_xml::escape::escape(s.as_str()).as_bytes());
+            tail.extend_from_slice(b"</t></si>");
+        }
+        let head = format!(
+            r#"<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="{}" uniqueCount="{}">"#,
+            self.count,
+            self.strings.len()
+        );
+
+        let Some(src) = &self.source else {
+            let mut xml = br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#.to_vec();
+            xml.extend_from_slice(head.as_bytes());
+            xml.extend_from_slice(&tail);
+            xml.extend_from_slice(b"</sst>");
+            return xml;
+        };
+
+        let mut xml = src.clone();
+        if let Some(end) = memmem::rfind(&xml, b"</sst>") {
+            xml.splice(end..end, tail);
+        } else if let Some(root) = memmem::find(&xml, b"<sst") {
+            // <sst .../> без содержимого
+            let root_end = find_bytes_from(&xml, b">", root).map_or(xml.len(), |p| p + 1);
+            let mut block = head.into_bytes();
+            block.extend_from_slice(&tail);
+            block.extend_from_slice(b"</sst>");
+            xml.splice(root..root_end, block);
+            return xml;
+        }
+        set_sst_attr(&mut xml, b" count=\"", self.count);
+        set_sst_attr(&mut xml, b" uniqueCount=\"", self.strings.len() as u32);
+        xml
+    }
+}
+
+fn sst_root(xml: &[u8]) -> Option<(usize, usize)> {
+    let start = memmem::find(xml, b"<sst")?;
+    let end = find_bytes_from(xml, b">", start)?;
+    Some((start, end))
+}
+
+fn sst_attr(xml: &[u8], attr: &[u8]) -> Option<u32> {
+    let (start, end) = sst_root(xml)?;
+    let a = find_bytes_from(xml, attr, start).filter(|&a| a < end)? + attr.len();
+    let b = find_bytes_from(xml, b"\"", a)?;
+    lexical_core::parse(&xml[a..b]).ok()
+}
+
+fn set_sst_attr(xml: &mut Vec<u8>, attr: &[u8], value: u32) {
+    let Some((start, end)) = sst_root(xml) else {
+        return;
+    };
+    match find_bytes_from(xml, attr, start).filter(|&a| a < end) {
+        Some(a) => {
+            let v0 = a + attr.len();
+            if let Some(v1) = find_bytes_from(xml, b"\"", v0) {
+                xml.splice(v0..v1, value.to_string().into_bytes());
+            }
+        }
+        None => {
+            let ins = if xml[end - 1] == b'/' { end - 1 } else { end };
+            let name = &attr[..attr.len() - 2];
+            let mut a = name.to_vec();
+            a.extend_from_slice(format!("=\"{value}\"").as_bytes());
+            xml.splice(ins..ins, a);
+        }
+    }
+}
+
+impl XlsxEditor {
+    /// Chooses how text cells are written from now on (see [`StringStorage`]).
+    pub fn set_string_storage(&mut self, storage: StringStorage) -> &mut Self {
+        self.string_storage = storage;
+        self
+    }
+
+    /// Shared strings table, loaded on first use.
+    pub(crate) fn shared_strings(&self) -> Result<&SharedStrings> {
+        if let Some(sst) = self.shared_strings.get() {
+            return Ok(sst);
+        }
+        let path = workbook_part_path(&self.rels_xml, SST_REL_TYPE)
+            .unwrap_or_else(|| "xl/sharedStrings.xml".to_owned());
+        let source = match self.new_files.get(&path) {
+            Some(xml) => Some(xml.clone()),
+            None => self.read_source_part(&path)?,
+        };
+        let sst = SharedStrings::load(path, source)?;
+        Ok(self.shared_strings.get_or_init(|| sst))
+    }
+
+    pub(crate) fn shared_strings_mut(&mut self) -> Result<&mut SharedStrings> {
+        self.shared_strings()?;
+        self.shared_strings
+            .get_mut()
+            .context("shared strings not loaded")
+    }
+
+    /// Puts the updated table into `new_files` and registers the part in
+    /// `workbook.xml.rels` if the workbook did not have one.
+    pub(crate) fn flush_shared_strings(&mut self) -> Result<()> {
+        let Some(sst) = self.shared_strings.get() else {
+            return Ok(());
+        };
+        if !sst.is_dirty() {
+            return Ok(());
+        }
+        let path = sst.path.clone();
+        let xml = sst.to_xml();
+        self.new_files.insert(path.clone(), xml);
+
+        if workbook_part_path(&self.rels_xml, SST_REL_TYPE).is_none() {
+            let target = path.strip_prefix("xl/").unwrap_or(&path).to_owned();
+            self.add_workbook_relationship(SST_REL_TYPE, &target)?;
+        }
+        Ok(())
+    }
+}
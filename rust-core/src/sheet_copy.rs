@@ -0,0 +1,737 @@
+//! sheet_copy.rs – копия листа вместе с его частями (таблицы, рисунки, диаграммы, примечания)
+//! и импорт листа из другой книги
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use anyhow::{Context, Result, bail};
+use memchr::memmem;
+use quick_xml::{
+    Reader, Writer,
+    events::{BytesEnd, BytesStart, BytesText, Event},
+};
+
+use crate::{
+    XlsxEditor,
+    files_part::{calc_last_row, ensure_ct_override, sheet_part_path, validate_sheet_name},
+    find_bytes_from,
+    formula::{RefTransform, same_sheet, transform_formula},
+    shift::{
+        attr, read_formula_text, rewrite_formula_elements, skip_element, with_attr, without_attr,
+    },
+    style::ForeignStyles,
+};
+
+// части, которые принадлежат листу и копируются вместе с ним
+const CLONED_RELS: [&str; 8] = [
+    "table",
+    "drawing",
+    "chart",
+    "chartUserShapes",
+    "chartStyle",
+    "chartColorStyle",
+    "comments",
+    "vmlDrawing",
+];
+// части, которые нельзя ни разделить между листами, ни просто скопировать
+const DROPPED_RELS: [&str; 2] = ["pivotTable", "threadedComment"];
+
+const CT_PATH: &str = "[Content_Types].xml";
+
+/// Состояние одной операции копирования.
+struct CopyCtx<'a> {
+    /// Все занятые пути архива.
+    names: HashSet<String>,
+    ct: Vec<u8>,
+    /// Имена таблиц книги в нижнем регистре.
+    table_names: HashSet<String>,
+    next_table_id: u32,
+    rename: RefTransform<'a>,
+}
+
+// "xl/worksheets/sheet1.xml" → ("xl/worksheets", "sheet1.xml")
+pub(crate) fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or(("", path))
+}
+
+// "xl/worksheets" + "../drawings/drawing1.xml" → "xl/drawings/drawing1.xml"
+pub(crate) fn resolve_target(base_dir: &str, target: &str) -> String {
+    if let Some(abs) = target.strip_prefix('/') {
+        return abs.to_owned();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for seg in target.split('/') {
+        match seg {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            s => parts.push(s),
+        }
+    }
+    parts.join("/")
+}
+
+// "xl/worksheets/sheet1.xml" → "xl/worksheets/_rels/sheet1.xml.rels"
+pub(crate) fn rels_path(part: &str) -> String {
+    let (dir, file) = split_path(part);
+    format!("{dir}/_rels/{file}.rels")
+}
+
+// первый свободный путь вида "<dir>/<stem>N.<ext>"
+pub(crate) fn free_part_path(names: &mut HashSet<String>, like: &str) -> String {
+    let (dir, file) = split_path(like);
+    let (stem, ext) = file.rsplit_once('.').unwrap_or((file, ""));
+    let stem = stem.trim_end_matches(|c: char| c.is_ascii_digit());
+    let path = (1..)
+        .map(|n| format!("{dir}/{stem}{n}.{ext}"))
+        .find(|p| !names.contains(p))
+        .expect("unbounded range");
+    names.insert(path.clone());
+    path
+}
+
+// значение атрибута в первом теге `<tag ...>`
+fn root_attr_range(xml: &[u8], tag: &[u8], key: &str) -> Option<(usize, usize)> {
+    let mut open = b"<".to_vec();
+    open.extend_from_slice(tag);
+    open.push(b' ');
+    let start = memmem::find(xml, &open)?;
+    let end = find_bytes_from(xml, b">", start)?;
+    let needle = format!(" {key}=\"");
+    let a = memmem::find(&xml[start..end], needle.as_bytes())? + start + needle.len();
+    let b = find_bytes_from(xml, b"\"", a)?;
+    Some((a, b))
+}
+
+fn ct_override_type(ct: &[u8], part: &str) -> Option<String> {
+    let needle = format!(r#"PartName="/{part}""#);
+    let pos = memmem::find(ct, needle.as_bytes())?;
+    let start = memmem::rfind(&ct[..pos], b"<Override")?;
+    let end = find_bytes_from(ct, b">", pos)?;
+    let key = b"ContentType=\"";
+    let a = memmem::find(&ct[start..end], key)? + start + key.len();
+    let b = find_bytes_from(ct, b"\"", a)?;
+    Some(String::from_utf8_lossy(&ct[a..b]).into_owned())
+}
+
+// ".../relationships/table" → "table"
+fn rel_kind(rel_type: &str) -> &str {
+    rel_type.rsplit('/').next().unwrap_or(rel_type)
+}
+
+impl XlsxEditor {
+    /// Copies sheet `src_name` with all its content and inserts the copy as `new_name` at
+    /// position `index` (0-based, clamped to the sheet count). The copy becomes the current sheet.
+    ///
+    /// Tables, drawings, charts and comments of the sheet are duplicated (tables get new
+    /// unique ids and names), images and printer settings are shared, pivot tables and
+    /// threaded comments are not copied. References to `src_name` in the copied formulas,
+    /// charts and sheet-scoped defined names are pointed at the copy.
+    ///
+    /// # Arguments
+    /// * `src_name` - The sheet to copy.
+    /// * `new_name` - The name of the new sheet.
+    /// * `index` - The position of the copy in the sheet tabs.
+    pub fn copy_worksheet(
+        &mut self,
+        src_name: &str,
+        new_name: &str,
+        index: usize,
+    ) -> Result<&mut Self> {
+        validate_sheet_name(new_name)?;
+        let sheet_names = self.sheet_names()?;
+        if sheet_names.iter().any(|n| same_sheet(n, new_name)) {
+            bail!("Sheet `{new_name}` already exists");
+        }
+        let src_idx = sheet_names
+            .iter()
+            .position(|n| n == src_name)
+            .with_context(|| format!("Sheet `{src_name}` not found"))?;
+        let src_path = sheet_part_path(&self.workbook_xml, &self.rels_xml, src_name)
+            .with_context(|| format!("Relationship for `{src_name}` not found"))?;
+        let src_xml = self
+            .sheet_part_xml(&src_path)?
+            .with_context(|| format!("{src_path} not found"))?;
+        let src_rels = self.part_bytes(&rels_path(&src_path))?;
+
+        let index = index.min(sheet_names.len());
+        self.add_worksheet_at(new_name, index)?;
+        let new_path = self.sheet_path.clone();
+
+        let rename = RefTransform::RenameSheet {
+            from: src_name,
+            to: new_name,
+        };
+        let mut ctx = self.copy_ctx(rename)?;
+        ctx.names.insert(new_path.clone());
+
+        // сам лист: формулы со ссылкой на исходный лист смотрят на копию
+        let mut xml = rewrite_formula_elements(
+            &src_xml,
+            &[b"f", b"formula", b"formula1", b"formula2"],
+            |f| transform_formula(f, new_name, rename),
+        )?
+        .unwrap_or(src_xml);
+        // выделенных вкладок должно остаться одна – иначе Excel сгруппирует листы
+        while let Some(p) = memmem::find(&xml, b" tabSelected=\"1\"") {
+            xml.drain(p..p + 16);
+        }
+        if let Some(rels) = src_rels {
+            let (dir, _) = split_path(&src_path);
+            let rels = self.clone_rels(&mut ctx, &rels, dir)?;
+            self.new_files.insert(rels_path(&new_path), rels);
+        }
+        self.last_row = calc_last_row(&xml);
+        self.sheet_xml = xml;
+        self.new_files.insert(CT_PATH.to_owned(), ctx.ct);
+
+        // имена уровня листа: после вставки исходный лист мог сдвинуться
+        let src_idx = if src_idx >= index {
+            src_idx + 1
+        } else {
+            src_idx
+        };
+        if memmem::find(&self.workbook_xml, b"localSheetId=").is_some() {
+            self.workbook_xml =
+                copy_local_names(&self.workbook_xml, src_idx, index, new_name, rename)?;
+        }
+        Ok(self)
+    }
+
+    // часть архива с учётом правок: new_files → loaded_files → zip
+    pub(crate) fn part_bytes(&self, path: &str) -> Result<Option<Vec<u8>>> {
+        if self.removed_files.contains(path) {
+            return Ok(None);
+        }
+        if let Some(buf) = self.new_files.get(path) {
+            return Ok(Some(buf.clone()));
+        }
+        self.read_source_part(path)
+    }
+
+    /// Все занятые пути архива с учётом новых и удалённых частей.
+    pub(crate) fn part_names(&self) -> Result<HashSet<String>> {
+        let mut names: HashSet<String> = self.new_files.keys().cloned().collect();
+        if let Some(zin) = self.source_archive()? {
+            names.extend(zin.file_names().map(str::to_owned));
+        }
+        names.insert(self.sheet_path.clone());
+        names.retain(|n| !self.removed_files.contains(n));
+        Ok(names)
+    }
+
+    fn copy_ctx<'a>(&self, rename: RefTransform<'a>) -> Result<CopyCtx<'a>> {
+        let names = self.part_names()?;
+
+        let mut table_names = HashSet::new();
+        let mut next_table_id = 1;
+        for path in names.iter().filter(|n| n.starts_with("xl/tables/")) {
+            let Some(xml) = self.part_bytes(path)? else {
+                continue;
+            };
+            for key in ["name", "displayName"] {
+                if let Some((a, b)) = root_attr_range(&xml, b"table", key) {
+                    table_names.insert(String::from_utf8_lossy(&xml[a..b]).to_lowercase());
+                }
+            }
+            if let Some((a, b)) = root_attr_range(&xml, b"table", "id")
+                && let Ok(id) = std::str::from_utf8(&xml[a..b])?.parse::<u32>()
+            {
+                next_table_id = next_table_id.max(id + 1);
+            }
+        }
+        let ct = self
+            .part_bytes(CT_PATH)?
+            .context("[Content_Types].xml not found")?;
+        Ok(CopyCtx {
+            names,
+            ct,
+            table_names,
+            next_table_id,
+            rename,
+        })
+    }
+
+    // копирует связи части, попутно дублируя принадлежащие ей части
+    fn clone_rels(
+        &mut self,
+        ctx: &mut CopyCtx,
+        rels_xml: &[u8],
+        base_dir: &str,
+    ) -> Result<Vec<u8>> {
+        let mut rdr = Reader::from_reader(rels_xml);
+        let mut wr = Writer::new(Vec::with_capacity(rels_xml.len()));
+        let mut buf = Vec::new();
+        loop {
+            buf.clear();
+            let ev = rdr.read_event_into(&mut buf)?;
+            let e = match &ev {
+                Event::Eof => break,
+                Event::Empty(e) if e.name().as_ref() == b"Relationship" => e,
+                _ => {
+                    wr.write_event(ev.borrow())?;
+                    continue;
+                }
+            };
+            let mut kind = String::new();
+            let mut target = None;
+            let mut external = false;
+            for a in e.attributes().with_checks(false).flatten() {
+                let v = a.unescape_value()?.into_owned();
+                match a.key.as_ref() {
+                    b"Type" => kind = rel_kind(&v).to_owned(),
+                    b"Target" => target = Some(v),
+                    b"TargetMode" => external = v == "External",
+                    _ => {}
+                }
+            }
+            if DROPPED_RELS.contains(&kind.as_str()) {
+                continue;
+            }
+            let Some(target) = target.filter(|_| !external && CLONED_RELS.contains(&kind.as_str()))
+            else {
+                wr.write_event(ev.borrow())?;
+                continue;
+            };
+            let src = resolve_target(base_dir, &target);
+            let new = self.clone_part(ctx, &src)?;
+            let (_, new_file) = split_path(&new);
+            let new_target = match target.rsplit_once('/') {
+                Some((dir, _)) => format!("{dir}/{new_file}"),
+                None => new_file.to_owned(),
+            };
+            let mut tag = BytesStart::new("Relationship");
+            for a in e.attributes().with_checks(false).flatten() {
+                if a.key.as_ref() == b"Target" {
+                    tag.push_attribute(("Target", new_target.as_str()));
+                } else {
+                    tag.push_attribute(a);
+                }
+            }
+            wr.write_event(Event::Empty(tag))?;
+        }
+        Ok(wr.into_inner())
+    }
+
+    // дублирует часть `src` (и её связи), возвращает путь копии
+    fn clone_part(&mut self, ctx: &mut CopyCtx, src: &str) -> Result<String> {
+        let mut xml = self
+            .part_bytes(src)?
+            .with_context(|| format!("{src} not found"))?;
+        let new = free_part_path(&mut ctx.names, src);
+
+        if src.starts_with("xl/tables/") {
+            self.renumber_table(ctx, &mut xml);
+        } else if src.starts_with("xl/charts/chart")
+            && let Some(x) =
+                rewrite_formula_elements(&xml, &[b"c:f"], |f| transform_formula(f, "", ctx.rename))?
+        {
+            xml = x;
+        }
+
+        if let Some(rels) = self.part_bytes(&rels_path(src))? {
+            let (dir, _) = split_path(src);
+            let rels = self.clone_rels(ctx, &rels, dir)?;
+            self.new_files.insert(rels_path(&new), rels);
+        }
+        if let Some(ct) = ct_override_type(&ctx.ct, src) {
+            ensure_ct_override(&mut ctx.ct, &new, &ct);
+        }
+        self.new_files.insert(new.clone(), xml);
+        Ok(new)
+    }
+
+    // таблицам нужны уникальные id и имена в пределах книги
+    fn renumber_table(&self, ctx: &mut CopyCtx, xml: &mut Vec<u8>) {
+        if let Some((a, b)) = root_attr_range(xml, b"table", "id") {
+            xml.splice(a..b, ctx.next_table_id.to_string().into_bytes());
+            ctx.next_table_id += 1;
+        }
+        let Some((a, b)) = root_attr_range(xml, b"table", "displayName") else {
+            return;
+        };
+        let old = String::from_utf8_lossy(&xml[a..b]).into_owned();
+        let base = old.trim_end_matches(|c: char| c.is_ascii_digit());
+        let name = (2..)
+            .map(|n| format!("{base}{n}"))
+            .find(|n| !ctx.table_names.contains(&n.to_lowercase()))
+            .expect("unbounded range");
+        ctx.table_names.insert(name.to_lowercase());
+        for key in ["displayName", "name"] {
+            if let Some((a, b)) = root_attr_range(xml, b"table", key) {
+                xml.splice(a..b, name.bytes());
+            }
+        }
+    }
+}
+
+// дублирует `<definedName localSheetId="from">` для листа `to`
+fn copy_local_names(
+    wb_xml: &[u8],
+    from: usize,
+    to: usize,
+    new_name: &str,
+    rename: RefTransform,
+) -> Result<Vec<u8>> {
+    let mut rdr = Reader::from_reader(wb_xml);
+    let mut wr = Writer::new(Vec::with_capacity(wb_xml.len()));
+    let mut buf = Vec::new();
+    let local_id = from.to_string();
+    loop {
+        buf.clear();
+        match rdr.read_event_into(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(e)
+                if e.name().as_ref() == b"definedName"
+                    && e.try_get_attribute("localSheetId")?
+                        .is_some_and(|a| *a.value == *local_id.as_bytes()) =>
+            {
+                let e = e.into_owned();
+                let text = read_formula_text(&mut rdr, &mut buf, b"definedName")?;
+                let end = BytesEnd::new("definedName");
+                wr.write_event(Event::Start(e.borrow()))?;
+                wr.write_event(Event::Text(BytesText::new(&text)))?;
+                wr.write_event(Event::End(end.borrow()))?;
+
+                let mut copy = BytesStart::new("definedName");
+                for a in e.attributes().with_checks(false).flatten() {
+                    if a.key.as_ref() == b"localSheetId" {
+                        copy.push_attribute(("localSheetId", to.to_string().as_str()));
+                    } else {
+                        copy.push_attribute(a);
+                    }
+                }
+                wr.write_event(Event::Start(copy))?;
+                let text = transform_formula(&text, new_name, rename);
+                wr.write_event(Event::Text(BytesText::new(&text)))?;
+                wr.write_event(Event::End(end))?;
+            }
+            ev => wr.write_event(ev)?,
+        }
+    }
+    Ok(wr.into_inner())
+}
+
+// ── импорт листа из другой книги ─────────────────────────────────────
+
+// элементы, которые ссылаются на части исходного архива – при импорте не переносятся
+const PART_POINTERS: [&[u8]; 7] = [
+    b"drawing",
+    b"legacyDrawing",
+    b"legacyDrawingHF",
+    b"tableParts",
+    b"picture",
+    b"oleObjects",
+    b"controls",
+];
+
+// дочерние элементы блока `<block>` как сырые байты
+pub(crate) fn block_children(xml: &[u8], block: &[u8]) -> Result<Vec<Vec<u8>>> {
+    let mut rdr = Reader::from_reader(xml);
+    let mut out = Vec::new();
+    let mut depth = 0usize;
+    let mut in_block = false;
+    let mut start = 0usize;
+    loop {
+        let pos = rdr.buffer_position() as usize;
+        match rdr.read_event()? {
+            Event::Start(e) if !in_block && e.name().as_ref() == block => in_block = true,
+            Event::Empty(e) if !in_block && e.name().as_ref() == block => break,
+            Event::Start(_) if in_block => {
+                if depth == 0 {
+                    start = pos;
+                }
+                depth += 1;
+            }
+            Event::Empty(_) if in_block && depth == 0 => {
+                out.push(xml[pos..rdr.buffer_position() as usize].to_vec());
+            }
+            Event::End(_) if in_block && depth == 0 => break,
+            Event::End(_) if in_block => {
+                depth -= 1;
+                if depth == 0 {
+                    out.push(xml[start..rdr.buffer_position() as usize].to_vec());
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+// дописывает элемент в блок `<block>` (создаёт блок перед `before`, если его нет)
+pub(crate) fn append_to_block(
+    xml: &mut Vec<u8>,
+    block: &str,
+    raw: &[u8],
+    before: &[&str],
+) -> Result<()> {
+    let close = format!("</{block}>");
+    if let Some(pos) = memmem::rfind(xml, close.as_bytes()) {
+        xml.splice(pos..pos, raw.iter().copied());
+        return crate::style::util::bump_count(xml, format!("<{block}").as_bytes(), b"count=\"");
+    }
+    // <dxfs count="0"/> или блока нет вовсе
+    let open = format!("<{block}");
+    let (at, end) = match memmem::find(xml, open.as_bytes()) {
+        Some(p) => (p, find_bytes_from(xml, b">", p).map_or(p, |e| e + 1)),
+        None => {
+            let p = before
+                .iter()
+                .find_map(|b| memmem::find(xml, format!("<{b}").as_bytes()))
+                .or_else(|| memmem::rfind(xml, b"</styleSheet>"))
+                .context("styles.xml: </styleSheet> not found")?;
+            (p, p)
+        }
+    };
+    let mut new = format!(r#"<{block} count="1">"#).into_bytes();
+    new.extend_from_slice(raw);
+    new.extend_from_slice(close.as_bytes());
+    xml.splice(at..end, new);
+    Ok(())
+}
+
+// значение атрибута сырого тега
+pub(crate) fn raw_attr(raw: &[u8], key: &str) -> Option<String> {
+    let end = find_bytes_from(raw, b">", 0)?;
+    let needle = format!(" {key}=\"");
+    let a = memmem::find(&raw[..end], needle.as_bytes())? + needle.len();
+    let b = find_bytes_from(raw, b"\"", a)?;
+    Some(String::from_utf8_lossy(&raw[a..b]).into_owned())
+}
+
+// заменяет значение атрибута в открывающем теге сырого элемента
+pub(crate) fn set_raw_attr(raw: &mut Vec<u8>, key: &str, value: &str) {
+    let Some(end) = find_bytes_from(raw, b">", 0) else {
+        return;
+    };
+    let needle = format!(" {key}=\"");
+    if let Some(a) = memmem::find(&raw[..end], needle.as_bytes()) {
+        let a = a + needle.len();
+        if let Some(b) = find_bytes_from(raw, b"\"", a) {
+            raw.splice(a..b, value.bytes());
+        }
+    }
+}
+
+impl XlsxEditor {
+    /// Переносит из чужого styles.xml только стили, на которые ссылается лист `xml`.
+    /// Возвращает (xf исходной книги → xf этой книги, dxf → dxf).
+    fn merge_styles_from(
+        &mut self,
+        src: &[u8],
+        xml: &[u8],
+    ) -> Result<(HashMap<u32, u32>, HashMap<u32, u32>)> {
+        let (mut used_xfs, mut used_dxfs) = (BTreeSet::new(), BTreeSet::new());
+        let mut rdr = Reader::from_reader(xml);
+        loop {
+            match rdr.read_event()? {
+                Event::Start(e) | Event::Empty(e) => {
+                    let (key, used): (&[u8], _) = match e.name().as_ref() {
+                        b"c" | b"row" => (b"s", &mut used_xfs),
+                        b"col" => (b"style", &mut used_xfs),
+                        b"cfRule" => (b"dxfId", &mut used_dxfs),
+                        _ => continue,
+                    };
+                    if let Some(i) = attr(&e, key).and_then(|v| v.parse::<u32>().ok()) {
+                        used.insert(i);
+                    }
+                }
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+
+        let foreign = ForeignStyles::parse(src)?;
+        let mut xfs = HashMap::new();
+        for i in used_xfs {
+            xfs.insert(i, self.import_xf(&foreign, i)?);
+        }
+        let mut dxfs = HashMap::new();
+        for i in used_dxfs {
+            dxfs.insert(i, self.import_dxf(&foreign, i)?);
+        }
+        Ok((xfs, dxfs))
+    }
+
+    /// Imports sheet `src_sheet` of another workbook as `new_name` at tab position `index`
+    /// (0-based, clamped to the sheet count). The imported sheet becomes the current sheet.
+    ///
+    /// The cell styles the sheet uses are added to this workbook's `styles.xml` (identical
+    /// fonts, fills, borders, number formats and cell formats are reused), shared strings are re-interned
+    /// as plain text, and column widths, merges, validations and conditional formats come
+    /// along. Drawings, tables and comments of the source sheet are not imported; external
+    /// hyperlinks are.
+    ///
+    /// # Arguments
+    /// * `src` - The workbook to take the sheet from.
+    /// * `src_sheet` - The name of the sheet in `src`.
+    /// * `new_name` - The name of the sheet in this workbook.
+    /// * `index` - The position of the new sheet in the sheet tabs.
+    pub fn import_worksheet(
+        &mut self,
+        src: &XlsxEditor,
+        src_sheet: &str,
+        new_name: &str,
+        index: usize,
+    ) -> Result<&mut Self> {
+        validate_sheet_name(new_name)?;
+        if self.sheet_names()?.iter().any(|n| same_sheet(n, new_name)) {
+            bail!("Sheet `{new_name}` already exists");
+        }
+        let src_path = sheet_part_path(&src.workbook_xml, &src.rels_xml, src_sheet)
+            .with_context(|| format!("Sheet `{src_sheet}` not found in the source workbook"))?;
+        let xml = src
+            .sheet_part_xml(&src_path)?
+            .with_context(|| format!("{src_path} not found"))?;
+
+        // внешние ссылки (гиперссылки) остаются, связи с частями архива – нет
+        let mut kept_rels = Vec::new();
+        let mut kept_ids = HashSet::new();
+        if let Some(rels) = src.part_bytes(&rels_path(&src_path))? {
+            let mut rdr = Reader::from_reader(rels.as_slice());
+            loop {
+                let pos = rdr.buffer_position() as usize;
+                match rdr.read_event()? {
+                    Event::Empty(e) if e.name().as_ref() == b"Relationship" => {
+                        let raw = &rels[pos..rdr.buffer_position() as usize];
+                        if raw_attr(raw, "TargetMode").as_deref() == Some("External")
+                            && let Some(id) = raw_attr(raw, "Id")
+                        {
+                            kept_ids.insert(id);
+                            kept_rels.extend_from_slice(raw);
+                        }
+                    }
+                    Event::Eof => break,
+                    _ => {}
+                }
+            }
+        }
+
+        let (xfs, dxfs) = self.merge_styles_from(&src.styles_xml, &xml)?;
+        let strings = if memmem::find(&xml, br#"t="s""#).is_some() {
+            src.shared_strings()?.strings().to_vec()
+        } else {
+            Vec::new()
+        };
+        let rename = (src_sheet != new_name).then_some(RefTransform::RenameSheet {
+            from: src_sheet,
+            to: new_name,
+        });
+        let xml = self.import_sheet_xml(&xml, &xfs, &dxfs, &strings, &kept_ids, rename)?;
+
+        self.add_worksheet_at(new_name, index)?;
+        if !kept_rels.is_empty() {
+            let mut rels = br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#
+                .to_vec();
+            rels.extend_from_slice(&kept_rels);
+            rels.extend_from_slice(b"</Relationships>");
+            self.new_files.insert(rels_path(&self.sheet_path), rels);
+        }
+        self.last_row = calc_last_row(&xml);
+        self.sheet_xml = xml;
+        Ok(self)
+    }
+
+    // переписывает лист чужой книги под стили и общие строки этой книги
+    fn import_sheet_xml(
+        &mut self,
+        xml: &[u8],
+        xfs: &HashMap<u32, u32>,
+        dxfs: &HashMap<u32, u32>,
+        strings: &[String],
+        kept_ids: &HashSet<String>,
+        rename: Option<RefTransform>,
+    ) -> Result<Vec<u8>> {
+        let remap = |v: Option<String>, map: &HashMap<u32, u32>| {
+            v.and_then(|v| v.parse::<u32>().ok())
+                .map(|i| map.get(&i).copied().unwrap_or(0).to_string())
+        };
+        let mut rdr = Reader::from_reader(xml);
+        let mut wr = Writer::new(Vec::with_capacity(xml.len()));
+        let mut buf = Vec::new();
+        let (mut sst_cell, mut in_v) = (false, false);
+        loop {
+            buf.clear();
+            let ev = rdr.read_event_into(&mut buf)?;
+            let (e, empty) = match &ev {
+                Event::Start(e) => (e, false),
+                Event::Empty(e) => (e, true),
+                Event::Text(t) if in_v => {
+                    let idx: usize = t.decode()?.trim().parse()?;
+                    let s = strings
+                        .get(idx)
+                        .with_context(|| format!("shared string {idx} not found"))?;
+                    let idx = self.shared_strings_mut()?.intern(s);
+                    wr.write_event(Event::Text(BytesText::new(&idx.to_string())))?;
+                    continue;
+                }
+                Event::End(e) => {
+                    match e.name().as_ref() {
+                        b"v" => in_v = false,
+                        b"c" => sst_cell = false,
+                        _ => {}
+                    }
+                    wr.write_event(ev.borrow())?;
+                    continue;
+                }
+                Event::Eof => break,
+                _ => {
+                    wr.write_event(ev.borrow())?;
+                    continue;
+                }
+            };
+            let name = e.name().as_ref().to_vec();
+            if PART_POINTERS.contains(&name.as_slice()) {
+                if !empty {
+                    skip_element(&mut rdr, &mut buf)?;
+                }
+                continue;
+            }
+            let mut tag = e.to_owned();
+            let style_key: &[u8] = match name.as_slice() {
+                b"c" | b"row" => b"s",
+                b"col" => b"style",
+                _ => b"",
+            };
+            if let Some(v) = remap(attr(&tag, style_key), xfs) {
+                tag = with_attr(&tag, style_key, &v);
+            }
+            if name == b"cfRule"
+                && let Some(v) = remap(attr(&tag, b"dxfId"), dxfs)
+            {
+                tag = with_attr(&tag, b"dxfId", &v);
+            }
+            if name == b"sheetView" {
+                tag = without_attr(&tag, b"tabSelected");
+            }
+            if attr(&tag, b"r:id").is_some_and(|id| !kept_ids.contains(&id)) {
+                tag = without_attr(&tag, b"r:id");
+            }
+            match name.as_slice() {
+                b"c" => sst_cell = !empty && attr(&tag, b"t").as_deref() == Some("s"),
+                b"v" => in_v = sst_cell && !empty,
+                _ => {}
+            }
+            if empty {
+                wr.write_event(Event::Empty(tag))?;
+                continue;
+            }
+            wr.write_event(Event::Start(tag))?;
+            if let Some(rename) = rename
+                && matches!(
+                    name.as_slice(),
+                    b"f" | b"formula" | b"formula1" | b"formula2"
+                )
+            {
+                let text = read_formula_text(&mut rdr, &mut buf, &name)?;
+                let text = transform_formula(&text, "", rename);
+                wr.write_event(Event::Text(BytesText::new(&text)))?;
+                wr.write_event(Event::End(BytesEnd::new(String::from_utf8_lossy(&name))))?;
+            }
+        }
+        Ok(wr.into_inner())
+    }
+}
@@ -0,0 +1,554 @@
+//! shift.rs – вставка/удаление строк и столбцов со сдвигом всех ссылок листа
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use memchr::memmem;
+use quick_xml::{
+    Reader, Writer,
+    events::{BytesStart, BytesText, Event},
+};
+
+use crate::{
+    XlsxEditor,
+    comments::moved_cell,
+    files_part::calc_last_row,
+    find_bytes_from,
+    formula::{
+        Axis, MAX_COL, MAX_ROW, RefTransform, Shift, offset_formula, parse_area, transform_formula,
+    },
+    hyperlink::hyperlink_rids,
+    read_part::parse_cell_ref,
+    style::util::{col_index, col_letter},
+};
+
+pub(crate) fn attr(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .flatten()
+        .find(|a| a.key.as_ref() == key)
+        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+}
+
+// копия тега без атрибута
+pub(crate) fn without_attr(e: &BytesStart, key: &[u8]) -> BytesStart<'static> {
+    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    let mut out = BytesStart::new(name);
+    out.extend_attributes(
+        e.attributes()
+            .with_checks(false)
+            .flatten()
+            .filter(|a| a.key.as_ref() != key),
+    );
+    out
+}
+
+// копия тега с заменённым значением атрибута
+pub(crate) fn with_attr(e: &BytesStart, key: &[u8], value: &str) -> BytesStart<'static> {
+    let name = String::from_utf8_lossy(e.name().as_ref()).into_owned();
+    let mut out = BytesStart::new(name);
+    for a in e.attributes().with_checks(false).flatten() {
+        if a.key.as_ref() == key {
+            out.push_attribute((key, value.as_bytes()));
+        } else {
+            out.push_attribute(a);
+        }
+    }
+    out
+}
+
+pub(crate) fn skip_element<R: std::io::BufRead>(
+    rdr: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+) -> Result<()> {
+    let mut depth = 1usize;
+    while depth > 0 {
+        buf.clear();
+        match rdr.read_event_into(buf)? {
+            Event::Start(_) => depth += 1,
+            Event::End(_) => depth -= 1,
+            Event::Eof => bail!("unexpected end of worksheet XML"),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+// текст элемента до закрывающего тега, без экранирования
+pub(crate) fn read_formula_text<R: std::io::BufRead>(
+    rdr: &mut Reader<R>,
+    buf: &mut Vec<u8>,
+    end: &[u8],
+) -> Result<String> {
+    let mut raw = String::new();
+    loop {
+        buf.clear();
+        match rdr.read_event_into(buf)? {
+            Event::Text(t) => raw.push_str(&t.decode()?),
+            Event::GeneralRef(r) => {
+                raw.push('&');
+                raw.push_str(&r.decode()?);
+                raw.push(';');
+            }
+            Event::CData(c) => raw.push_str(&quick_xml::escape::escape(c.decode()?)),
+            Event::End(e) if e.name().as_ref() == end => break,
+            Event::Eof => bail!("unexpected end of worksheet XML"),
+            _ => {}
+        }
+    }
+    Ok(quick_xml::escape::unescape(&raw)?.into_owned())
+}
+
+// общая формула, чья главная ячейка удаляется: её место займёт первая уцелевшая ячейка группы
+struct Promoted {
+    /// Исходные (до сдвига) столбец и строка новой главной ячейки.
+    cell: (u32, u32),
+    /// Формула главной ячейки, перенесённая на `cell`.
+    text: String,
+    /// Диапазон группы после сдвига.
+    sqref: String,
+}
+
+// si -> новая главная ячейка для групп общих формул, потерявших главную
+fn shared_promotions(xml: &[u8], shift: Shift) -> Result<HashMap<String, Promoted>> {
+    let mut out = HashMap::new();
+    if !shift.delete || memmem::find(xml, br#"t="shared""#).is_none() {
+        return Ok(out);
+    }
+    let mut rdr = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut cell = None;
+    let mut masters: HashMap<String, ((u32, u32), String, String)> = HashMap::new();
+    let mut dependents: Vec<(String, (u32, u32))> = Vec::new();
+    loop {
+        buf.clear();
+        let (e, empty) = match rdr.read_event_into(&mut buf)? {
+            Event::Start(e) => (e.into_owned(), false),
+            Event::Empty(e) => (e.into_owned(), true),
+            Event::Eof => break,
+            _ => continue,
+        };
+        match e.name().as_ref() {
+            b"c" => cell = attr(&e, b"r").and_then(|r| parse_cell_ref(&r).ok()),
+            b"f" if attr(&e, b"t").as_deref() == Some("shared") => {
+                let (Some(si), Some(at)) = (attr(&e, b"si"), cell) else {
+                    continue;
+                };
+                let text = if empty {
+                    String::new()
+                } else {
+                    read_formula_text(&mut rdr, &mut buf, b"f")?
+                };
+                match attr(&e, b"ref") {
+                    Some(r) if !text.is_empty() => {
+                        masters.insert(si, (at, text, r));
+                    }
+                    _ => dependents.push((si, at)),
+                }
+            }
+            _ => {}
+        }
+    }
+    for (si, ((col, row), text, r)) in masters {
+        if moved_cell(shift, col, row).is_some() {
+            continue;
+        }
+        // уцелевших ячеек нет – группа исчезает вместе со своими ячейками
+        let Some(&(_, (dc, dr))) = dependents
+            .iter()
+            .find(|(s, (c, r))| *s == si && moved_cell(shift, *c, *r).is_some())
+        else {
+            continue;
+        };
+        let text = offset_formula(&text, dr as i64 - row as i64, dc as i64 - col as i64);
+        let sqref = shift.sqref(&r);
+        out.insert(
+            si,
+            Promoted {
+                cell: (dc, dr),
+                text,
+                sqref,
+            },
+        );
+    }
+    Ok(out)
+}
+
+/// Rewrites a worksheet: renumbers rows and cells, drops deleted rows/columns and moves every
+/// reference (`mergeCells`, hyperlinks, validations, conditional formats, formulas, …).
+pub(crate) fn shift_sheet_xml(
+    xml: &[u8],
+    shift: Shift,
+    sheet_name: Option<&str>,
+) -> Result<Vec<u8>> {
+    let mut rdr = Reader::from_reader(xml);
+    let mut wr = Writer::new(Vec::with_capacity(xml.len()));
+    let mut buf = Vec::new();
+    let promotions = shared_promotions(xml, shift)?;
+    let mut cell = None;
+
+    loop {
+        buf.clear();
+        let ev = rdr.read_event_into(&mut buf)?;
+        let (e, empty) = match &ev {
+            Event::Start(e) => (e, false),
+            Event::Empty(e) => (e, true),
+            Event::Eof => break,
+            _ => {
+                wr.write_event(ev.borrow())?;
+                continue;
+            }
+        };
+        let name = e.name().as_ref().to_vec();
+        if name == b"c" {
+            cell = attr(e, b"r").and_then(|r| parse_cell_ref(&r).ok());
+        }
+
+        // зависимая ячейка общей формулы становится главной вместо удалённой
+        if name == b"f"
+            && let Some(p) = attr(e, b"si")
+                .and_then(|si| promotions.get(&si))
+                .filter(|p| cell == Some(p.cell))
+        {
+            let mut tag = without_attr(e, b"ref");
+            tag.push_attribute(("ref", p.sqref.as_str()));
+            if !empty {
+                skip_element(&mut rdr, &mut buf)?;
+            }
+            wr.write_event(Event::Start(tag))?;
+            let text = shift.formula(&p.text, sheet_name);
+            wr.write_event(Event::Text(BytesText::new(&text)))?;
+            wr.write_event(Event::End(quick_xml::events::BytesEnd::new("f")))?;
+            continue;
+        }
+
+        // None – выкинуть элемент целиком
+        let replaced: Option<BytesStart> = match name.as_slice() {
+            // spans – лишь подсказка, после сдвига столбцов проще её убрать
+            b"row" if shift.axis == Axis::Col => Some(without_attr(e, b"spans")),
+            b"row" => match attr(e, b"r").and_then(|r| r.parse::<u32>().ok()) {
+                Some(r) => match shift.one(r) {
+                    Some(r) => Some(with_attr(e, b"r", &r.to_string())),
+                    None if !shift.delete => bail!("row {r} would move past the end of the sheet"),
+                    None => None,
+                },
+                None => Some(e.to_owned()),
+            },
+            b"c" => match attr(e, b"r").and_then(|r| parse_cell_ref(&r).ok()) {
+                Some((col, row)) => {
+                    let moved = match shift.axis {
+                        Axis::Row => shift.one(row).map(|row| (col, row)),
+                        Axis::Col => shift.one(col).map(|col| (col, row)),
+                    };
+                    match moved {
+                        Some((col, row)) => {
+                            Some(with_attr(e, b"r", &format!("{}{row}", col_letter(col))))
+                        }
+                        None if !shift.delete => bail!(
+                            "cell {}{row} would move past the end of the sheet",
+                            col_letter(col)
+                        ),
+                        None => None,
+                    }
+                }
+                None => Some(e.to_owned()),
+            },
+            // объединение, схлопнувшееся до одной ячейки, больше не нужно
+            b"mergeCell" => attr(e, b"ref")
+                .map(|r| shift.sqref(&r))
+                .filter(|s| parse_area(s).is_some_and(|a| a.end.is_some_and(|end| end != a.start)))
+                .map(|s| with_attr(e, b"ref", &s)),
+            b"hyperlink" | b"autoFilter" => match attr(e, b"ref") {
+                Some(r) => Some(shift.sqref(&r))
+                    .filter(|s| !s.is_empty())
+                    .map(|s| with_attr(e, b"ref", &s)),
+                None => Some(e.to_owned()),
+            },
+            b"dataValidation" | b"conditionalFormatting" => match attr(e, b"sqref") {
+                Some(r) => Some(shift.sqref(&r))
+                    .filter(|s| !s.is_empty())
+                    .map(|s| with_attr(e, b"sqref", &s)),
+                None => Some(e.to_owned()),
+            },
+            // от диапазона формулы ничего не осталось – формула уходит, значение остаётся
+            b"f" => match attr(e, b"ref").map(|r| shift.sqref(&r)) {
+                Some(s) if s.is_empty() => None,
+                Some(s) => Some(with_attr(e, b"ref", &s)),
+                None => Some(e.to_owned()),
+            },
+            b"dimension" => Some(match attr(e, b"ref").map(|r| shift.sqref(&r)) {
+                Some(s) if !s.is_empty() => with_attr(e, b"ref", &s),
+                _ => e.to_owned(),
+            }),
+            b"selection" => {
+                let mut t = e.to_owned();
+                for key in [&b"activeCell"[..], b"sqref"] {
+                    if let Some(s) = attr(&t, key).map(|r| shift.sqref(&r))
+                        && !s.is_empty()
+                    {
+                        t = with_attr(&t, key, &s);
+                    }
+                }
+                Some(t)
+            }
+            b"pane" => Some(match attr(e, b"topLeftCell").map(|r| shift.sqref(&r)) {
+                Some(s) if !s.is_empty() => with_attr(e, b"topLeftCell", &s),
+                _ => e.to_owned(),
+            }),
+            _ => Some(e.to_owned()),
+        };
+
+        let Some(tag) = replaced else {
+            if !empty {
+                skip_element(&mut rdr, &mut buf)?;
+            }
+            continue;
+        };
+        if empty {
+            wr.write_event(Event::Empty(tag))?;
+            continue;
+        }
+        wr.write_event(Event::Start(tag))?;
+        if matches!(
+            name.as_slice(),
+            b"f" | b"formula" | b"formula1" | b"formula2"
+        ) {
+            let text = read_formula_text(&mut rdr, &mut buf, &name)?;
+            let text = shift.formula(&text, sheet_name);
+            wr.write_event(Event::Text(BytesText::new(&text)))?;
+            wr.write_event(Event::End(quick_xml::events::BytesEnd::new(
+                String::from_utf8_lossy(&name),
+            )))?;
+        }
+    }
+
+    let mut out = wr.into_inner();
+    fix_block(&mut out, b"mergeCells", b"<mergeCell ");
+    fix_block(&mut out, b"hyperlinks", b"<hyperlink ");
+    fix_block(&mut out, b"dataValidations", b"<dataValidation ");
+    Ok(out)
+}
+
+// обновляет count у контейнера; пустой контейнер удаляется целиком
+fn fix_block(xml: &mut Vec<u8>, tag: &[u8], child: &[u8]) {
+    let mut open = b"<".to_vec();
+    open.extend_from_slice(tag);
+    let Some(start) = memmem::find(xml, &open) else {
+        return;
+    };
+    // <mergeCells .../> или <mergeCells ...>...</mergeCells>
+    let Some(head_end) = find_bytes_from(xml, b">", start) else {
+        return;
+    };
+    let end = if xml[head_end - 1] == b'/' {
+        head_end + 1
+    } else {
+        let mut close = b"</".to_vec();
+        close.extend_from_slice(tag);
+        close.push(b'>');
+        match find_bytes_from(xml, &close, head_end) {
+            Some(p) => p + close.len(),
+            None => return,
+        }
+    };
+    let n = memmem::find_iter(&xml[start..end], child).count();
+    if n == 0 {
+        xml.drain(start..end);
+        return;
+    }
+    let key = b" count=\"";
+    if let Some(a) = find_bytes_from(xml, key, start).filter(|&a| a < head_end) {
+        let v0 = a + key.len();
+        if let Some(v1) = find_bytes_from(xml, b"\"", v0) {
+            xml.splice(v0..v1, n.to_string().into_bytes());
+        }
+    }
+}
+
+const FORMULA_TAGS: [&[u8]; 4] = [b"f", b"formula", b"formula1", b"formula2"];
+
+// переписывает текст всех элементов `tags`; None – ничего не изменилось
+pub(crate) fn rewrite_formula_elements(
+    xml: &[u8],
+    tags: &[&[u8]],
+    mut f: impl FnMut(&str) -> String,
+) -> Result<Option<Vec<u8>>> {
+    let mut rdr = Reader::from_reader(xml);
+    let mut wr = Writer::new(Vec::with_capacity(xml.len()));
+    let mut buf = Vec::new();
+    let mut changed = false;
+    loop {
+        buf.clear();
+        match rdr.read_event_into(&mut buf)? {
+            Event::Eof => break,
+            Event::Start(e) if tags.contains(&e.name().as_ref()) => {
+                let name = e.name().as_ref().to_vec();
+                wr.write_event(Event::Start(e.into_owned()))?;
+                let text = read_formula_text(&mut rdr, &mut buf, &name)?;
+                let new_text = f(&text);
+                changed |= new_text != text;
+                wr.write_event(Event::Text(BytesText::new(&new_text)))?;
+                wr.write_event(Event::End(quick_xml::events::BytesEnd::new(
+                    String::from_utf8_lossy(&name),
+                )))?;
+            }
+            ev => wr.write_event(ev)?,
+        }
+    }
+    Ok(changed.then(|| wr.into_inner()))
+}
+
+impl XlsxEditor {
+    /// Applies `t` to the formulas of every sheet (except `skip`, a part path) and to the
+    /// defined names of the workbook.
+    pub(crate) fn rewrite_workbook_formulas(
+        &mut self,
+        t: RefTransform,
+        skip: Option<&str>,
+    ) -> Result<()> {
+        for (name, path) in self.sheet_parts()? {
+            if skip == Some(path.as_str()) {
+                continue;
+            }
+            let Some(xml) = self.sheet_part_xml(&path)? else {
+                continue;
+            };
+            if memmem::find(&xml, b"<f").is_none() && memmem::find(&xml, b"<formula").is_none() {
+                continue;
+            }
+            let Some(xml) =
+                rewrite_formula_elements(&xml, &FORMULA_TAGS, |f| transform_formula(f, &name, t))?
+            else {
+                continue;
+            };
+            if path == self.sheet_path {
+                self.sheet_xml = xml;
+            } else {
+                self.loaded_files.remove(&path);
+                self.new_files.insert(path, xml);
+            }
+        }
+        // у имён нет «своего» листа: трогаем только ссылки с явным листом
+        if memmem::find(&self.workbook_xml, b"<definedName").is_some()
+            && let Some(xml) =
+                rewrite_formula_elements(&self.workbook_xml, &[b"definedName"], |f| {
+                    transform_formula(f, "", t)
+                })?
+        {
+            self.workbook_xml = xml;
+        }
+        Ok(())
+    }
+}
+
+impl XlsxEditor {
+    /// Inserts `n` empty rows before row `at` (1-based) on the current sheet.
+    ///
+    /// Rows below move down; cell coordinates, merged ranges, hyperlinks, notes, data
+    /// validations, conditional formats, the autofilter and formula references on the sheet
+    /// (and defined names pointing at it) are shifted accordingly. Ranges that span `at` grow.
+    pub fn insert_rows(&mut self, at: u32, n: u32) -> Result<&mut Self> {
+        if at == 0 || at > MAX_ROW {
+            bail!("row {at} is out of range");
+        }
+        if n == 0 {
+            return Ok(self);
+        }
+        self.apply_shift(Shift {
+            axis: Axis::Row,
+            at,
+            n,
+            delete: false,
+        })
+    }
+
+    /// Deletes rows `at..at + n` (1-based) from the current sheet.
+    ///
+    /// Rows below move up and references are shifted as in [`XlsxEditor::insert_rows`];
+    /// references to deleted cells become `#REF!`, ranges that only partly covered them shrink
+    /// and notes on deleted cells are removed.
+    pub fn delete_rows(&mut self, at: u32, n: u32) -> Result<&mut Self> {
+        if at == 0 || at > MAX_ROW {
+            bail!("row {at} is out of range");
+        }
+        if n == 0 {
+            return Ok(self);
+        }
+        let n = n.min(MAX_ROW - at + 1);
+        self.apply_shift(Shift {
+            axis: Axis::Row,
+            at,
+            n,
+            delete: true,
+        })
+    }
+
+    /// Inserts `n` empty columns before column `col` (`"C"`) on the current sheet.
+    ///
+    /// Columns to the right move; cell coordinates, `<cols>` widths and styles, merged
+    /// ranges, the autofilter and formula references are shifted as in
+    /// [`XlsxEditor::insert_rows`].
+    pub fn insert_columns(&mut self, col: &str, n: u32) -> Result<&mut Self> {
+        let at = parse_col(col)?;
+        if n == 0 {
+            return Ok(self);
+        }
+        self.apply_shift(Shift {
+            axis: Axis::Col,
+            at,
+            n,
+            delete: false,
+        })
+    }
+
+    /// Deletes `n` columns starting at column `col` (`"C"`) from the current sheet.
+    ///
+    /// References to deleted cells become `#REF!`, ranges that only partly covered them shrink.
+    pub fn delete_columns(&mut self, col: &str, n: u32) -> Result<&mut Self> {
+        let at = parse_col(col)?;
+        if n == 0 {
+            return Ok(self);
+        }
+        let n = n.min(MAX_COL - at + 1);
+        self.apply_shift(Shift {
+            axis: Axis::Col,
+            at,
+            n,
+            delete: true,
+        })
+    }
+
+    fn apply_shift(&mut self, shift: Shift) -> Result<&mut Self> {
+        let sheet_name = self.current_sheet_name();
+        let rids_before = hyperlink_rids(&self.sheet_xml);
+        self.sheet_xml = shift_sheet_xml(&self.sheet_xml, shift, sheet_name.as_deref())?;
+        // внешние ссылки из удалённой полосы уносят с собой и связь в rels
+        let rids_after = hyperlink_rids(&self.sheet_xml);
+        for rid in rids_before.difference(&rids_after) {
+            self.remove_sheet_relationship(rid)?;
+        }
+        if shift.axis == Axis::Col {
+            self.shift_cols(|c| shift.one(c))?;
+        }
+        self.shift_comments(shift)?;
+        self.last_row = calc_last_row(&self.sheet_xml);
+        if let Some(name) = &sheet_name {
+            let path = self.sheet_path.clone();
+            self.rewrite_workbook_formulas(shift.transform(name), Some(&path))?;
+        }
+        Ok(self)
+    }
+}
+
+// "C" → 2
+fn parse_col(col: &str) -> Result<u32> {
+    let col = col.trim().trim_start_matches('$');
+    if col.is_empty() || col.len() > 3 || !col.bytes().all(|b| b.is_ascii_alphabetic()) {
+        bail!("invalid column `{col}`");
+    }
+    let idx = col_index(col) as u32;
+    if idx > MAX_COL {
+        bail!("column `{col}` is past XFD");
+    }
+    Ok(idx)
+}
@@ -2,10 +2,15 @@
 
 use anyhow::{Context, Result, bail};
 use memchr::{memchr, memmem, memrchr};
-use quick_xml::{Reader, events::Event};
+use quick_xml::{
+    Reader,
+    escape::escape,
+    events::{BytesStart, Event},
+};
 use std::collections::HashMap;
 use std::{fmt, str::FromStr};
 
+use crate::sheet_copy::{append_to_block, block_children, raw_attr, set_raw_attr};
 use crate::style::util::{bump_count, col_index, find_bytes_from};
 use crate::{FontKey, StyleIndex, StyleKey, XfParts, XlsxEditor};
 
@@ -18,20 +23,26 @@ pub use util::{col_letter, split_coord};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum HorizAlignment {
+    General,
     Left,
     Center,
     Right,
     Fill,
     Justify,
+    CenterContinuous,
+    Distributed,
 }
 impl fmt::Display for HorizAlignment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
+            HorizAlignment::General => "general",
             HorizAlignment::Left => "left",
             HorizAlignment::Center => "center",
             HorizAlignment::Right => "right",
             HorizAlignment::Fill => "fill",
             HorizAlignment::Justify => "justify",
+            HorizAlignment::CenterContinuous => "centerContinuous",
+            HorizAlignment::Distributed => "distributed",
         })
     }
 }
@@ -39,11 +50,14 @@ impl FromStr for HorizAlignment {
     type Err = anyhow::Error;
     fn from_str(s: &str) -> Result<Self> {
         Ok(match s {
+            "general" => HorizAlignment::General,
             "left" => HorizAlignment::Left,
             "center" => HorizAlignment::Center,
             "right" => HorizAlignment::Right,
             "fill" => HorizAlignment::Fill,
             "justify" => HorizAlignment::Justify,
+            "centerContinuous" => HorizAlignment::CenterContinuous,
+            "distributed" => HorizAlignment::Distributed,
             _ => bail!("Unknown horizontal alignment: {s}"),
         })
     }
@@ -55,6 +69,7 @@ pub enum VertAlignment {
     Center,
     Bottom,
     Justify,
+    Distributed,
 }
 impl fmt::Display for VertAlignment {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -63,6 +78,7 @@ impl fmt::Display for VertAlignment {
             VertAlignment::Center => "center",
             VertAlignment::Bottom => "bottom",
             VertAlignment::Justify => "justify",
+            VertAlignment::Distributed => "distributed",
         })
     }
 }
@@ -74,18 +90,857 @@ impl FromStr for VertAlignment {
             "center" => VertAlignment::Center,
             "bottom" => VertAlignment::Bottom,
             "justify" => VertAlignment::Justify,
+            "distributed" => VertAlignment::Distributed,
             _ => bail!("Unknown vertical alignment: {s}"),
         })
     }
 }
 
-#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ReadingOrder {
+    Context,
+    LeftToRight,
+    RightToLeft,
+}
+impl ReadingOrder {
+    fn value(self) -> u8 {
+        match self {
+            ReadingOrder::Context => 0,
+            ReadingOrder::LeftToRight => 1,
+            ReadingOrder::RightToLeft => 2,
+        }
+    }
+}
+impl FromStr for ReadingOrder {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "context" | "0" => ReadingOrder::Context,
+            "ltr" | "1" => ReadingOrder::LeftToRight,
+            "rtl" | "2" => ReadingOrder::RightToLeft,
+            _ => bail!("Unknown reading order: {s}"),
+        })
+    }
+}
+
+/// Вертикальный текст (буквы друг под другом) в `text_rotation`.
+pub const TEXT_ROTATION_VERTICAL: u32 = 255;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
 pub struct AlignSpec {
     pub horiz: Option<HorizAlignment>,
     pub vert: Option<VertAlignment>,
     pub wrap: bool,
 }
 
+/// Full cell alignment for [`XlsxEditor::set_alignment_spec`]: everything [`AlignSpec`]
+/// has plus indent, text rotation, shrink-to-fit and reading order.
+///
+/// Build it with `AlignmentSpec::default()` (or `From<AlignSpec>`) and the `with_*`
+/// methods; the struct is `#[non_exhaustive]` so more options can be added later.
+#[non_exhaustive]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AlignmentSpec {
+    pub horiz: Option<HorizAlignment>,
+    pub vert: Option<VertAlignment>,
+    pub wrap: bool,
+    /// Indent level for left/right/distributed text.
+    pub indent: Option<u32>,
+    /// 0–90 counter-clockwise, 91–180 clockwise (`90 + deg`), or
+    /// [`TEXT_ROTATION_VERTICAL`].
+    pub text_rotation: Option<u32>,
+    /// `Some(false)` turns off shrink-to-fit a cell already has; `None` keeps it.
+    pub shrink_to_fit: Option<bool>,
+    pub reading_order: Option<ReadingOrder>,
+}
+
+impl From<AlignSpec> for AlignmentSpec {
+    fn from(a: AlignSpec) -> Self {
+        AlignmentSpec {
+            horiz: a.horiz,
+            vert: a.vert,
+            wrap: a.wrap,
+            ..Default::default()
+        }
+    }
+}
+
+impl AlignmentSpec {
+    pub fn with_horiz(mut self, horiz: HorizAlignment) -> Self {
+        self.horiz = Some(horiz);
+        self
+    }
+
+    pub fn with_vert(mut self, vert: VertAlignment) -> Self {
+        self.vert = Some(vert);
+        self
+    }
+
+    pub fn with_wrap(mut self, wrap: bool) -> Self {
+        self.wrap = wrap;
+        self
+    }
+
+    pub fn with_indent(mut self, indent: u32) -> Self {
+        self.indent = Some(indent);
+        self
+    }
+
+    /// See [`AlignmentSpec::text_rotation`].
+    pub fn with_text_rotation(mut self, rotation: u32) -> Self {
+        self.text_rotation = Some(rotation);
+        self
+    }
+
+    pub fn with_shrink_to_fit(mut self, shrink: bool) -> Self {
+        self.shrink_to_fit = Some(shrink);
+        self
+    }
+
+    pub fn with_reading_order(mut self, order: ReadingOrder) -> Self {
+        self.reading_order = Some(order);
+        self
+    }
+
+    fn validate(&self) -> Result<()> {
+        if let Some(r) = self.text_rotation
+            && r > 180
+            && r != TEXT_ROTATION_VERTICAL
+        {
+            bail!("text rotation must be 0..=180 or 255, got {r}");
+        }
+        if let Some(i) = self.indent
+            && i > 250
+        {
+            bail!("indent must be 0..=250, got {i}");
+        }
+        Ok(())
+    }
+
+    fn is_empty(&self) -> bool {
+        *self == AlignmentSpec::default()
+    }
+
+    // выключенный флаг в готовом стиле – то же, что отсутствующий
+    fn normalized(mut self) -> Self {
+        if self.shrink_to_fit == Some(false) {
+            self.shrink_to_fit = None;
+        }
+        self
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<alignment");
+        if let Some(h) = &self.horiz {
+            xml.push_str(&format!(r#" horizontal="{h}""#));
+        }
+        if let Some(v) = &self.vert {
+            xml.push_str(&format!(r#" vertical="{v}""#));
+        }
+        if let Some(r) = self.text_rotation {
+            xml.push_str(&format!(r#" textRotation="{r}""#));
+        }
+        if self.wrap {
+            xml.push_str(r#" wrapText="1""#);
+        }
+        if let Some(i) = self.indent {
+            xml.push_str(&format!(r#" indent="{i}""#));
+        }
+        if self.shrink_to_fit == Some(true) {
+            xml.push_str(r#" shrinkToFit="1""#);
+        }
+        if let Some(o) = self.reading_order {
+            xml.push_str(&format!(r#" readingOrder="{}""#, o.value()));
+        }
+        xml.push_str("/>");
+        xml
+    }
+}
+
+fn read_alignment(e: &BytesStart) -> Result<AlignmentSpec> {
+    let mut spec = AlignmentSpec::default();
+    for a in e.attributes().with_checks(false).flatten() {
+        let v = String::from_utf8_lossy(&a.value).into_owned();
+        let on = v == "1" || v == "true";
+        match a.key.as_ref() {
+            b"horizontal" => spec.horiz = Some(v.parse()?),
+            b"vertical" => spec.vert = Some(v.parse()?),
+            b"wrapText" => spec.wrap = on,
+            b"indent" => spec.indent = v.parse().ok().filter(|&i| i != 0),
+            b"textRotation" => spec.text_rotation = v.parse().ok().filter(|&r| r != 0),
+            b"shrinkToFit" => spec.shrink_to_fit = on.then_some(true),
+            b"readingOrder" => spec.reading_order = Some(v.parse()?),
+            _ => {}
+        }
+    }
+    Ok(spec)
+}
+
+/* ========================== PROTECTION API ================================ */
+
+/// Cell protection; only takes effect once the sheet is protected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CellProtection {
+    /// The cell can't be edited (Excel's default for every cell).
+    pub locked: bool,
+    /// The formula is hidden from the formula bar.
+    pub hidden: bool,
+}
+
+impl Default for CellProtection {
+    fn default() -> Self {
+        Self {
+            locked: true,
+            hidden: false,
+        }
+    }
+}
+
+impl CellProtection {
+    fn to_xml(self) -> String {
+        format!(
+            r#"<protection locked="{}" hidden="{}"/>"#,
+            self.locked as u8, self.hidden as u8
+        )
+    }
+}
+
+fn read_protection(e: &BytesStart) -> CellProtection {
+    let mut p = CellProtection::default();
+    for a in e.attributes().with_checks(false).flatten() {
+        let on = matches!(&*a.value, b"1" | b"true");
+        match a.key.as_ref() {
+            b"locked" => p.locked = on,
+            b"hidden" => p.hidden = on,
+            _ => {}
+        }
+    }
+    p
+}
+
+/* ========================== FONT API ====================================== */
+
+/// Color of a font or a fill.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Color {
+    /// ARGB or RGB hex (`"FF0000"`).
+    Rgb(String),
+    /// Theme color (0–11); `tint` from -1.0 (darker) to 1.0 (lighter), 0.0 – as is.
+    Theme { index: u32, tint: f64 },
+    /// Legacy palette index.
+    Indexed(u32),
+}
+
+impl Color {
+    pub fn rgb(rgb: &str) -> Self {
+        Color::Rgb(rgb.to_owned())
+    }
+
+    pub fn theme(index: u32) -> Self {
+        Color::Theme { index, tint: 0.0 }
+    }
+
+    pub fn theme_tint(index: u32, tint: f64) -> Self {
+        Color::Theme { index, tint }
+    }
+
+    pub fn indexed(index: u32) -> Self {
+        Color::Indexed(index)
+    }
+
+    /// `<{tag} .../>`; RGB приводится к ARGB в верхнем регистре, так что строка годится как ключ.
+    pub(crate) fn to_xml(&self, tag: &str) -> String {
+        match self {
+            Color::Rgb(rgb) => {
+                let mut c = rgb.to_ascii_uppercase();
+                if c.len() == 6 {
+                    c.insert_str(0, "FF");
+                }
+                format!(r#"<{tag} rgb="{}"/>"#, escape(&c))
+            }
+            Color::Theme { index, tint } if *tint == 0.0 => format!(r#"<{tag} theme="{index}"/>"#),
+            Color::Theme { index, tint } => format!(r#"<{tag} theme="{index}" tint="{tint}"/>"#),
+            Color::Indexed(i) => format!(r#"<{tag} indexed="{i}"/>"#),
+        }
+    }
+}
+
+// цвет из атрибутов <color>/<fgColor>/...; false – есть то, что Color не хранит
+fn read_color(e: &BytesStart) -> (Option<Color>, bool) {
+    let (mut rgb, mut theme, mut indexed, mut tint) = (None, None, None, 0.0);
+    let mut exact = true;
+    for a in e.attributes().with_checks(false).flatten() {
+        let v = String::from_utf8_lossy(&a.value).into_owned();
+        match a.key.as_ref() {
+            b"rgb" => rgb = Some(v),
+            b"theme" => theme = v.parse().ok(),
+            b"indexed" => indexed = v.parse().ok(),
+            b"tint" => tint = v.parse().unwrap_or(0.0),
+            b"auto" => {}
+            _ => exact = false,
+        }
+    }
+    let color = match (rgb, theme, indexed) {
+        (Some(rgb), None, None) => Some(Color::Rgb(rgb)),
+        (None, Some(index), None) => Some(Color::Theme { index, tint }),
+        (None, None, Some(i)) => Some(Color::Indexed(i)),
+        (None, None, None) => None,
+        _ => {
+            exact = false;
+            None
+        }
+    };
+    if tint != 0.0 && !matches!(color, Some(Color::Theme { .. })) {
+        exact = false;
+    }
+    (color, exact)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Underline {
+    Single,
+    Double,
+    SingleAccounting,
+    DoubleAccounting,
+}
+impl fmt::Display for Underline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Underline::Single => "single",
+            Underline::Double => "double",
+            Underline::SingleAccounting => "singleAccounting",
+            Underline::DoubleAccounting => "doubleAccounting",
+        })
+    }
+}
+impl FromStr for Underline {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "single" => Underline::Single,
+            "double" => Underline::Double,
+            "singleAccounting" => Underline::SingleAccounting,
+            "doubleAccounting" => Underline::DoubleAccounting,
+            _ => bail!("Unknown underline: {s}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FontVertAlign {
+    Superscript,
+    Subscript,
+}
+impl fmt::Display for FontVertAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FontVertAlign::Superscript => "superscript",
+            FontVertAlign::Subscript => "subscript",
+        })
+    }
+}
+impl FromStr for FontVertAlign {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "superscript" => FontVertAlign::Superscript,
+            "subscript" => FontVertAlign::Subscript,
+            _ => bail!("Unknown font vertical alignment: {s}"),
+        })
+    }
+}
+
+/// Full font description for [`XlsxEditor::set_font_spec`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontSpec {
+    pub name: String,
+    pub size: f32,
+    pub bold: bool,
+    pub italic: bool,
+    pub color: Option<Color>,
+    pub underline: Option<Underline>,
+    pub strike: bool,
+    pub vert_align: Option<FontVertAlign>,
+    /// Font family (`2` – swiss, `1` – roman, ...).
+    pub family: Option<u32>,
+    /// Theme font scheme: `"major"`, `"minor"` or `"none"`.
+    pub scheme: Option<String>,
+}
+
+impl Default for FontSpec {
+    fn default() -> Self {
+        FontSpec::new("Calibri", 11.0)
+    }
+}
+
+impl FontSpec {
+    pub fn new(name: &str, size: f32) -> Self {
+        Self {
+            name: name.to_owned(),
+            size,
+            bold: false,
+            italic: false,
+            color: None,
+            underline: None,
+            strike: false,
+            vert_align: None,
+            family: None,
+            scheme: None,
+        }
+    }
+
+    fn key(&self) -> FontKey {
+        FontKey {
+            name: self.name.clone(),
+            size_100: (self.size * 100.0).round() as u32,
+            bold: self.bold,
+            italic: self.italic,
+            color: self.color.as_ref().map(|c| c.to_xml("color")),
+            underline: self.underline,
+            strike: self.strike,
+            vert_align: self.vert_align,
+            family: self.family,
+            scheme: self.scheme.clone(),
+        }
+    }
+}
+
+impl FontKey {
+    // порядок дочерних элементов – как пишет Excel
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<font>");
+        if self.bold {
+            xml.push_str("<b/>");
+        }
+        if self.italic {
+            xml.push_str("<i/>");
+        }
+        if self.strike {
+            xml.push_str("<strike/>");
+        }
+        match self.underline {
+            Some(Underline::Single) => xml.push_str("<u/>"),
+            Some(u) => xml.push_str(&format!(r#"<u val="{u}"/>"#)),
+            None => {}
+        }
+        if let Some(v) = self.vert_align {
+            xml.push_str(&format!(r#"<vertAlign val="{v}"/>"#));
+        }
+        xml.push_str(&format!(
+            r#"<sz val="{}"/>"#,
+            (self.size_100 as f32) / 100.0
+        ));
+        if let Some(c) = &self.color {
+            xml.push_str(c);
+        }
+        xml.push_str(&format!(r#"<name val="{}"/>"#, escape(&self.name)));
+        if let Some(f) = self.family {
+            xml.push_str(&format!(r#"<family val="{f}"/>"#));
+        }
+        if let Some(s) = &self.scheme {
+            xml.push_str(&format!(r#"<scheme val="{}"/>"#, escape(s)));
+        }
+        xml.push_str("</font>");
+        xml
+    }
+}
+
+/* ========================== FILL API ====================================== */
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PatternType {
+    None,
+    Solid,
+    MediumGray,
+    DarkGray,
+    LightGray,
+    DarkHorizontal,
+    DarkVertical,
+    DarkDown,
+    DarkUp,
+    DarkGrid,
+    DarkTrellis,
+    LightHorizontal,
+    LightVertical,
+    LightDown,
+    LightUp,
+    LightGrid,
+    LightTrellis,
+    Gray125,
+    Gray0625,
+}
+impl fmt::Display for PatternType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            PatternType::None => "none",
+            PatternType::Solid => "solid",
+            PatternType::MediumGray => "mediumGray",
+            PatternType::DarkGray => "darkGray",
+            PatternType::LightGray => "lightGray",
+            PatternType::DarkHorizontal => "darkHorizontal",
+            PatternType::DarkVertical => "darkVertical",
+            PatternType::DarkDown => "darkDown",
+            PatternType::DarkUp => "darkUp",
+            PatternType::DarkGrid => "darkGrid",
+            PatternType::DarkTrellis => "darkTrellis",
+            PatternType::LightHorizontal => "lightHorizontal",
+            PatternType::LightVertical => "lightVertical",
+            PatternType::LightDown => "lightDown",
+            PatternType::LightUp => "lightUp",
+            PatternType::LightGrid => "lightGrid",
+            PatternType::LightTrellis => "lightTrellis",
+            PatternType::Gray125 => "gray125",
+            PatternType::Gray0625 => "gray0625",
+        })
+    }
+}
+impl FromStr for PatternType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "none" => PatternType::None,
+            "solid" => PatternType::Solid,
+            "mediumGray" => PatternType::MediumGray,
+            "darkGray" => PatternType::DarkGray,
+            "lightGray" => PatternType::LightGray,
+            "darkHorizontal" => PatternType::DarkHorizontal,
+            "darkVertical" => PatternType::DarkVertical,
+            "darkDown" => PatternType::DarkDown,
+            "darkUp" => PatternType::DarkUp,
+            "darkGrid" => PatternType::DarkGrid,
+            "darkTrellis" => PatternType::DarkTrellis,
+            "lightHorizontal" => PatternType::LightHorizontal,
+            "lightVertical" => PatternType::LightVertical,
+            "lightDown" => PatternType::LightDown,
+            "lightUp" => PatternType::LightUp,
+            "lightGrid" => PatternType::LightGrid,
+            "lightTrellis" => PatternType::LightTrellis,
+            "gray125" => PatternType::Gray125,
+            "gray0625" => PatternType::Gray0625,
+            _ => bail!("Unknown pattern type: {s}"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GradientType {
+    /// Linear gradient at `degree` (0 – left to right, 90 – top to bottom).
+    Linear { degree: f64 },
+    /// Path gradient; the inner rectangle as fractions of the cell (0.5 on all sides – from the center).
+    Path {
+        left: f64,
+        right: f64,
+        top: f64,
+        bottom: f64,
+    },
+}
+
+/// Cell background.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FillSpec {
+    /// `fg` is the pattern color (the whole cell for `Solid`), `bg` – the color behind it.
+    Pattern {
+        pattern: PatternType,
+        fg: Option<Color>,
+        bg: Option<Color>,
+    },
+    /// Gradient through `stops` (`position` 0.0–1.0, color).
+    Gradient {
+        gradient: GradientType,
+        stops: Vec<(f64, Color)>,
+    },
+}
+
+impl FillSpec {
+    pub fn solid(color: Color) -> Self {
+        FillSpec::Pattern {
+            pattern: PatternType::Solid,
+            fg: Some(color),
+            bg: None,
+        }
+    }
+
+    pub fn pattern(pattern: PatternType, fg: Option<Color>, bg: Option<Color>) -> Self {
+        FillSpec::Pattern { pattern, fg, bg }
+    }
+
+    pub fn linear(degree: f64, stops: Vec<(f64, Color)>) -> Self {
+        FillSpec::Gradient {
+            gradient: GradientType::Linear { degree },
+            stops,
+        }
+    }
+
+    // каноничная запись <fill>: она же ключ для поиска одинаковых заливок
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<fill>");
+        match self {
+            FillSpec::Pattern { pattern, fg, bg } => {
+                // у solid фон не виден – не пишем его, чтобы одинаковые заливки совпадали
+                let bg = if *pattern == PatternType::Solid {
+                    &None
+                } else {
+                    bg
+                };
+                xml.push_str(&format!(r#"<patternFill patternType="{pattern}""#));
+                if fg.is_none() && bg.is_none() {
+                    xml.push_str("/>");
+                } else {
+                    xml.push('>');
+                    if let Some(c) = fg {
+                        xml.push_str(&c.to_xml("fgColor"));
+                    }
+                    if let Some(c) = bg {
+                        xml.push_str(&c.to_xml("bgColor"));
+                    }
+                    xml.push_str("</patternFill>");
+                }
+            }
+            FillSpec::Gradient { gradient, stops } => {
+                match gradient {
+                    GradientType::Linear { degree } => {
+                        xml.push_str(&format!(r#"<gradientFill degree="{degree}">"#))
+                    }
+                    GradientType::Path {
+                        left,
+                        right,
+                        top,
+                        bottom,
+                    } => xml.push_str(&format!(
+                        r#"<gradientFill type="path" left="{left}" right="{right}" top="{top}" bottom="{bottom}">"#
+                    )),
+                }
+                for (pos, c) in stops {
+                    xml.push_str(&format!(
+                        r#"<stop position="{pos}">{}</stop>"#,
+                        c.to_xml("color")
+                    ));
+                }
+                xml.push_str("</gradientFill>");
+            }
+        }
+        xml.push_str("</fill>");
+        xml
+    }
+}
+
+/* ========================== BORDER API ==================================== */
+
+/// One edge of a cell border.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderSide {
+    /// Line style: `thin`, `medium`, `thick`, `double`, `dashed`, `dotted`, ...
+    /// `none` removes the edge.
+    pub style: String,
+    /// Line color; `None` – automatic.
+    pub color: Option<Color>,
+}
+
+impl BorderSide {
+    pub fn new(style: &str) -> Self {
+        Self {
+            style: style.to_owned(),
+            color: None,
+        }
+    }
+
+    /// ARGB or RGB hex color (`"FF0000"`).
+    pub fn with_color(self, rgb: &str) -> Self {
+        self.with_color_spec(Color::rgb(rgb))
+    }
+
+    /// RGB, theme or indexed color.
+    pub fn with_color_spec(mut self, color: Color) -> Self {
+        self.color = Some(color);
+        self
+    }
+
+    fn is_none(&self) -> bool {
+        self.style.is_empty() || self.style == "none"
+    }
+}
+
+/// Border of a cell, side by side. Sides left as `None` are not drawn.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BorderSpec {
+    pub left: Option<BorderSide>,
+    pub right: Option<BorderSide>,
+    pub top: Option<BorderSide>,
+    pub bottom: Option<BorderSide>,
+    pub diagonal: Option<BorderSide>,
+    pub diagonal_up: bool,
+    pub diagonal_down: bool,
+}
+
+impl BorderSpec {
+    /// The same line on all four sides.
+    pub fn all(side: BorderSide) -> Self {
+        Self {
+            left: Some(side.clone()),
+            right: Some(side.clone()),
+            top: Some(side.clone()),
+            bottom: Some(side),
+            ..Default::default()
+        }
+    }
+
+    /// Накладывает заданные стороны `patch` поверх текущих; сторона `none` стирает линию.
+    fn merge(&self, patch: &BorderSpec) -> BorderSpec {
+        fn pick(base: &Option<BorderSide>, patch: &Option<BorderSide>) -> Option<BorderSide> {
+            match patch {
+                Some(p) if p.is_none() => None,
+                Some(p) => Some(p.clone()),
+                None => base.clone(),
+            }
+        }
+        let mut out = BorderSpec {
+            left: pick(&self.left, &patch.left),
+            right: pick(&self.right, &patch.right),
+            top: pick(&self.top, &patch.top),
+            bottom: pick(&self.bottom, &patch.bottom),
+            diagonal: pick(&self.diagonal, &patch.diagonal),
+            diagonal_up: self.diagonal_up || patch.diagonal_up,
+            diagonal_down: self.diagonal_down || patch.diagonal_down,
+        };
+        out.normalize();
+        out
+    }
+
+    // RGB в верхнем регистре с альфой, пустые стороны – None (один ключ на одну рамку)
+    fn normalize(&mut self) {
+        for side in [
+            &mut self.left,
+            &mut self.right,
+            &mut self.top,
+            &mut self.bottom,
+            &mut self.diagonal,
+        ] {
+            if side.as_ref().is_some_and(BorderSide::is_none) {
+                *side = None;
+            }
+            if let Some(s) = side
+                && let Some(Color::Rgb(c)) = &mut s.color
+            {
+                c.make_ascii_uppercase();
+                if c.len() == 6 {
+                    c.insert_str(0, "FF");
+                }
+            }
+        }
+        if self.diagonal.is_none() {
+            self.diagonal_up = false;
+            self.diagonal_down = false;
+        }
+    }
+
+    fn to_xml(&self) -> String {
+        let mut xml = String::from("<border");
+        if self.diagonal_up {
+            xml.push_str(r#" diagonalUp="1""#);
+        }
+        if self.diagonal_down {
+            xml.push_str(r#" diagonalDown="1""#);
+        }
+        xml.push('>');
+        for (tag, side) in [
+            ("left", &self.left),
+            ("right", &self.right),
+            ("top", &self.top),
+            ("bottom", &self.bottom),
+            ("diagonal", &self.diagonal),
+        ] {
+            match side {
+                None => xml.push_str(&format!("<{tag}/>")),
+                Some(BorderSide { style, color: None }) => {
+                    xml.push_str(&format!(r#"<{tag} style="{}"/>"#, escape(style)))
+                }
+                Some(BorderSide {
+                    style,
+                    color: Some(color),
+                }) => xml.push_str(&format!(
+                    r#"<{tag} style="{}">{}</{tag}>"#,
+                    escape(style),
+                    color.to_xml("color")
+                )),
+            }
+        }
+        xml.push_str("</border>");
+        xml
+    }
+}
+
+/* ========================== DXF API ======================================= */
+
+/// Differential style: what a conditional formatting rule changes on a cell.
+/// Fields left as `None` keep the cell's own formatting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DxfStyle {
+    pub font_color: Option<Color>,
+    pub bold: Option<bool>,
+    pub italic: Option<bool>,
+    pub strike: Option<bool>,
+    pub underline: Option<Underline>,
+    /// Solid background color.
+    pub fill: Option<Color>,
+    /// Number format code, e.g. `"0.0%"`.
+    pub num_fmt: Option<String>,
+    /// The same line on all four sides.
+    pub border: Option<BorderSide>,
+}
+
+impl DxfStyle {
+    pub fn is_empty(&self) -> bool {
+        *self == DxfStyle::default()
+    }
+
+    // порядок CT_Dxf: font, numFmt, fill, alignment, protection, border
+    fn to_xml(&self, num_fmt_id: Option<u32>) -> String {
+        let mut xml = String::from("<dxf>");
+        let flag = |tag: &str, v: Option<bool>| match v {
+            Some(true) => format!("<{tag}/>"),
+            Some(false) => format!(r#"<{tag} val="0"/>"#),
+            None => String::new(),
+        };
+        let font = [
+            flag("b", self.bold),
+            flag("i", self.italic),
+            flag("strike", self.strike),
+            match self.underline {
+                Some(Underline::Single) => "<u/>".to_owned(),
+                Some(u) => format!(r#"<u val="{u}"/>"#),
+                None => String::new(),
+            },
+            self.font_color
+                .as_ref()
+                .map_or(String::new(), |c| c.to_xml("color")),
+        ]
+        .concat();
+        if !font.is_empty() {
+            xml.push_str(&format!("<font>{font}</font>"));
+        }
+        if let (Some(id), Some(code)) = (num_fmt_id, &self.num_fmt) {
+            xml.push_str(&format!(
+                r#"<numFmt numFmtId="{id}" formatCode="{}"/>"#,
+                escape(code)
+            ));
+        }
+        if let Some(c) = &self.fill {
+            // в dxf заливка сплошным цветом задаётся через bgColor
+            xml.push_str(&format!(
+                "<fill><patternFill>{}</patternFill></fill>",
+                c.to_xml("bgColor")
+            ));
+        }
+        if let Some(side) = &self.border {
+            let mut b = BorderSpec::all(side.clone());
+            b.normalize();
+            xml.push_str(&b.to_xml());
+        }
+        xml.push_str("</dxf>");
+        xml
+    }
+}
+
 /* ========================== CORE STYLE STRUCT ============================= */
 
 #[derive(Debug, Clone, Default)]
@@ -94,7 +949,10 @@ struct StyleParts {
     pub font: Option<u32>,
     pub fill: Option<u32>,
     pub border: Option<u32>,
-    pub align: Option<AlignSpec>,
+    /// Стороны рамки, накладываемые на текущую рамку ячейки (в отличие от `border`)
+    pub border_sides: Option<BorderSpec>,
+    pub align: Option<AlignmentSpec>,
+    pub protection: Option<CellProtection>,
 }
 
 /* ========================== TARGET PARSER ================================= */
@@ -138,6 +996,18 @@ fn parse_target(s: &str) -> Result<Target> {
     bail!("invalid range syntax: {s}");
 }
 
+// прямоугольник для рамок: ячейка или диапазон, (c0, r0, c1, r1) по возрастанию
+fn border_rect(range: &str) -> Result<(u32, u32, u32, u32)> {
+    match parse_target(range)? {
+        Target::Cell(cell) => {
+            let (c, r) = split_coord(&cell);
+            Ok((c, r, c, r))
+        }
+        Target::Rect { c0, r0, c1, r1 } => Ok((c0.min(c1), r0.min(r1), c0.max(c1), r0.max(r1))),
+        _ => bail!("border helpers need a cell or a rectangle, got `{range}`"),
+    }
+}
+
 fn parse_open_column_selector(s: &str) -> Option<(u32, u32)> {
     if !s.ends_with(':') {
         return None;
@@ -168,6 +1038,239 @@ fn parse_open_column_selector(s: &str) -> Option<(u32, u32)> {
     Some((col_index(&head[..p]) as u32, row_start))
 }
 
+// читает <font> (курсор сразу после открывающего тега);
+// false – шрифт с чем-то, чего нет в FontKey (charset, tint, outline...), его не переиспользуем
+fn read_font(rdr: &mut Reader<&[u8]>) -> Result<(FontKey, bool)> {
+    let mut spec = FontSpec::default();
+    let mut exact = true;
+    let mut depth = 1;
+    while depth > 0 {
+        let ev = rdr.read_event()?;
+        let fe = match &ev {
+            Event::Start(fe) => {
+                depth += 1;
+                fe
+            }
+            Event::Empty(fe) => fe,
+            Event::End(_) => {
+                depth -= 1;
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        if fe.name().as_ref() == b"color" {
+            let (color, ok) = read_color(fe);
+            spec.color = color;
+            exact &= ok;
+            continue;
+        }
+        let mut val: Option<String> = None;
+        for a in fe.attributes().with_checks(false).flatten() {
+            match a.key.as_ref() {
+                b"val" => val = Some(String::from_utf8_lossy(&a.value).into_owned()),
+                _ => exact = false,
+            }
+        }
+        // <b/> == <b val="1"/>
+        let on = !matches!(val.as_deref(), Some("0" | "false"));
+        match fe.name().as_ref() {
+            b"b" => spec.bold = on,
+            b"i" => spec.italic = on,
+            b"strike" => spec.strike = on,
+            b"u" => {
+                spec.underline = match val.as_deref() {
+                    None => Some(Underline::Single),
+                    Some("none") => None,
+                    Some(v) => Some(v.parse()?),
+                }
+            }
+            b"vertAlign" => {
+                spec.vert_align = match val.as_deref() {
+                    None | Some("baseline") => None,
+                    Some(v) => Some(v.parse()?),
+                }
+            }
+            b"sz" => spec.size = val.and_then(|v| v.parse().ok()).unwrap_or(11.0),
+            b"name" => spec.name = val.unwrap_or_default(),
+            b"family" => spec.family = val.and_then(|v| v.parse().ok()),
+            b"scheme" => spec.scheme = val,
+            _ => exact = false,
+        }
+    }
+    Ok((spec.key(), exact))
+}
+
+// читает <fill> (курсор сразу после открывающего тега); false – заливку не сможем записать так же
+fn read_fill(rdr: &mut Reader<&[u8]>) -> Result<(Option<FillSpec>, bool)> {
+    let mut fill = None;
+    let mut exact = true;
+    let mut stop_pos: Option<f64> = None;
+    let mut depth = 1;
+    while depth > 0 {
+        let ev = rdr.read_event()?;
+        let fe = match &ev {
+            Event::Start(fe) => {
+                depth += 1;
+                fe
+            }
+            Event::Empty(fe) => fe,
+            Event::End(_) => {
+                depth -= 1;
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        let num = |key: &[u8]| -> Option<f64> {
+            fe.attributes()
+                .with_checks(false)
+                .flatten()
+                .find(|a| a.key.as_ref() == key)
+                .and_then(|a| String::from_utf8_lossy(&a.value).parse().ok())
+        };
+        match (fe.name().as_ref(), &mut fill) {
+            (b"patternFill", None) => {
+                let pattern = match attr_str(fe, b"patternType") {
+                    Some(p) => p.parse()?,
+                    None => PatternType::None,
+                };
+                fill = Some(FillSpec::Pattern {
+                    pattern,
+                    fg: None,
+                    bg: None,
+                });
+            }
+            (b"gradientFill", None) => {
+                let gradient = if attr_str(fe, b"type").as_deref() == Some("path") {
+                    GradientType::Path {
+                        left: num(b"left").unwrap_or(0.0),
+                        right: num(b"right").unwrap_or(0.0),
+                        top: num(b"top").unwrap_or(0.0),
+                        bottom: num(b"bottom").unwrap_or(0.0),
+                    }
+                } else {
+                    GradientType::Linear {
+                        degree: num(b"degree").unwrap_or(0.0),
+                    }
+                };
+                fill = Some(FillSpec::Gradient {
+                    gradient,
+                    stops: Vec::new(),
+                });
+            }
+            (b"fgColor", Some(FillSpec::Pattern { fg, .. })) => {
+                let (c, ok) = read_color(fe);
+                *fg = c;
+                exact &= ok;
+            }
+            (b"bgColor", Some(FillSpec::Pattern { bg, .. })) => {
+                let (c, ok) = read_color(fe);
+                *bg = c;
+                exact &= ok;
+            }
+            (b"stop", Some(FillSpec::Gradient { .. })) => stop_pos = num(b"position"),
+            (b"color", Some(FillSpec::Gradient { stops, .. })) => {
+                let (c, ok) = read_color(fe);
+                exact &= ok;
+                match (stop_pos, c) {
+                    (Some(p), Some(c)) => stops.push((p, c)),
+                    _ => exact = false,
+                }
+            }
+            _ => exact = false,
+        }
+    }
+    Ok((fill, exact))
+}
+
+fn attr_str(e: &BytesStart, key: &[u8]) -> Option<String> {
+    e.attributes()
+        .with_checks(false)
+        .flatten()
+        .find(|a| a.key.as_ref() == key)
+        .map(|a| String::from_utf8_lossy(&a.value).into_owned())
+}
+
+// читает <border> (курсор сразу после открывающего тега);
+// второй флаг – false, если встретилось то, чего BorderSpec не хранит
+fn read_border(rdr: &mut Reader<&[u8]>, e: &BytesStart, empty: bool) -> Result<(BorderSpec, bool)> {
+    let mut spec = BorderSpec::default();
+    let mut exact = true;
+    for a in e.attributes().with_checks(false).flatten() {
+        let on = matches!(&*a.value, b"1" | b"true");
+        match a.key.as_ref() {
+            b"diagonalUp" => spec.diagonal_up = on,
+            b"diagonalDown" => spec.diagonal_down = on,
+            _ => {}
+        }
+    }
+    if empty {
+        spec.normalize();
+        return Ok((spec, exact));
+    }
+
+    let mut sides: [Option<BorderSide>; 5] = Default::default(); // left,right,top,bottom,diagonal
+    let mut current: Option<usize> = None;
+    let mut depth = 1;
+    while depth > 0 {
+        let ev = rdr.read_event()?;
+        let (be, is_start) = match &ev {
+            Event::Start(be) => (be, true),
+            Event::Empty(be) => (be, false),
+            Event::End(_) => {
+                depth -= 1;
+                if depth == 1 {
+                    current = None;
+                }
+                continue;
+            }
+            Event::Eof => break,
+            _ => continue,
+        };
+        if is_start {
+            depth += 1;
+        }
+        let side = match be.name().as_ref() {
+            b"left" | b"start" => Some(0),
+            b"right" | b"end" => Some(1),
+            b"top" => Some(2),
+            b"bottom" => Some(3),
+            b"diagonal" => Some(4),
+            _ => None,
+        };
+        if let Some(i) = side {
+            if let Some(style) = be
+                .attributes()
+                .with_checks(false)
+                .flatten()
+                .find(|a| a.key.as_ref() == b"style")
+            {
+                sides[i] = Some(BorderSide::new(&String::from_utf8_lossy(&style.value)));
+            }
+            if is_start {
+                current = Some(i);
+            }
+        } else if be.name().as_ref() == b"color"
+            && let Some(i) = current
+        {
+            let (color, ok) = read_color(be);
+            exact &= ok;
+            if let Some(s) = &mut sides[i] {
+                s.color = color;
+            }
+        }
+    }
+    let [left, right, top, bottom, diagonal] = sides;
+    spec.left = left;
+    spec.right = right;
+    spec.top = top;
+    spec.bottom = bottom;
+    spec.diagonal = diagonal;
+    spec.normalize();
+    Ok((spec, exact))
+}
+
 impl StyleIndex {
     fn build(styles: &[u8]) -> Result<Self> {
         let mut ix = StyleIndex {
@@ -176,13 +1279,16 @@ impl StyleIndex {
             next_custom_numfmt: 164,
 
             font_by_key: HashMap::new(),
-            fill_by_rgb: HashMap::new(),
+            fill_by_key: HashMap::new(),
             border_by_key: HashMap::new(),
+            borders: Vec::new(),
             xf_by_key: HashMap::new(),
+            dxf_by_key: HashMap::new(),
 
             fonts_count: 0,
             fills_count: 0,
             borders_count: 0,
+            dxfs_count: 0,
         };
 
         let mut rdr = Reader::from_reader(styles);
@@ -230,76 +1336,13 @@ impl StyleIndex {
                     break;
                 }
                 Event::Start(ref e) if in_fonts && e.name().as_ref() == b"font" => {
-                    // читаем <font> целиком
-                    let mut depth = 1;
-                    let mut bold = false;
-                    let mut italic = false;
-                    let mut size: f32 = 11.0;
-                    let mut name: String = "Calibri".into();
-
-                    while depth > 0 {
-                        match rdr.read_event()? {
-                            Event::Start(ref fe) => {
-                                depth += 1;
-                                match fe.name().as_ref() {
-                                    b"b" => bold = true,
-                                    b"i" => italic = true,
-                                    b"sz" => {
-                                        for a in fe.attributes().with_checks(false).flatten() {
-                                            if a.key.as_ref() == b"val" {
-                                                size = String::from_utf8_lossy(&a.value)
-                                                    .parse()
-                                                    .unwrap_or(11.0);
-                                            }
-                                        }
-                                    }
-                                    b"name" => {
-                                        for a in fe.attributes().with_checks(false).flatten() {
-                                            if a.key.as_ref() == b"val" {
-                                                name =
-                                                    String::from_utf8_lossy(&a.value).into_owned();
-                                            }
-                                        }
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            Event::Empty(ref fe) => match fe.name().as_ref() {
-                                b"b" => bold = true,
-                                b"i" => italic = true,
-                                b"sz" => {
-                                    for a in fe.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"val" {
-                                            size = String::from_utf8_lossy(&a.value)
-                                                .parse()
-                                                .unwrap_or(11.0);
-                                        }
-                                    }
-                                }
-                                b"name" => {
-                                    for a in fe.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"val" {
-                                            name = String::from_utf8_lossy(&a.value).into_owned();
-                                        }
-                                    }
-                                }
-                                _ => {}
-                            },
-                            Event::End(_) => depth -= 1,
-                            Event::Eof => break,
-                            _ => {}
-                        }
+                    let (key, exact) = read_font(&mut rdr)?;
+                    if exact {
+                        ix.font_by_key.entry(key).or_insert(font_id);
                     }
-
-                    let key = FontKey {
-                        name,
-                        size_100: (size * 100.0).round() as u32,
-                        bold,
-                        italic,
-                    };
-                    ix.font_by_key.entry(key).or_insert(font_id);
                     font_id += 1;
                 }
+                Event::Empty(ref e) if in_fonts && e.name().as_ref() == b"font" => font_id += 1,
                 Event::Eof => break,
                 _ => {}
             }
@@ -319,49 +1362,12 @@ impl StyleIndex {
                     break;
                 }
                 Event::Start(ref e) if in_fills && e.name().as_ref() == b"fill" => {
-                    // считываем <fill> целиком; учитываем только solid fgColor rgb
-                    let mut depth = 1;
-                    let mut rgb: Option<String> = None;
-
-                    while depth > 0 {
-                        match rdr.read_event()? {
-                            Event::Start(ref fe) => {
-                                if fe.name().as_ref() == b"fgColor" {
-                                    for a in fe.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"rgb" {
-                                            let mut v =
-                                                String::from_utf8_lossy(&a.value).into_owned();
-                                            v.make_ascii_uppercase();
-                                            rgb = Some(v);
-                                        }
-                                    }
-                                }
-                                depth += 1;
-                            }
-                            Event::Empty(ref fe) => {
-                                if fe.name().as_ref() == b"fgColor" {
-                                    for a in fe.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"rgb" {
-                                            let mut v =
-                                                String::from_utf8_lossy(&a.value).into_owned();
-                                            v.make_ascii_uppercase();
-                                            rgb = Some(v);
-                                        }
-                                    }
-                                }
-                                // depth не меняем
-                            }
-                            Event::End(_) => depth -= 1,
-                            Event::Eof => break,
-                            _ => {}
-                        }
-                    }
-
-                    if let Some(k) = rgb {
-                        ix.fill_by_rgb.entry(k).or_insert(fill_id);
+                    if let (Some(fill), true) = read_fill(&mut rdr)? {
+                        ix.fill_by_key.entry(fill.to_xml()).or_insert(fill_id);
                     }
                     fill_id += 1;
                 }
+                Event::Empty(ref e) if in_fills && e.name().as_ref() == b"fill" => fill_id += 1,
                 Event::Eof => break,
                 _ => {}
             }
@@ -369,75 +1375,26 @@ impl StyleIndex {
         ix.fills_count = fill_id;
 
         // --- borders ---
-        let mut rdr = Reader::from_reader(styles);
-        rdr.config_mut().trim_text(true);
-        let mut in_borders = false;
-        let mut border_id = 0u32;
-        while let Ok(ev) = rdr.read_event() {
-            match ev {
-                Event::Start(ref e) if e.name().as_ref() == b"borders" => in_borders = true,
-                Event::End(ref e) if e.name().as_ref() == b"borders" => {
-                    // in_borders = false;
-                    break;
-                }
-                Event::Start(ref e) if in_borders && e.name().as_ref() == b"border" => {
-                    let mut depth = 1;
-                    let mut styles = [None, None, None, None]; // left,right,top,bottom
-
-                    while depth > 0 {
-                        match rdr.read_event()? {
-                            Event::Start(ref be) => {
-                                let side = match be.name().as_ref() {
-                                    b"left" => Some(0),
-                                    b"right" => Some(1),
-                                    b"top" => Some(2),
-                                    b"bottom" => Some(3),
-                                    _ => None,
-                                };
-                                if let Some(i) = side {
-                                    for a in be.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"style" {
-                                            styles[i] = Some(
-                                                String::from_utf8_lossy(&a.value).into_owned(),
-                                            );
-                                        }
-                                    }
-                                }
-                                depth += 1;
-                            }
-                            Event::Empty(ref be) => {
-                                let side = match be.name().as_ref() {
-                                    b"left" => Some(0),
-                                    b"right" => Some(1),
-                                    b"top" => Some(2),
-                                    b"bottom" => Some(3),
-                                    _ => None,
-                                };
-                                if let Some(i) = side {
-                                    for a in be.attributes().with_checks(false).flatten() {
-                                        if a.key.as_ref() == b"style" {
-                                            styles[i] = Some(
-                                                String::from_utf8_lossy(&a.value).into_owned(),
-                                            );
-                                        }
-                                    }
-                                }
-                            }
-
-                            Event::End(_) => depth -= 1,
-                            Event::Eof => break,
-                            _ => {}
-                        }
-                    }
-
-                    // ключ только для «ровных» рамок, иначе пропускаем (мы такие и создаём)
-                    if let (Some(l), Some(r), Some(t), Some(b)) =
-                        (&styles[0], &styles[1], &styles[2], &styles[3])
-                    {
-                        if l == r && r == t && t == b {
-                            ix.border_by_key.entry(l.clone()).or_insert(border_id);
-                        }
+        let mut rdr = Reader::from_reader(styles);
+        rdr.config_mut().trim_text(true);
+        let mut in_borders = false;
+        let mut border_id = 0u32;
+        while let Ok(ev) = rdr.read_event() {
+            match ev {
+                Event::Start(ref e) if e.name().as_ref() == b"borders" => in_borders = true,
+                Event::End(ref e) if e.name().as_ref() == b"borders" => {
+                    // in_borders = false;
+                    break;
+                }
+                Event::Start(ref e) | Event::Empty(ref e)
+                    if in_borders && e.name().as_ref() == b"border" =>
+                {
+                    let (spec, exact) = read_border(&mut rdr, e, matches!(ev, Event::Empty(_)))?;
+                    // в ключ идут только рамки, которые мы можем записать так же
+                    if exact {
+                        ix.border_by_key.entry(spec.to_xml()).or_insert(border_id);
                     }
+                    ix.borders.push(spec);
                     border_id += 1;
                 }
                 Event::Eof => break,
@@ -480,30 +1437,22 @@ impl StyleIndex {
                         }
                     }
 
-                    // выцепим alignment (если есть)
-                    let mut align: Option<AlignSpec> = None;
+                    // выцепим alignment и protection (если есть)
+                    let mut align: Option<AlignmentSpec> = None;
+                    let mut protection: Option<CellProtection> = None;
                     if matches!(ev, Event::Start(_)) {
                         let mut depth = 1;
                         while depth > 0 {
-                            match rdr.read_event()? {
-                                Event::Start(ref ae) => {
-                                    depth += 1;
-                                    if ae.name().as_ref() == b"alignment" {
-                                        let mut spec = AlignSpec::default();
-                                        for a in ae.attributes().with_checks(false).flatten() {
-                                            let v = String::from_utf8_lossy(&a.value).into_owned();
-                                            match a.key.as_ref() {
-                                                b"horizontal" => spec.horiz = Some(v.parse()?),
-                                                b"vertical" => spec.vert = Some(v.parse()?),
-                                                b"wrapText" => {
-                                                    if v == "1" {
-                                                        spec.wrap = true
-                                                    }
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        align = Some(spec);
+                            let ie = rdr.read_event()?;
+                            match &ie {
+                                Event::Start(ce) | Event::Empty(ce) => {
+                                    match ce.name().as_ref() {
+                                        b"alignment" => align = Some(read_alignment(ce)?),
+                                        b"protection" => protection = Some(read_protection(ce)),
+                                        _ => {}
+                                    }
+                                    if matches!(ie, Event::Start(_)) {
+                                        depth += 1;
                                     }
                                 }
                                 Event::End(_) => depth -= 1,
@@ -519,6 +1468,7 @@ impl StyleIndex {
                         fill_id,
                         border_id,
                         align: align.clone(),
+                        protection,
                     });
 
                     let sk = StyleKey {
@@ -526,9 +1476,8 @@ impl StyleIndex {
                         font_id,
                         fill_id,
                         border_id,
-                        align: align
-                            .as_ref()
-                            .map(|a| (a.horiz.clone(), a.vert.clone(), a.wrap)),
+                        align,
+                        protection,
                     };
                     ix.xf_by_key.entry(sk).or_insert(xf_id);
                     xf_id += 1;
@@ -538,12 +1487,21 @@ impl StyleIndex {
             }
         }
 
+        // --- dxfs ---
+        // ключ – сырой элемент: свои пишем канонично, чужие совпадут только побайтно
+        for raw in block_children(styles, b"dxfs")? {
+            ix.dxf_by_key
+                .entry(String::from_utf8_lossy(&raw).into_owned())
+                .or_insert(ix.dxfs_count);
+            ix.dxfs_count += 1;
+        }
+
         Ok(ix)
     }
 }
 
 impl XlsxEditor {
-    fn style_ix_mut(&mut self) -> Result<&mut StyleIndex> {
+    pub(crate) fn style_ix_mut(&mut self) -> Result<&mut StyleIndex> {
         if self.styles_index.is_none() {
             let ix = StyleIndex::build(&self.styles_xml)?;
             self.styles_index = Some(ix);
@@ -554,13 +1512,96 @@ impl XlsxEditor {
     fn invalidate_styles_ix(&mut self) {
         self.styles_index = None;
     }
+
+    /// Для каждого style id: является ли его числовой формат датой/временем.
+    pub(crate) fn date_style_kinds(&self) -> Result<Vec<Option<DateFormatKind>>> {
+        let built;
+        let ix = match &self.styles_index {
+            Some(ix) => ix,
+            None => {
+                built = StyleIndex::build(&self.styles_xml)?;
+                &built
+            }
+        };
+        let code_by_id: HashMap<u32, &str> = ix
+            .numfmt_by_code
+            .iter()
+            .map(|(code, &id)| (id, code.as_str()))
+            .collect();
+        Ok(ix
+            .xfs
+            .iter()
+            .map(|xf| date_format_kind(xf.num_fmt_id, code_by_id.get(&xf.num_fmt_id).copied()))
+            .collect())
+    }
+}
+
+/// What a date-like number format displays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DateFormatKind {
+    Date,
+    DateTime,
+    Time,
+}
+
+/// Classifies a number format: built-in ids by the ECMA-376 table, custom codes by the
+/// date/time tokens left after dropping literals, colours and conditions.
+pub(crate) fn date_format_kind(num_fmt_id: u32, code: Option<&str>) -> Option<DateFormatKind> {
+    let Some(code) = code else {
+        return match num_fmt_id {
+            14..=17 | 27..=31 | 34..=36 | 50..=58 => Some(DateFormatKind::Date),
+            22 => Some(DateFormatKind::DateTime),
+            18..=21 | 32 | 33 | 45..=47 => Some(DateFormatKind::Time),
+            _ => None,
+        };
+    };
+    let code =
+        quick_xml::escape::unescape(code).map_or_else(|_| code.to_owned(), |c| c.into_owned());
+    // первая секция формата (положительные числа)
+    let section = code.split(';').next().unwrap_or("");
+    let mut tokens = String::new();
+    let mut chars = section.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                }
+            }
+            '\\' | '_' | '*' => {
+                chars.next();
+            }
+            '[' => {
+                let inner: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                // [h], [mm], [ss] – прошедшее время
+                if inner
+                    .chars()
+                    .all(|c| matches!(c.to_ascii_lowercase(), 'h' | 'm' | 's'))
+                {
+                    tokens.push_str(&inner.to_ascii_lowercase());
+                }
+            }
+            c => tokens.push(c.to_ascii_lowercase()),
+        }
+    }
+    let tokens = tokens.replace("am/pm", "").replace("a/p", "");
+    let has_time = tokens.contains(['h', 's']);
+    let has_date = tokens.contains(['y', 'd']) || (tokens.contains('m') && !has_time);
+    match (has_date, has_time) {
+        (true, true) => Some(DateFormatKind::DateTime),
+        (true, false) => Some(DateFormatKind::Date),
+        (false, true) => Some(DateFormatKind::Time),
+        (false, false) => None,
+    }
 }
 
 /* ========================== PUBLIC API ==================================== */
 
 impl XlsxEditor {
     pub fn set_border(&mut self, range: &str, border_style: &str) -> Result<&mut Self> {
-        let border_id = self.ensure_border(border_style)?;
+        let border_id = self.ensure_border(&BorderSpec::all(BorderSide::new(border_style)))?;
         self.apply_patch(
             range,
             StyleParts {
@@ -571,6 +1612,88 @@ impl XlsxEditor {
         Ok(self)
     }
 
+    /// Draws the given sides on every cell of `range`.
+    ///
+    /// Sides left as `None` keep whatever the cell already has; a side with
+    /// style `"none"` removes that edge.
+    ///
+    /// # Arguments
+    /// * `range` - A cell or a rectangle (e.g. `"A1:D1"`).
+    /// * `spec` - The sides to draw.
+    pub fn set_border_spec(&mut self, range: &str, spec: &BorderSpec) -> Result<&mut Self> {
+        self.apply_patch(
+            range,
+            StyleParts {
+                border_sides: Some(spec.clone()),
+                ..Default::default()
+            },
+        )?;
+        Ok(self)
+    }
+
+    /// Draws a box around the outer edge of `range`; inner cell edges are left as they are.
+    ///
+    /// # Arguments
+    /// * `range` - A cell or a rectangle (e.g. `"A10:D12"`).
+    /// * `side` - The line to draw.
+    pub fn set_outline_border(&mut self, range: &str, side: &BorderSide) -> Result<&mut Self> {
+        let (c0, r0, c1, r1) = border_rect(range)?;
+        let edge = |f: fn(&mut BorderSpec, BorderSide)| {
+            let mut spec = BorderSpec::default();
+            f(&mut spec, side.clone());
+            StyleParts {
+                border_sides: Some(spec),
+                ..Default::default()
+            }
+        };
+        self.apply_patch_rect_one_pass(c0, r0, c1, r0, &edge(|s, v| s.top = Some(v)))?;
+        self.apply_patch_rect_one_pass(c0, r1, c1, r1, &edge(|s, v| s.bottom = Some(v)))?;
+        self.apply_patch_rect_one_pass(c0, r0, c0, r1, &edge(|s, v| s.left = Some(v)))?;
+        self.apply_patch_rect_one_pass(c1, r0, c1, r1, &edge(|s, v| s.right = Some(v)))?;
+        Ok(self)
+    }
+
+    /// Draws lines between the cells of `range` without touching its outer edge.
+    ///
+    /// # Arguments
+    /// * `range` - A rectangle (e.g. `"A1:D10"`).
+    /// * `horizontal` - Line between rows, `None` to leave them unchanged.
+    /// * `vertical` - Line between columns, `None` to leave them unchanged.
+    pub fn set_inner_borders(
+        &mut self,
+        range: &str,
+        horizontal: Option<&BorderSide>,
+        vertical: Option<&BorderSide>,
+    ) -> Result<&mut Self> {
+        let (c0, r0, c1, r1) = border_rect(range)?;
+        // нижняя линия у всех строк, кроме последней; правая – у всех столбцов, кроме последнего
+        if let Some(side) = horizontal
+            && r1 > r0
+        {
+            let patch = StyleParts {
+                border_sides: Some(BorderSpec {
+                    bottom: Some(side.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            self.apply_patch_rect_one_pass(c0, r0, c1, r1 - 1, &patch)?;
+        }
+        if let Some(side) = vertical
+            && c1 > c0
+        {
+            let patch = StyleParts {
+                border_sides: Some(BorderSpec {
+                    right: Some(side.clone()),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            };
+            self.apply_patch_rect_one_pass(c0, r0, c1 - 1, r1, &patch)?;
+        }
+        Ok(self)
+    }
+
     pub fn set_font(
         &mut self,
         range: &str,
@@ -579,7 +1702,24 @@ impl XlsxEditor {
         bold: bool,
         italic: bool,
     ) -> Result<&mut Self> {
-        let font_id = self.ensure_font(name, size, bold, italic)?;
+        self.set_font_spec(
+            range,
+            &FontSpec {
+                bold,
+                italic,
+                ..FontSpec::new(name, size)
+            },
+        )
+    }
+
+    /// Sets the font of every cell in `range`, including color, underline,
+    /// strikethrough, super/subscript and family/scheme.
+    ///
+    /// # Arguments
+    /// * `range` - A cell or a rectangle (e.g. `"B2:B20"`).
+    /// * `spec` - The font to apply; identical fonts are reused.
+    pub fn set_font_spec(&mut self, range: &str, spec: &FontSpec) -> Result<&mut Self> {
+        let font_id = self.ensure_font(spec)?;
         self.apply_patch(
             range,
             StyleParts {
@@ -599,7 +1739,12 @@ impl XlsxEditor {
         italic: bool,
         align: &AlignSpec,
     ) -> Result<&mut Self> {
-        let font_id = self.ensure_font(name, size, bold, italic)?;
+        let align = AlignmentSpec::from(align.clone());
+        let font_id = self.ensure_font(&FontSpec {
+            bold,
+            italic,
+            ..FontSpec::new(name, size)
+        })?;
         self.apply_patch(
             range,
             StyleParts {
@@ -612,7 +1757,16 @@ impl XlsxEditor {
     }
 
     pub fn set_fill(&mut self, range: &str, rgb: &str) -> Result<&mut Self> {
-        let fill_id = self.ensure_fill(rgb)?;
+        self.set_fill_spec(range, &FillSpec::solid(Color::rgb(rgb)))
+    }
+
+    /// Sets a pattern or gradient background on every cell in `range`.
+    ///
+    /// # Arguments
+    /// * `range` - A cell or a rectangle (e.g. `"C2:C40"`).
+    /// * `spec` - The fill; identical fills already in `styles.xml` are reused.
+    pub fn set_fill_spec(&mut self, range: &str, spec: &FillSpec) -> Result<&mut Self> {
+        let fill_id = self.ensure_fill(spec)?;
         self.apply_patch(
             range,
             StyleParts {
@@ -624,6 +1778,18 @@ impl XlsxEditor {
     }
 
     pub fn set_alignment(&mut self, range: &str, align: &AlignSpec) -> Result<&mut Self> {
+        self.set_alignment_spec(range, &align.clone().into())
+    }
+
+    /// Sets alignment, indent, rotation, shrink-to-fit and reading order on `range`.
+    ///
+    /// Only the options set in `align` change; the rest of each cell's alignment is kept.
+    ///
+    /// # Arguments
+    /// * `range` - A cell, a rectangle or an open column selector (e.g. `"B2:B"`).
+    /// * `align` - The alignment options to apply.
+    pub fn set_alignment_spec(&mut self, range: &str, align: &AlignmentSpec) -> Result<&mut Self> {
+        align.validate()?;
         if let Some((col0, row_start)) = parse_open_column_selector(range) {
             let patch = StyleParts {
                 align: Some(align.clone()),
@@ -646,6 +1812,25 @@ impl XlsxEditor {
         )?;
         Ok(self)
     }
+    /// Locks/unlocks (and hides formulas of) every cell in `range`.
+    ///
+    /// Has no visible effect until the sheet is protected with
+    /// [`XlsxEditor::protect_sheet`]: then only unlocked cells can be edited.
+    pub fn set_cell_protection(
+        &mut self,
+        range: &str,
+        protection: CellProtection,
+    ) -> Result<&mut Self> {
+        self.apply_patch(
+            range,
+            StyleParts {
+                protection: Some(protection),
+                ..Default::default()
+            },
+        )?;
+        Ok(self)
+    }
+
     pub fn remove_style(&mut self, range: &str) -> Result<&mut Self> {
         match parse_target(range)? {
             Target::Cell(cell) => self.remove_style_from_cell(&cell)?,
@@ -696,7 +1881,7 @@ impl XlsxEditor {
             return sid;
         }
         let old_parts = self.read_style_parts(old_sid).unwrap();
-        let merged = merge_style_parts(old_parts, patch);
+        let merged = self.merge_parts(old_parts, patch).unwrap();
         let sid = self.ensure_style_from_parts(&merged).unwrap();
         cache.insert(old_sid, sid);
         sid
@@ -740,12 +1925,12 @@ impl XlsxEditor {
 
             // r="...":
             let mut row_r: Option<u32> = None;
-            if let Some(pos) = find_bytes_from(&src, b" r=\"", row_start) {
-                if pos < row_tag_end {
-                    let v0 = pos + 4;
-                    if let Some(v1) = find_bytes_from(&src, b"\"", v0) {
-                        row_r = lexical_core::parse::<u32>(&src[v0..v1]).ok();
-                    }
+            if let Some(pos) = find_bytes_from(&src, b" r=\"", row_start)
+                && pos < row_tag_end
+            {
+                let v0 = pos + 4;
+                if let Some(v1) = find_bytes_from(&src, b"\"", v0) {
+                    row_r = lexical_core::parse::<u32>(&src[v0..v1]).ok();
                 }
             }
 
@@ -851,12 +2036,13 @@ impl XlsxEditor {
                         }
 
                         let mut col_in_range = false;
-                        if let Some(ci0) = col_idx {
-                            if ci0 >= range_col_start && ci0 <= range_col_end {
-                                col_in_range = true;
-                                if !coverage.is_empty() {
-                                    coverage[(ci0 - range_col_start) as usize] = 1;
-                                }
+                        if let Some(ci0) = col_idx
+                            && ci0 >= range_col_start
+                            && ci0 <= range_col_end
+                        {
+                            col_in_range = true;
+                            if !coverage.is_empty() {
+                                coverage[(ci0 - range_col_start) as usize] = 1;
                             }
                         }
 
@@ -1003,12 +2189,10 @@ impl XlsxEditor {
                             .map(|b| b.to_ascii_uppercase())
                             .eq(col_bytes.iter().copied())
                         && val[col_len..].iter().all(|b| b.is_ascii_digit())
+                        && let Ok(row_num) = lexical_core::parse::<u32>(&val[col_len..])
+                        && row_num >= row_start
                     {
-                        if let Ok(row_num) = lexical_core::parse::<u32>(&val[col_len..]) {
-                            if row_num >= row_start {
-                                in_target_col = true;
-                            }
-                        }
+                        in_target_col = true;
                     }
                 }
             }
@@ -1056,7 +2240,7 @@ impl XlsxEditor {
                 let sid = self.cell_style_id(&cell)?;
                 let new_sid = *sid_cache.entry(sid).or_insert_with(|| {
                     let old = self.read_style_parts(sid).unwrap();
-                    let merged = merge_style_parts(old, &patch);
+                    let merged = self.merge_parts(old, &patch).unwrap();
                     self.ensure_style_from_parts(&merged).unwrap()
                 });
                 self.apply_style_to_cell(&cell, new_sid)?;
@@ -1077,12 +2261,15 @@ impl XlsxEditor {
             let (font, fill) = self.xf_components(sid)?;
             let border = self.xf_border(sid)?;
             let align = self.xf_alignment(sid)?;
+            let protection = self.xf_protection(sid)?;
             Ok(StyleParts {
                 num_fmt_code: None,
                 font,
                 fill,
                 border,
+                border_sides: None,
                 align,
+                protection,
             })
         } else {
             Ok(StyleParts::default())
@@ -1091,6 +2278,14 @@ impl XlsxEditor {
 }
 
 impl XlsxEditor {
+    /// Стиль, у которого задан только числовой формат (для ячеек-дат).
+    pub(crate) fn number_format_style(&mut self, code: &str) -> Result<u32> {
+        self.ensure_style_from_parts(&StyleParts {
+            num_fmt_code: Some(code.to_owned()),
+            ..Default::default()
+        })
+    }
+
     fn ensure_style_from_parts(&mut self, parts: &StyleParts) -> Result<u32> {
         // 1) numFmtId сначала (чтобы не держать &mut индекса)
         let num_fmt_id = if let Some(code) = parts.num_fmt_code.as_deref() {
@@ -1098,20 +2293,22 @@ impl XlsxEditor {
         } else {
             0
         };
+        self.ensure_xf(num_fmt_id, parts)
+    }
 
+    // как ensure_style_from_parts, но с готовым numFmtId (встроенные форматы не имеют кода)
+    fn ensure_xf(&mut self, num_fmt_id: u32, parts: &StyleParts) -> Result<u32> {
         let font_id = parts.font;
         let fill_id = parts.fill;
         let border_id = parts.border;
-        let align_key = parts
-            .align
-            .as_ref()
-            .map(|a| (a.horiz.clone(), a.vert.clone(), a.wrap));
+        let align = parts.align.clone().map(AlignmentSpec::normalized);
         let sk = StyleKey {
             num_fmt_id,
             font_id,
             fill_id,
             border_id,
-            align: align_key.clone(),
+            align: align.clone(),
+            protection: parts.protection,
         };
 
         // 2) короткий мут-заимствование: проверяем кэш
@@ -1128,7 +2325,8 @@ impl XlsxEditor {
             font_id,
             fill_id,
             border_id,
-            parts.align.as_ref(),
+            align.as_ref(),
+            parts.protection,
         )?;
 
         // 4) короткий мут-заимствование: обновляем индекс
@@ -1139,7 +2337,8 @@ impl XlsxEditor {
                 font_id,
                 fill_id,
                 border_id,
-                align: parts.align.clone(),
+                align,
+                protection: parts.protection,
             });
             ix.xf_by_key.insert(sk, sid);
         }
@@ -1153,7 +2352,8 @@ impl XlsxEditor {
         font_id: Option<u32>,
         fill_id: Option<u32>,
         border_id: Option<u32>,
-        align: Option<&AlignSpec>,
+        align: Option<&AlignmentSpec>,
+        protection: Option<CellProtection>,
     ) -> Result<u32> {
         let mut xf = String::from("<xf xfId=\"0\" ");
 
@@ -1179,23 +2379,19 @@ impl XlsxEditor {
         if align.is_some() {
             xf.push_str(r#"applyAlignment="1" "#);
         }
+        if protection.is_some() {
+            xf.push_str(r#"applyProtection="1" "#);
+        }
         xf.pop();
         xf.push('>');
 
-        if let Some(al) = align {
-            if al.horiz.is_some() || al.vert.is_some() || al.wrap {
-                xf.push_str("<alignment");
-                if let Some(h) = &al.horiz {
-                    xf.push_str(&format!(r#" horizontal="{}""#, h));
-                }
-                if let Some(v) = &al.vert {
-                    xf.push_str(&format!(r#" vertical="{}""#, v));
-                }
-                if al.wrap {
-                    xf.push_str(r#" wrapText="1""#);
-                }
-                xf.push_str("/>");
-            }
+        if let Some(al) = align
+            && !al.is_empty()
+        {
+            xf.push_str(&al.to_xml());
+        }
+        if let Some(p) = protection {
+            xf.push_str(&p.to_xml());
         }
         xf.push_str("</xf>");
 
@@ -1233,10 +2429,10 @@ impl XlsxEditor {
 //     }
 // }
 
-fn merge_align(base: Option<AlignSpec>, patch: Option<AlignSpec>) -> Option<AlignSpec> {
+fn merge_align(base: Option<AlignmentSpec>, patch: Option<AlignmentSpec>) -> Option<AlignmentSpec> {
     match (base, patch) {
         (b, None) => b,
-        (None, Some(p)) => Some(p),
+        (None, Some(p)) => Some(p.normalized()),
         (Some(mut b), Some(p)) => {
             if p.horiz.is_some() {
                 b.horiz = p.horiz;
@@ -1244,9 +2440,37 @@ fn merge_align(base: Option<AlignSpec>, patch: Option<AlignSpec>) -> Option<Alig
             if p.vert.is_some() {
                 b.vert = p.vert;
             }
+            if p.indent.is_some() {
+                b.indent = p.indent;
+            }
+            if p.text_rotation.is_some() {
+                b.text_rotation = p.text_rotation;
+            }
+            if p.reading_order.is_some() {
+                b.reading_order = p.reading_order;
+            }
+            if p.shrink_to_fit.is_some() {
+                b.shrink_to_fit = p.shrink_to_fit;
+            }
             b.wrap = b.wrap || p.wrap; // wrap только «наращиваем»
-            Some(b)
+            Some(b.normalized())
+        }
+    }
+}
+
+impl XlsxEditor {
+    // как merge_style_parts, но стороны рамки накладываются на рамку ячейки
+    fn merge_parts(&mut self, base: StyleParts, patch: &StyleParts) -> Result<StyleParts> {
+        let mut merged = merge_style_parts(base, patch);
+        if let Some(sides) = &patch.border_sides {
+            let current = match merged.border {
+                Some(id) => self.style_ix_mut()?.borders.get(id as usize).cloned(),
+                None => None,
+            };
+            let spec = current.unwrap_or_default().merge(sides);
+            merged.border = Some(self.ensure_border(&spec)?);
         }
+        Ok(merged)
     }
 }
 
@@ -1267,8 +2491,8 @@ fn merge_style_parts(mut base: StyleParts, patch: &StyleParts) -> StyleParts {
     if patch.border.is_some() {
         base.border = patch.border;
     }
-    if patch.align.is_some() {
-        base.align = patch.align.clone();
+    if patch.protection.is_some() {
+        base.protection = patch.protection;
     }
     base
 }
@@ -1282,7 +2506,7 @@ impl XlsxEditor {
         font_id: Option<u32>,
         fill_id: Option<u32>,
         border_id: Option<u32>,
-        align: Option<&AlignSpec>,
+        align: Option<&AlignmentSpec>,
     ) -> Result<u32> {
         let fmt_id: u32 = if let Some(code) = num_fmt {
             self.ensure_num_fmt(code)?
@@ -1290,16 +2514,16 @@ impl XlsxEditor {
             0
         };
 
-        if align.is_none() {
-            if let Some(id) = self.find_matching_xf(fmt_id, font_id, fill_id, border_id)? {
-                return Ok(id);
-            }
+        if align.is_none()
+            && let Some(id) = self.find_matching_xf(fmt_id, font_id, fill_id, border_id)?
+        {
+            return Ok(id);
         }
 
         self.add_new_xf(fmt_id, font_id, fill_id, border_id, align)
     }
 
-    fn ensure_num_fmt(&mut self, code: &str) -> Result<u32> {
+    pub(crate) fn ensure_num_fmt(&mut self, code: &str) -> Result<u32> {
         // A) есть в кеше?
         if let Some(id) = self
             .styles_index
@@ -1373,7 +2597,7 @@ impl XlsxEditor {
                 Event::Start(ref e) | Event::Empty(ref e)
                     if in_xfs && e.name().as_ref() == b"xf" =>
                 {
-                    // С xf с alignment мы не сравниваем — пропускаем
+                    // С xf с alignment/protection мы не сравниваем — пропускаем
                     let mut has_alignment_child = false;
                     // Event::Start -> значит дальше внутри могут быть теги
                     if matches!(ev, Event::Start(_)) {
@@ -1381,11 +2605,19 @@ impl XlsxEditor {
                         while depth > 0 {
                             match rdr.read_event()? {
                                 Event::Start(ref ie) => {
-                                    if ie.name().as_ref() == b"alignment" {
+                                    if matches!(ie.name().as_ref(), b"alignment" | b"protection") {
                                         has_alignment_child = true;
                                     }
                                     depth += 1;
                                 }
+                                Event::Empty(ref ie)
+                                    if matches!(
+                                        ie.name().as_ref(),
+                                        b"alignment" | b"protection"
+                                    ) =>
+                                {
+                                    has_alignment_child = true;
+                                }
                                 Event::End(_) => depth -= 1,
                                 Event::Eof => break,
                                 _ => {}
@@ -1411,9 +2643,9 @@ impl XlsxEditor {
                         }
                     }
                     let num_ok = num.unwrap_or(0) == fmt_id;
-                    let font_ok = font_id.map_or(true, |v| Some(v) == fnt);
-                    let fill_ok = fill_id.map_or(true, |v| Some(v) == fil);
-                    let border_ok = border_id.map_or(true, |v| Some(v) == bdr);
+                    let font_ok = font_id.is_none_or(|v| Some(v) == fnt);
+                    let fill_ok = fill_id.is_none_or(|v| Some(v) == fil);
+                    let border_ok = border_id.is_none_or(|v| Some(v) == bdr);
 
                     if num_ok && font_ok && fill_ok && border_ok {
                         return Ok(Some(idx));
@@ -1433,7 +2665,7 @@ impl XlsxEditor {
         font_id: Option<u32>,
         fill_id: Option<u32>,
         border_id: Option<u32>,
-        align: Option<&AlignSpec>,
+        align: Option<&AlignmentSpec>,
     ) -> Result<u32> {
         let mut xf = String::from("<xf xfId=\"0\" ");
 
@@ -1461,20 +2693,10 @@ impl XlsxEditor {
         xf.pop();
         xf.push('>');
 
-        if let Some(al) = align {
-            if al.horiz.is_some() || al.vert.is_some() || al.wrap {
-                xf.push_str("<alignment");
-                if let Some(h) = &al.horiz {
-                    xf.push_str(&format!(r#" horizontal="{}""#, h));
-                }
-                if let Some(v) = &al.vert {
-                    xf.push_str(&format!(r#" vertical="{}""#, v));
-                }
-                if al.wrap {
-                    xf.push_str(r#" wrapText="1""#);
-                }
-                xf.push_str("/>");
-            }
+        if let Some(al) = align
+            && !al.is_empty()
+        {
+            xf.push_str(&al.to_xml());
         }
         xf.push_str("</xf>");
 
@@ -1506,23 +2728,12 @@ impl XlsxEditor {
         Ok(cnt - 1)
     }
 
-    fn ensure_font(&mut self, name: &str, size: f32, bold: bool, italic: bool) -> Result<u32> {
-        let key = FontKey {
-            name: name.to_string(),
-            size_100: (size * 100.0).round() as u32,
-            bold,
-            italic,
-        };
-
-        // 0) индекс/поиск
-        {
-            let ix = self.style_ix_mut()?;
-            if let Some(&id) = ix.font_by_key.get(&key) {
-                return Ok(id);
-            }
-        }
+    fn ensure_font(&mut self, spec: &FontSpec) -> Result<u32> {
+        self.ensure_font_key(spec.key())
+    }
 
-        // 1) id до вставки
+    fn ensure_font_key(&mut self, key: FontKey) -> Result<u32> {
+        // 0) индекс/поиск + id до вставки
         let new_id = {
             let ix = self.style_ix_mut()?;
             if let Some(&id) = ix.font_by_key.get(&key) {
@@ -1531,23 +2742,14 @@ impl XlsxEditor {
             ix.fonts_count
         };
 
-        // 2) XML
+        // 1) XML
         let insert = memmem::rfind(&self.styles_xml, b"</fonts>")
             .context("<fonts> block not found in styles.xml")?;
-        let mut xml = String::from("<font>");
-        if bold {
-            xml.push_str("<b/>");
-        }
-        if italic {
-            xml.push_str("<i/>");
-        }
-        xml.push_str(&format!(r#"<sz val="{}"/>"#, (key.size_100 as f32) / 100.0));
-        xml.push_str(&format!(r#"<name val="{}"/>"#, name));
-        xml.push_str("</font>");
-        self.styles_xml.splice(insert..insert, xml.bytes());
+        self.styles_xml
+            .splice(insert..insert, key.to_xml().into_bytes());
         bump_count(&mut self.styles_xml, b"<fonts", b"count=\"")?;
 
-        // 3) индекс
+        // 2) индекс
         {
             let ix = self.style_ix_mut()?;
             ix.font_by_key.insert(key, new_id);
@@ -1557,79 +2759,93 @@ impl XlsxEditor {
         Ok(new_id)
     }
 
-    fn ensure_fill(&mut self, rgb: &str) -> Result<u32> {
-        let mut key = rgb.to_string();
-        key.make_ascii_uppercase();
-
-        // 0) индекс/поиск
-        {
-            let ix = self.style_ix_mut()?;
-            if let Some(&id) = ix.fill_by_rgb.get(&key) {
-                return Ok(id);
-            }
-        }
+    fn ensure_fill(&mut self, spec: &FillSpec) -> Result<u32> {
+        let xml = spec.to_xml();
 
-        // 1) id до вставки
+        // 0) индекс/поиск + id до вставки
         let new_id = {
             let ix = self.style_ix_mut()?;
-            if let Some(&id) = ix.fill_by_rgb.get(&key) {
+            if let Some(&id) = ix.fill_by_key.get(&xml) {
                 return Ok(id);
             }
             ix.fills_count
         };
 
-        // 2) XML
+        // 1) XML
         let insert = memmem::rfind(&self.styles_xml, b"</fills>")
             .context("<fills> block not found in styles.xml")?;
-        let xml = format!(
-            r#"<fill><patternFill patternType="solid"><fgColor rgb="{key}"/><bgColor indexed="64"/></patternFill></fill>"#
-        );
         self.styles_xml.splice(insert..insert, xml.bytes());
         bump_count(&mut self.styles_xml, b"<fills", b"count=\"")?;
 
-        // 3) индекс
+        // 2) индекс
         {
             let ix = self.style_ix_mut()?;
-            ix.fill_by_rgb.insert(key, new_id);
+            ix.fill_by_key.insert(xml, new_id);
             ix.fills_count = new_id + 1;
         }
 
         Ok(new_id)
     }
 
-    fn ensure_border(&mut self, style: &str) -> Result<u32> {
-        // 0) Убедимся, что индекс инициализирован и попробуем найти готовый
-        {
+    /// Возвращает dxfId для `style`, дописывая `<dxf>` в styles.xml при необходимости.
+    pub(crate) fn ensure_dxf(&mut self, style: &DxfStyle) -> Result<u32> {
+        let num_fmt_id = match &style.num_fmt {
+            Some(code) => Some(self.ensure_num_fmt(code)?),
+            None => None,
+        };
+        self.ensure_dxf_xml(style.to_xml(num_fmt_id))
+    }
+
+    fn ensure_dxf_xml(&mut self, xml: String) -> Result<u32> {
+        let new_id = {
             let ix = self.style_ix_mut()?;
-            if let Some(&id) = ix.border_by_key.get(style) {
+            if let Some(&id) = ix.dxf_by_key.get(&xml) {
                 return Ok(id);
             }
+            ix.dxfs_count
+        };
+
+        append_to_block(
+            &mut self.styles_xml,
+            "dxfs",
+            xml.as_bytes(),
+            &["tableStyles", "colors", "extLst"],
+        )?;
+
+        {
+            let ix = self.style_ix_mut()?;
+            ix.dxf_by_key.insert(xml, new_id);
+            ix.dxfs_count = new_id + 1;
         }
+        Ok(new_id)
+    }
 
-        // 1) Снимем "текущий" id ДО модификации XML
+    fn ensure_border(&mut self, spec: &BorderSpec) -> Result<u32> {
+        let mut key = spec.clone();
+        key.normalize();
+        let xml = key.to_xml();
+
+        // 0) Убедимся, что индекс инициализирован и попробуем найти готовый
         let new_id = {
             let ix = self.style_ix_mut()?;
-            // повторная проверка на случай гонки
-            if let Some(&id) = ix.border_by_key.get(style) {
+            if let Some(&id) = ix.border_by_key.get(&xml) {
                 return Ok(id);
             }
+            // 1) Снимем "текущий" id ДО модификации XML
             ix.borders_count
         };
 
         // 2) Вставляем XML
         let end_pos = memmem::rfind(&self.styles_xml, b"</borders>")
             .context("styles.xml: </borders> not found")?;
-        let tag = format!(
-            r#"<border><left style="{s}"/><right style="{s}"/><top style="{s}"/><bottom style="{s}"/><diagonal/></border>"#,
-            s = style
-        );
-        self.styles_xml.splice(end_pos..end_pos, tag.bytes());
+        self.styles_xml.splice(end_pos..end_pos, xml.bytes());
         bump_count(&mut self.styles_xml, b"<borders", b"count=\"")?;
 
         // 3) Обновляем индекс ПОСЛЕ вставки, используя pre‑id
         {
             let ix = self.style_ix_mut()?;
-            ix.border_by_key.insert(style.to_string(), new_id);
+            ix.border_by_key.insert(xml, new_id);
+            ix.borders.push(key);
             ix.borders_count = new_id + 1;
         }
 
@@ -1699,7 +2915,7 @@ impl XlsxEditor {
         Ok(None)
     }
 
-    fn xf_alignment(&self, style_id: u32) -> Result<Option<AlignSpec>> {
+    fn xf_alignment(&self, style_id: u32) -> Result<Option<AlignmentSpec>> {
         let mut rdr = Reader::from_reader(self.styles_xml.as_slice());
         rdr.config_mut().trim_text(true);
         let mut in_xfs = false;
@@ -1719,24 +2935,12 @@ impl XlsxEditor {
                                 Event::Start(ref ie) => {
                                     depth += 1;
                                     if ie.name().as_ref() == b"alignment" {
-                                        let mut spec = AlignSpec::default();
-                                        for attr in ie.attributes().with_checks(false).flatten() {
-                                            let val =
-                                                String::from_utf8_lossy(&attr.value).into_owned();
-                                            match attr.key.as_ref() {
-                                                b"horizontal" => spec.horiz = Some(val.parse()?),
-                                                b"vertical" => spec.vert = Some(val.parse()?),
-                                                b"wrapText" => {
-                                                    if val == "1" {
-                                                        spec.wrap = true
-                                                    }
-                                                }
-                                                _ => {}
-                                            }
-                                        }
-                                        return Ok(Some(spec));
+                                        return Ok(Some(read_alignment(ie)?));
                                     }
                                 }
+                                Event::Empty(ref ie) if ie.name().as_ref() == b"alignment" => {
+                                    return Ok(Some(read_alignment(ie)?));
+                                }
                                 Event::End(_) => depth -= 1,
                                 Event::Eof => break,
                                 _ => {}
@@ -1746,7 +2950,7 @@ impl XlsxEditor {
                     }
                     xf_idx += 1;
                 }
-                Event::Empty(ref _e) if in_xfs => {
+                Event::Empty(ref e) if in_xfs && e.name().as_ref() == b"xf" => {
                     if xf_idx == style_id {
                         return Ok(None);
                     }
@@ -1759,22 +2963,64 @@ impl XlsxEditor {
         Ok(None)
     }
 
-    fn cell_style_id(&self, coord: &str) -> Result<Option<u32>> {
-        let tag = format!(r#"<c r="{coord}""#);
-        if let Some(pos) = memmem::rfind(&self.sheet_xml, tag.as_bytes()) {
-            if let Some(spos) = find_bytes_from(&self.sheet_xml, b" s=\"", pos) {
-                let val_start = spos + 4;
-                let val_end =
-                    find_bytes_from(&self.sheet_xml, b"\"", val_start + 1).unwrap_or(val_start);
-                let id = std::str::from_utf8(&self.sheet_xml[val_start..val_end])?
-                    .parse::<u32>()
-                    .unwrap_or(0);
-                return Ok(Some(id));
+    fn xf_protection(&self, style_id: u32) -> Result<Option<CellProtection>> {
+        let mut rdr = Reader::from_reader(self.styles_xml.as_slice());
+        rdr.config_mut().trim_text(true);
+        let mut in_xfs = false;
+        let mut xf_idx = 0u32;
+        while let Ok(ev) = rdr.read_event() {
+            match ev {
+                Event::Start(ref e) if e.name().as_ref() == b"cellXfs" => in_xfs = true,
+                Event::End(ref e) if e.name().as_ref() == b"cellXfs" => break,
+                Event::Empty(ref e) if in_xfs && e.name().as_ref() == b"xf" => {
+                    if xf_idx == style_id {
+                        return Ok(None);
+                    }
+                    xf_idx += 1;
+                }
+                Event::Start(ref e) if in_xfs && e.name().as_ref() == b"xf" => {
+                    if xf_idx == style_id {
+                        let mut depth = 1;
+                        while depth > 0 {
+                            match rdr.read_event()? {
+                                Event::Start(ref pe) | Event::Empty(ref pe)
+                                    if pe.name().as_ref() == b"protection" =>
+                                {
+                                    return Ok(Some(read_protection(pe)));
+                                }
+                                Event::Start(_) => depth += 1,
+                                Event::End(_) => depth -= 1,
+                                Event::Eof => break,
+                                _ => {}
+                            }
+                        }
+                        return Ok(None);
+                    }
+                    xf_idx += 1;
+                }
+                Event::Eof => break,
+                _ => {}
             }
         }
         Ok(None)
     }
 
+    pub(crate) fn cell_style_id(&self, coord: &str) -> Result<Option<u32>> {
+        let tag = format!(r#"<c r="{coord}""#);
+        if let Some(pos) = memmem::rfind(&self.sheet_xml, tag.as_bytes())
+            && let Some(spos) = find_bytes_from(&self.sheet_xml, b" s=\"", pos)
+        {
+            let val_start = spos + 4;
+            let val_end =
+                find_bytes_from(&self.sheet_xml, b"\"", val_start + 1).unwrap_or(val_start);
+            let id = std::str::from_utf8(&self.sheet_xml[val_start..val_end])?
+                .parse::<u32>()
+                .unwrap_or(0);
+            return Ok(Some(id));
+        }
+        Ok(None)
+    }
+
     fn apply_style_to_cell(&mut self, coord: &str, style: u32) -> Result<()> {
         let row_num = coord.trim_start_matches(|c: char| c.is_ascii_alphabetic());
         let row_tag = format!(r#"<row r="{row_num}""#);
@@ -1802,15 +3048,15 @@ impl XlsxEditor {
 
         let ctag_end = find_bytes_from(&self.sheet_xml, b">", cpos).context("malformed <c> tag")?;
 
-        if let Some(sattr) = find_bytes_from(&self.sheet_xml, b" s=\"", cpos) {
-            if sattr < ctag_end {
-                let val_start = sattr + 4;
-                let val_end = find_bytes_from(&self.sheet_xml, b"\"", val_start + 1)
-                    .context("attr closing '\"' not found")?;
-                self.sheet_xml
-                    .splice(val_start..val_end, style.to_string().bytes());
-                return Ok(());
-            }
+        if let Some(sattr) = find_bytes_from(&self.sheet_xml, b" s=\"", cpos)
+            && sattr < ctag_end
+        {
+            let val_start = sattr + 4;
+            let val_end = find_bytes_from(&self.sheet_xml, b"\"", val_start + 1)
+                .context("attr closing '\"' not found")?;
+            self.sheet_xml
+                .splice(val_start..val_end, style.to_string().bytes());
+            return Ok(());
         }
         self.sheet_xml
             .splice(ctag_end..ctag_end, format!(r#" s="{style}""#).bytes());
@@ -1841,13 +3087,13 @@ impl XlsxEditor {
 
         let ctag_end = find_bytes_from(&self.sheet_xml, b">", cpos).context("malformed <c> tag")?;
 
-        if let Some(sattr) = find_bytes_from(&self.sheet_xml, b" s=\"", cpos) {
-            if sattr < ctag_end {
-                let val_end = find_bytes_from(&self.sheet_xml, b"\"", sattr + 4)
-                    .context("style attribute not closed")?;
-                let remove_end = val_end + 1;
-                self.sheet_xml.drain(sattr..remove_end);
-            }
+        if let Some(sattr) = find_bytes_from(&self.sheet_xml, b" s=\"", cpos)
+            && sattr < ctag_end
+        {
+            let val_end = find_bytes_from(&self.sheet_xml, b"\"", sattr + 4)
+                .context("style attribute not closed")?;
+            let remove_end = val_end + 1;
+            self.sheet_xml.drain(sattr..remove_end);
         }
         Ok(())
     }
@@ -1890,12 +3136,12 @@ impl XlsxEditor {
 
             // r="..."
             let mut row_r: Option<u32> = None;
-            if let Some(pos) = find_bytes_from(&src, b" r=\"", row_start) {
-                if pos < row_tag_end {
-                    let v0 = pos + 4;
-                    if let Some(v1) = find_bytes_from(&src, b"\"", v0) {
-                        row_r = lexical_core::parse::<u32>(&src[v0..v1]).ok();
-                    }
+            if let Some(pos) = find_bytes_from(&src, b" r=\"", row_start)
+                && pos < row_tag_end
+            {
+                let v0 = pos + 4;
+                if let Some(v1) = find_bytes_from(&src, b"\"", v0) {
+                    row_r = lexical_core::parse::<u32>(&src[v0..v1]).ok();
                 }
             }
 
@@ -2075,3 +3321,143 @@ impl XlsxEditor {
         Ok(())
     }
 }
+
+/* ========================== STYLE IMPORT ================================== */
+
+/// styles.xml другой книги, разобранный по индексам, – для переноса стилей по одному.
+/// Элементы, которые мы не можем записать без потерь, хранятся только сырыми (`None`).
+pub(crate) struct ForeignStyles {
+    xfs: Vec<XfParts>,
+    num_fmts: HashMap<u32, String>,
+    fonts: Vec<(Option<FontKey>, Vec<u8>)>,
+    fills: Vec<(Option<FillSpec>, Vec<u8>)>,
+    borders: Vec<(Option<BorderSpec>, Vec<u8>)>,
+    dxfs: Vec<Vec<u8>>,
+}
+
+// разбирает дочерние элементы блока; `read` получает ридер сразу после открывающего тега
+fn read_foreign<T>(
+    styles: &[u8],
+    block: &[u8],
+    read: impl Fn(&mut Reader<&[u8]>, &BytesStart, bool) -> Result<Option<T>>,
+) -> Result<Vec<(Option<T>, Vec<u8>)>> {
+    let mut out = Vec::new();
+    for raw in block_children(styles, block)? {
+        let mut rdr = Reader::from_reader(raw.as_slice());
+        rdr.config_mut().trim_text(true);
+        let spec = match rdr.read_event()? {
+            Event::Start(e) => read(&mut rdr, &e, false)?,
+            Event::Empty(e) => read(&mut rdr, &e, true)?,
+            _ => None,
+        };
+        out.push((spec, raw));
+    }
+    Ok(out)
+}
+
+impl ForeignStyles {
+    pub(crate) fn parse(styles: &[u8]) -> Result<Self> {
+        let mut num_fmts = HashMap::new();
+        for raw in block_children(styles, b"numFmts")? {
+            if let (Some(id), Some(code)) =
+                (raw_attr(&raw, "numFmtId"), raw_attr(&raw, "formatCode"))
+                && let Ok(id) = id.parse()
+            {
+                num_fmts.insert(id, code);
+            }
+        }
+        Ok(ForeignStyles {
+            xfs: StyleIndex::build(styles)?.xfs,
+            num_fmts,
+            fonts: read_foreign(styles, b"fonts", |rdr, _, empty| {
+                if empty {
+                    return Ok(None);
+                }
+                let (key, exact) = read_font(rdr)?;
+                Ok(exact.then_some(key))
+            })?,
+            fills: read_foreign(styles, b"fills", |rdr, _, empty| {
+                if empty {
+                    return Ok(None);
+                }
+                let (fill, exact) = read_fill(rdr)?;
+                Ok(fill.filter(|_| exact))
+            })?,
+            borders: read_foreign(styles, b"borders", |rdr, e, empty| {
+                let (spec, exact) = read_border(rdr, e, empty)?;
+                Ok(exact.then_some(spec))
+            })?,
+            dxfs: block_children(styles, b"dxfs")?,
+        })
+    }
+}
+
+impl XlsxEditor {
+    /// Переносит формат ячейки `xf` другой книги: шрифт, заливка, рамка, числовой формат
+    /// и сам `<xf>` ищутся среди уже имеющихся и дописываются только при отсутствии.
+    pub(crate) fn import_xf(&mut self, src: &ForeignStyles, xf: u32) -> Result<u32> {
+        let Some(xf) = src.xfs.get(xf as usize) else {
+            return Ok(0);
+        };
+        let num_fmt_id = match src.num_fmts.get(&xf.num_fmt_id) {
+            Some(code) => self.ensure_num_fmt(code)?,
+            // встроенный формат – id одинаков во всех книгах
+            None => xf.num_fmt_id,
+        };
+        let font = match xf.font_id.and_then(|i| src.fonts.get(i as usize)) {
+            Some((Some(key), _)) => Some(self.ensure_font_key(key.clone())?),
+            Some((None, raw)) => Some(self.ensure_raw_style("fonts", raw)?),
+            None => None,
+        };
+        let fill = match xf.fill_id.and_then(|i| src.fills.get(i as usize)) {
+            Some((Some(spec), _)) => Some(self.ensure_fill(spec)?),
+            Some((None, raw)) => Some(self.ensure_raw_style("fills", raw)?),
+            None => None,
+        };
+        let border = match xf.border_id.and_then(|i| src.borders.get(i as usize)) {
+            Some((Some(spec), _)) => Some(self.ensure_border(spec)?),
+            Some((None, raw)) => Some(self.ensure_raw_style("borders", raw)?),
+            None => None,
+        };
+        self.ensure_xf(
+            num_fmt_id,
+            &StyleParts {
+                font,
+                fill,
+                border,
+                align: xf.align.clone(),
+                protection: xf.protection,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Переносит дифференциальный формат `dxf` другой книги (для условного форматирования).
+    pub(crate) fn import_dxf(&mut self, src: &ForeignStyles, dxf: u32) -> Result<u32> {
+        let Some(raw) = src.dxfs.get(dxf as usize) else {
+            return Ok(0);
+        };
+        let mut raw = raw.clone();
+        // пользовательский числовой формат – под id этой книги
+        if let Some(p) = memmem::find(&raw, b"<numFmt ")
+            && let Some(code) = raw_attr(&raw[p..], "formatCode")
+        {
+            let id = self.ensure_num_fmt(&code)?;
+            let mut tail = raw.split_off(p);
+            set_raw_attr(&mut tail, "numFmtId", &id.to_string());
+            raw.extend_from_slice(&tail);
+        }
+        self.ensure_dxf_xml(String::from_utf8_lossy(&raw).into_owned())
+    }
+
+    // элемент, который не разобрать без потерь: ищем такой же побайтно, иначе дописываем
+    fn ensure_raw_style(&mut self, block: &str, raw: &[u8]) -> Result<u32> {
+        let have = block_children(&self.styles_xml, block.as_bytes())?;
+        if let Some(i) = have.iter().position(|h| h == raw) {
+            return Ok(i as u32);
+        }
+        append_to_block(&mut self.styles_xml, block, raw, &[])?;
+        self.invalidate_styles_ix();
+        Ok(have.len() as u32)
+    }
+}
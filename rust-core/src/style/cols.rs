@@ -2,7 +2,7 @@ use std::collections::BTreeMap;
 
 use anyhow::{Context, Result};
 use memchr::memmem;
-use quick_xml::{events::Event, Reader};
+use quick_xml::{Reader, events::Event};
 
 use crate::XlsxEditor;
 use crate::style::util::{col_letter, find_bytes_from};
@@ -14,6 +14,8 @@ struct ColProp {
     best_fit: bool,
     custom_width: bool,
     hidden: bool,
+    // прочие атрибуты (outlineLevel, collapsed, phonetic, …) как есть, в исходном порядке
+    extra: Vec<(String, String)>,
 }
 
 fn equal_props(a: &ColProp, b: &ColProp) -> bool {
@@ -22,6 +24,7 @@ fn equal_props(a: &ColProp, b: &ColProp) -> bool {
         && a.best_fit == b.best_fit
         && a.custom_width == b.custom_width
         && a.hidden == b.hidden
+        && a.extra == b.extra
 }
 
 impl XlsxEditor {
@@ -130,6 +133,28 @@ impl XlsxEditor {
         Ok(())
     }
 
+    /// Переносит `<col>` при вставке/удалении столбцов: `f` получает 0-based номер
+    /// столбца и возвращает новый (`None` – столбец удалён). Пустой блок убирается.
+    pub(crate) fn shift_cols(&mut self, f: impl Fn(u32) -> Option<u32>) -> Result<()> {
+        let (Some(start), Some(end)) = (
+            memmem::find(&self.sheet_xml, b"<cols>"),
+            memmem::find(&self.sheet_xml, b"</cols>"),
+        ) else {
+            return Ok(());
+        };
+        let end = end + "</cols>".len();
+        let map = self.read_cols_map(start, end)?;
+        let shifted: BTreeMap<u32, ColProp> = map
+            .into_iter()
+            .filter_map(|(i, p)| f(i - 1).map(|c| (c + 1, p)))
+            .collect();
+        if shifted.is_empty() {
+            self.sheet_xml.drain(start..end);
+            return Ok(());
+        }
+        self.write_cols_map(start, end, &shifted)
+    }
+
     fn ensure_cols_block(&mut self) -> Result<(usize, usize)> {
         use memchr::memmem;
 
@@ -149,9 +174,9 @@ impl XlsxEditor {
         let (insert_after_dim, _inserted_len): (usize, usize) =
             if let Some(dim_start) = memmem::find(&self.sheet_xml, b"<dimension") {
                 // конец тега <dimension ...>
-                let dim_end =
-                    find_bytes_from(&self.sheet_xml, b">", dim_start).context("<dimension> not closed")?
-                        + 1;
+                let dim_end = find_bytes_from(&self.sheet_xml, b">", dim_start)
+                    .context("<dimension> not closed")?
+                    + 1;
                 (dim_end, 0)
             } else {
                 // создаём минимальный dimension прямо перед sheetData
@@ -189,6 +214,7 @@ impl XlsxEditor {
                     let mut best_fit = false;
                     let mut custom_width = false;
                     let mut hidden = false;
+                    let mut extra = Vec::new();
 
                     for a in e.attributes().with_checks(false).flatten() {
                         let v = String::from_utf8_lossy(&a.value);
@@ -200,7 +226,8 @@ impl XlsxEditor {
                             b"bestFit" => best_fit = v == "1" || v == "true",
                             b"customWidth" => custom_width = v == "1" || v == "true",
                             b"hidden" => hidden = v == "1" || v == "true",
-                            _ => {}
+                            k => extra
+                                .push((String::from_utf8_lossy(k).into_owned(), v.into_owned())),
                         }
                     }
                     let min = min.unwrap_or(1);
@@ -211,6 +238,7 @@ impl XlsxEditor {
                         best_fit,
                         custom_width,
                         hidden,
+                        extra,
                     };
                     for i in min..=max {
                         map.insert(i, p.clone());
@@ -272,6 +300,9 @@ fn build_one_col_tag(min: u32, max: u32, p: &ColProp) -> String {
     if p.hidden {
         s.push_str(r#" hidden="1""#);
     }
+    for (k, v) in &p.extra {
+        s.push_str(&format!(r#" {k}="{v}""#));
+    }
     s.push_str("/>");
     s
 }
@@ -37,15 +37,15 @@ pub fn find_bytes_from(hay: &[u8], needle: &[u8], start: usize) -> Option<usize>
 }
 
 pub fn bump_count(xml: &mut Vec<u8>, tag: &[u8], attr: &[u8]) -> Result<()> {
-    if let Some(pos) = memmem::rfind(xml, tag) {
-        if let Some(a) = find_bytes_from(xml, attr, pos) {
-            let start = a + attr.len();
-            let end = find_bytes_from(xml, b"\"", start).context("closing quote not found")?;
-            let mut num: u32 = std::str::from_utf8(&xml[start..end])?.parse()?;
-            num += 1;
-            xml.splice(start..end, num.to_string().bytes());
-            return Ok(());
-        }
+    if let Some(pos) = memmem::rfind(xml, tag)
+        && let Some(a) = find_bytes_from(xml, attr, pos)
+    {
+        let start = a + attr.len();
+        let end = find_bytes_from(xml, b"\"", start).context("closing quote not found")?;
+        let mut num: u32 = std::str::from_utf8(&xml[start..end])?.parse()?;
+        num += 1;
+        xml.splice(start..end, num.to_string().bytes());
+        return Ok(());
     }
     Err(anyhow::anyhow!("attribute count not found"))
 }
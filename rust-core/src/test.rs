@@ -1,6 +1,6 @@
 #[cfg(test)]
 use crate::{
-    XlsxEditor, scan,
+    CellValue, DateValue, SheetReader, StringStorage, XlsxEditor, for_each_row, scan, scan_reader,
     style::{AlignSpec, HorizAlignment, VertAlignment},
 };
 #[cfg(test)]
@@ -219,3 +219,1922 @@ fn rename_del_worksheet() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn get_cell_typed() -> Result<()> {
+    let file_name = "../test/test.xlsx";
+    let app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    assert_eq!(app.get_cell("A1")?, CellValue::Number(1.0));
+    assert_eq!(app.get_cell("A3")?, CellValue::Text("fd".to_owned()));
+    assert_eq!(app.get_cell("$H$20")?, CellValue::Text("fc".to_owned()));
+    assert_eq!(app.get_cell("Z99")?, CellValue::Blank);
+    Ok(())
+}
+
+#[test]
+fn get_range_after_write() -> Result<()> {
+    let file_name = "../test/test_last_row_index.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    app.set_cell("E1", "a & b")?;
+    app.set_cell("F1", "=SUM(A1:C1)")?;
+
+    let grid = app.get_range("A1:F2")?;
+    assert_eq!(grid.len(), 2);
+    assert_eq!(grid[0].len(), 6);
+    assert_eq!(grid[0][0], CellValue::Number(1.0));
+    assert_eq!(grid[0][3], CellValue::Blank);
+    assert_eq!(grid[0][4], CellValue::Text("a & b".to_owned()));
+    assert_eq!(
+        grid[0][5],
+        CellValue::Formula {
+            formula: "SUM(A1:C1)".to_owned(),
+            cached: None
+        }
+    );
+    assert_eq!(grid[1][1], CellValue::Blank);
+    assert_eq!(grid[1][3], CellValue::Number(1.0));
+
+    // сетка всегда размером с запрошенный прямоугольник, даже за пределами данных
+    let used = app.used_range()?.unwrap();
+    let whole = app.get_range(&used)?;
+    assert_eq!(whole[0][5], grid[0][5]);
+    let mut empty = XlsxEditor::new_workbook("Data")?;
+    empty.set_cell("A1", 1)?;
+    let grid = empty.get_range("A1:D5")?;
+    assert_eq!((grid.len(), grid[0].len()), (5, 4));
+    assert_eq!(grid[0][0], CellValue::Number(1.0));
+    assert_eq!(
+        empty.get_range("C3:D5")?,
+        vec![vec![CellValue::Blank; 2]; 3]
+    );
+    Ok(())
+}
+
+#[test]
+fn shared_strings_existing_table() -> Result<()> {
+    let file_name = "../test/test.xlsx";
+    let file_name_out = "../test/test_sst_out.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    app.set_string_storage(StringStorage::Shared);
+    app.append_table_at("A30", [["fd", "new", "new"], ["x y ", "42", "new"]])?;
+    assert_eq!(app.get_cell("B30")?, CellValue::Text("new".to_owned()));
+    app.save(file_name_out)?;
+
+    let app = XlsxEditor::open(file_name_out, &scan(file_name_out)?[0])?;
+    assert_eq!(app.get_cell("A3")?, CellValue::Text("fd".to_owned()));
+    let grid = app.get_range("A30:C31")?;
+    assert_eq!(grid[0][0], CellValue::Text("fd".to_owned()));
+    assert_eq!(grid[0][2], CellValue::Text("new".to_owned()));
+    assert_eq!(grid[1][0], CellValue::Text("x y ".to_owned()));
+    assert_eq!(grid[1][1], CellValue::Number(42.0));
+    assert_eq!(grid[1][2], CellValue::Text("new".to_owned()));
+
+    // "fd" уже был в таблице, "new" добавлен один раз
+    let sst = app.shared_strings()?.strings();
+    assert_eq!(sst.iter().filter(|s| *s == "new").count(), 1);
+    assert_eq!(sst.iter().filter(|s| *s == "fd").count(), 1);
+    Ok(())
+}
+
+#[test]
+fn shared_strings_created() -> Result<()> {
+    let file_name = "../test/test_last_row_index.xlsx";
+    let file_name_out = "../test/test_last_row_index_sst_out.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    app.set_string_storage(StringStorage::Shared)
+        .set_cell("E1", "a & b")?;
+    app.append_row(["one", "two", "one"])?;
+    app.save(file_name_out)?;
+
+    let app = XlsxEditor::open(file_name_out, &scan(file_name_out)?[0])?;
+    assert_eq!(app.get_cell("E1")?, CellValue::Text("a & b".to_owned()));
+    assert_eq!(app.get_cell("C9")?, CellValue::Text("one".to_owned()));
+    assert_eq!(app.shared_strings()?.strings(), ["a & b", "one", "two"]);
+    Ok(())
+}
+
+#[test]
+fn string_cell_writes() -> Result<()> {
+    // String / &String / format!() проходят ту же типизацию, что и &str
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    let total = String::from("=SUM(A2:A3)");
+    app.set_cell("A1", format!("{}", 7))?;
+    app.set_cell("B1", &total)?;
+    app.append_row(vec![String::from("x"), String::from("2.5")])?;
+    app.append_table_at("A5", vec![vec![String::from("10"), String::from("y")]])?;
+    assert_eq!(app.get_cell("A1")?, CellValue::Number(7.0));
+    assert_eq!(
+        app.get_cell("B1")?,
+        CellValue::Formula {
+            formula: "SUM(A2:A3)".to_owned(),
+            cached: None
+        }
+    );
+    assert_eq!(app.get_cell("B2")?, CellValue::Number(2.5));
+    assert_eq!(app.get_cell("A5")?, CellValue::Number(10.0));
+    assert_eq!(app.get_cell("B5")?, CellValue::Text("y".to_owned()));
+    Ok(())
+}
+
+#[test]
+fn typed_cell_writes() -> Result<()> {
+    let file_name = "../test/test_last_row_index.xlsx";
+    let file_name_out = "../test/test_typed_writes_out.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    app.append_row([
+        CellValue::Text("00123".to_owned()),
+        "1e5".into(),
+        "NaN".into(),
+        f64::NAN.into(),
+        true.into(),
+        CellValue::Error("#N/A".to_owned()),
+        42u32.into(),
+        CellValue::Blank,
+        CellValue::Formula {
+            formula: "A9&\"x\"".to_owned(),
+            cached: Some(Box::new(CellValue::Text("00123x".to_owned()))),
+        },
+    ])?;
+    app.set_cell("J9", None::<f64>)?;
+    app.save(file_name_out)?;
+
+    let app = XlsxEditor::open(file_name_out, &scan(file_name_out)?[0])?;
+    let row = app.get_range("A9:J9")?.remove(0);
+    assert_eq!(row[0], CellValue::Text("00123".to_owned()));
+    assert_eq!(row[1], CellValue::Number(100000.0));
+    assert_eq!(row[2], CellValue::Text("NaN".to_owned()));
+    assert_eq!(row[3], CellValue::Error("#NUM!".to_owned()));
+    assert_eq!(row[4], CellValue::Bool(true));
+    assert_eq!(row[5], CellValue::Error("#N/A".to_owned()));
+    assert_eq!(row[6], CellValue::Number(42.0));
+    assert_eq!(row[7], CellValue::Blank);
+    assert_eq!(row[8].as_text(), Some("00123x"));
+    assert_eq!(row[9], CellValue::Blank);
+    Ok(())
+}
+
+#[test]
+fn date_serials() -> Result<()> {
+    assert_eq!(DateValue::date(1900, 1, 1).to_serial(false)?, 1.0);
+    assert_eq!(DateValue::date(1900, 2, 28).to_serial(false)?, 59.0);
+    assert_eq!(DateValue::date(1900, 3, 1).to_serial(false)?, 61.0);
+    assert_eq!(DateValue::date(2024, 1, 1).to_serial(false)?, 45292.0);
+    assert_eq!(DateValue::date(2024, 1, 1).to_serial(true)?, 43830.0);
+    assert_eq!(DateValue::date(1904, 1, 1).to_serial(true)?, 0.0);
+    assert_eq!(
+        DateValue::datetime(2024, 1, 1, 18, 0, 0.0).to_serial(false)?,
+        45292.75
+    );
+    assert_eq!(DateValue::time(12, 0, 0.0).to_serial(true)?, 0.5);
+    assert!(DateValue::date(2023, 2, 29).to_serial(false).is_err());
+    assert!(DateValue::date(1899, 12, 31).to_serial(false).is_err());
+    assert!(DateValue::time(24, 0, 0.0).to_serial(false).is_err());
+    assert_eq!(
+        DateValue::from_unix(1_706_722_200, 500_000_000),
+        DateValue::datetime(2024, 1, 31, 17, 30, 0.5)
+    );
+    assert_eq!(
+        DateValue::from_unix(-1, 0),
+        DateValue::datetime(1969, 12, 31, 23, 59, 59.0)
+    );
+    Ok(())
+}
+
+#[test]
+fn date_cells_get_number_format() -> Result<()> {
+    let file_name = "../test/test.xlsx";
+    let file_name_out = "../test/test_dates_out.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    app.append_table_at(
+        "A40",
+        [
+            [DateValue::date(2024, 1, 1), DateValue::time(6, 0, 0.0)],
+            [DateValue::date(2024, 1, 2), DateValue::time(12, 0, 0.0)],
+        ],
+    )?;
+    app.save(file_name_out)?;
+
+    let mut app = XlsxEditor::open(file_name_out, &scan(file_name_out)?[0])?;
+    assert_eq!(app.get_cell("A40")?, CellValue::Number(45292.0));
+    assert_eq!(app.get_cell("B41")?, CellValue::Number(0.5));
+    let date_sid = app.cell_style_id("A41")?.expect("date cell has a style");
+    assert_eq!(app.cell_style_id("A40")?, Some(date_sid));
+    let time_sid = app.cell_style_id("B40")?.expect("time cell has a style");
+    let ix = app.style_ix_mut()?;
+    assert_eq!(
+        ix.xfs[date_sid as usize].num_fmt_id,
+        ix.numfmt_by_code["yyyy-mm-dd"]
+    );
+    assert_eq!(
+        ix.xfs[time_sid as usize].num_fmt_id,
+        ix.numfmt_by_code["hh:mm:ss"]
+    );
+    Ok(())
+}
+
+#[test]
+fn append_columns_at_writes_column_blocks() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row(["keep", "keep", "keep"])?;
+    let columns = vec![
+        vec![CellValue::Text("a".into()), CellValue::Text("b".into())],
+        vec![
+            DateValue::date(2024, 1, 1).into(),
+            DateValue::date(2024, 1, 2).into(),
+        ],
+    ];
+    // первая строка блока уже есть – обновляется, вторая дописывается
+    app.append_columns_at("B1", &columns)?;
+    assert_eq!(app.get_cell("A1")?, CellValue::Text("keep".into()));
+    assert_eq!(app.get_cell("B1")?, CellValue::Text("a".into()));
+    assert_eq!(app.get_cell("C2")?, CellValue::Number(45293.0));
+    let sid = app.cell_style_id("C1")?.expect("date cell has a style");
+    assert_eq!(app.cell_style_id("C2")?, Some(sid));
+    assert_eq!(
+        app.get_range_with_dates("B2:C2")?[0][1],
+        CellValue::Date(DateValue::date(2024, 1, 2))
+    );
+    assert!(
+        app.append_columns_at("A5", &[vec![CellValue::Blank], vec![]])
+            .is_err()
+    );
+    Ok(())
+}
+
+#[test]
+fn rows_stream_sparse() -> Result<()> {
+    let xml = br#"<worksheet><sheetData><row r="2"><c r="B2" s="1"/><c r="D2" t="s"><v>0</v></c><c><v>7</v></c></row><row r="5"/><row><c r="A6" t="b"><v>1</v></c></row></sheetData></worksheet>"#;
+    let sst = ["x".to_owned()];
+    let rows = SheetReader::new(&xml[..], &sst).collect::<Result<Vec<_>>>()?;
+    assert_eq!(
+        rows,
+        vec![
+            (
+                2,
+                vec![
+                    (3, CellValue::Text("x".to_owned())),
+                    (4, CellValue::Number(7.0))
+                ]
+            ),
+            (5, vec![]),
+            (6, vec![(0, CellValue::Bool(true))]),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn rows_match_get_range() -> Result<()> {
+    let file_name = "../test/test.xlsx";
+    let sheet = &scan(file_name)?[0];
+    let mut app = XlsxEditor::open(file_name, sheet)?;
+    let grid = app.get_range("A1:Z30")?;
+    let mut seen = 0;
+    for row in app.rows()? {
+        let (r, cells) = row?;
+        for (c, v) in cells {
+            assert_eq!(grid[r as usize - 1][c as usize], v);
+            seen += 1;
+        }
+    }
+    assert!(seen > 0);
+
+    // потоковое чтение из файла видит то же, что и редактор до правок
+    let mut streamed = Vec::new();
+    for_each_row(file_name, sheet, |r, cells| {
+        streamed.push((r, cells));
+        Ok(())
+    })?;
+    assert_eq!(streamed, app.rows()?.collect::<Result<Vec<_>>>()?);
+
+    app.append_row(["tail"])?;
+    let (last, cells) = app.rows()?.last().unwrap()?;
+    assert_eq!(last, app.get_last_row_index("A")?);
+    assert_eq!(cells, vec![(0, CellValue::Text("tail".to_owned()))]);
+    Ok(())
+}
+
+#[test]
+fn date_formats_detected() {
+    use crate::style::{DateFormatKind, date_format_kind};
+    assert_eq!(date_format_kind(14, None), Some(DateFormatKind::Date));
+    assert_eq!(date_format_kind(22, None), Some(DateFormatKind::DateTime));
+    assert_eq!(date_format_kind(2, None), None);
+    let custom = |code| date_format_kind(164, Some(code));
+    assert_eq!(custom("dd.mm.yyyy"), Some(DateFormatKind::Date));
+    assert_eq!(custom("mmm\\-yy"), Some(DateFormatKind::Date));
+    assert_eq!(custom("[h]:mm:ss"), Some(DateFormatKind::Time));
+    assert_eq!(custom("h:mm AM/PM"), Some(DateFormatKind::Time));
+    assert_eq!(custom("yyyy-mm-dd hh:mm"), Some(DateFormatKind::DateTime));
+    assert_eq!(custom("#,##0.00\"days\""), None);
+    assert_eq!(custom("[Red]0.00;[Blue]-0.00"), None);
+    assert_eq!(custom("General"), None);
+}
+
+#[test]
+fn read_dates_back() -> Result<()> {
+    let serial = DateValue::datetime(2024, 2, 29, 13, 45, 30.5).to_serial(false)?;
+    assert_eq!(
+        DateValue::from_serial(serial, false)?,
+        DateValue::datetime(2024, 2, 29, 13, 45, 30.5)
+    );
+    assert_eq!(
+        DateValue::from_serial(61.0, false)?,
+        DateValue::datetime(1900, 3, 1, 0, 0, 0.0)
+    );
+
+    let file_name = "../test/test_last_row_index.xlsx";
+    let mut app = XlsxEditor::open(file_name, &scan(file_name)?[0])?;
+    assert_eq!(app.used_range()?.as_deref(), Some("A1:D8"));
+    app.append_row([
+        CellValue::Date(DateValue::date(2024, 1, 31)),
+        DateValue::datetime(2024, 1, 31, 8, 30, 0.0).into(),
+        DateValue::time(23, 59, 59.0).into(),
+        1.5.into(),
+    ])?;
+    assert_eq!(app.used_range()?.as_deref(), Some("A1:D9"));
+    let row = app.get_range_with_dates("A9:D9")?.remove(0);
+    assert_eq!(
+        row,
+        vec![
+            CellValue::Date(DateValue::date(2024, 1, 31)),
+            CellValue::Date(DateValue::datetime(2024, 1, 31, 8, 30, 0.0)),
+            CellValue::Date(DateValue::time(23, 59, 59.0)),
+            CellValue::Number(1.5),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn new_workbook_roundtrip() -> Result<()> {
+    let out = "../test/new_workbook_result.xlsx";
+    let mut app = XlsxEditor::new_workbook("Report")?;
+    app.set_string_storage(StringStorage::Shared);
+    app.append_row(["Name", "Score"])?;
+    app.append_row([CellValue::from("Alice"), CellValue::Number(1.5)])?;
+    app.add_worksheet("Extra")?;
+    app.set_cell("B2", "x")?;
+    app.save(out)?;
+
+    assert_eq!(scan(out)?, vec!["Report", "Extra"]);
+    let book = XlsxEditor::open(out, "Report")?;
+    assert_eq!(book.get_cell("A2")?, CellValue::Text("Alice".into()));
+    assert_eq!(book.get_cell("B2")?, CellValue::Number(1.5));
+    let extra = XlsxEditor::open(out, "Extra")?;
+    assert_eq!(extra.get_cell("B2")?, CellValue::Text("x".into()));
+
+    assert!(XlsxEditor::new_workbook("a/b").is_err());
+    assert!(XlsxEditor::new_workbook("").is_err());
+    Ok(())
+}
+
+#[test]
+fn open_from_bytes_and_to_bytes() -> Result<()> {
+    let file_name = "../test/test.xlsx";
+    let bytes = std::fs::read(file_name)?;
+    let sheet_names = scan_reader(std::io::Cursor::new(&bytes))?;
+    assert_eq!(sheet_names, scan(file_name)?);
+
+    let mut app = XlsxEditor::open_from_reader(bytes.as_slice(), &sheet_names[0])?;
+    app.set_cell("A25", "from memory")?;
+    let out = app.to_bytes()?;
+
+    // другой лист из того же архива в памяти
+    let mut again = XlsxEditor::open_from_bytes(out, &sheet_names[0])?;
+    assert_eq!(
+        again.get_cell("A25")?,
+        CellValue::Text("from memory".into())
+    );
+    assert_eq!(
+        again.get_cell("A1")?,
+        XlsxEditor::open(file_name, &sheet_names[0])?.get_cell("A1")?
+    );
+    again.add_worksheet("Extra")?;
+    again.set_cell("A1", 1)?;
+    let mut buf = std::io::Cursor::new(Vec::new());
+    again.save_to_writer(&mut buf)?;
+    let names = scan_reader(std::io::Cursor::new(buf.into_inner()))?;
+    assert_eq!(names.last().map(String::as_str), Some("Extra"));
+    Ok(())
+}
+
+#[test]
+fn formula_references_rewritten() {
+    use crate::formula::rewrite_references;
+    // сдвигаем всё, что ниже 3-й строки, на 2
+    let shift = |f: &str| {
+        rewrite_references(f, |r| {
+            for part in std::iter::once(&mut r.start).chain(r.end.as_mut()) {
+                if let Some(row) = part.row.as_mut()
+                    && *row >= 3
+                {
+                    *row += 2;
+                }
+            }
+            true
+        })
+    };
+    assert_eq!(shift("SUM(A1:B5)*$C$3"), "SUM(A1:B7)*$C$5");
+    assert_eq!(shift("'My Sheet'!A3&\"A3\""), "'My Sheet'!A5&\"A3\"");
+    assert_eq!(
+        shift("Data!$A:$A+3:4+LOG10(A3)"),
+        "Data!$A:$A+5:6+LOG10(A5)"
+    );
+    assert_eq!(
+        shift("[1]Ext!A3+Table1[Col]+#REF!+Rate2024"),
+        "[1]Ext!A3+Table1[Col]+#REF!+Rate2024"
+    );
+    assert_eq!(shift("Jan:Mar!A3+Лист1!B4"), "Jan:Mar!A5+Лист1!B6");
+    assert_eq!(shift("SUM(A3:INDEX(B:B,5))"), "SUM(A5:INDEX(B:B,5))");
+    assert_eq!(
+        rewrite_references("A1+Sheet2!B2", |r| r.sheet.is_some()),
+        "#REF!+Sheet2!B2"
+    );
+}
+
+#[test]
+fn insert_and_delete_rows() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    for i in 1..=5 {
+        app.append_row([i])?;
+    }
+    app.set_cell("B1", "=SUM(A1:A5)+A4")?;
+    app.set_cell("C1", "=Data!A3")?;
+    app.merge_cells("A2:B3")?;
+
+    app.insert_rows(3, 2)?;
+    assert_eq!(app.get_cell("A2")?, CellValue::Number(2.0));
+    assert_eq!(app.get_cell("A3")?, CellValue::Blank);
+    assert_eq!(app.get_cell("A5")?, CellValue::Number(3.0));
+    assert_eq!(app.get_cell("A7")?, CellValue::Number(5.0));
+    let formula = |app: &XlsxEditor, c: &str| match app.get_cell(c) {
+        Ok(CellValue::Formula { formula, .. }) => formula,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(formula(&app, "B1"), "SUM(A1:A7)+A6");
+    assert_eq!(formula(&app, "C1"), "Data!A5");
+    assert!(memchr::memmem::find(&app.sheet_xml, br#"<mergeCell ref="A2:B5"/>"#).is_some());
+
+    app.delete_rows(5, 2)?;
+    assert_eq!(app.get_cell("A5")?, CellValue::Number(5.0));
+    assert_eq!(formula(&app, "B1"), "SUM(A1:A5)+#REF!");
+    assert_eq!(formula(&app, "C1"), "Data!#REF!");
+    assert!(memchr::memmem::find(&app.sheet_xml, br#"<mergeCell ref="A2:B4"/>"#).is_some());
+
+    app.delete_rows(2, 3)?;
+    assert!(memchr::memmem::find(&app.sheet_xml, b"<mergeCells").is_none());
+    assert_eq!(app.get_cell("A2")?, CellValue::Number(5.0));
+    assert_eq!(formula(&app, "B1"), "SUM(A1:A2)+#REF!");
+
+    let out = app.to_bytes()?;
+    let reopened = XlsxEditor::open_from_bytes(out, "Data")?;
+    assert_eq!(reopened.get_cell("A2")?, CellValue::Number(5.0));
+    Ok(())
+}
+
+#[test]
+fn deleted_shared_formula_master_is_promoted() -> Result<()> {
+    // B1 – главная ячейка общей формулы, B2:B3 – зависимые
+    let sheet = |app: &mut XlsxEditor| {
+        app.sheet_xml = String::from_utf8_lossy(&app.sheet_xml)
+            .replace(
+                "<sheetData></sheetData>",
+                concat!(
+                    r#"<sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><f t="shared" ref="B1:B3" si="0">A1*2+$A$3</f><v>8</v></c></row>"#,
+                    r#"<row r="2"><c r="A2"><v>2</v></c><c r="B2"><f t="shared" si="0"/><v>10</v></c></row>"#,
+                    r#"<row r="3"><c r="A3"><v>3</v></c><c r="B3"><f t="shared" si="0"/><v>12</v></c></row></sheetData>"#,
+                ),
+            )
+            .into_bytes();
+    };
+
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    sheet(&mut app);
+    app.delete_rows(1, 1)?;
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(
+        xml.contains(r#"<c r="B1"><f t="shared" si="0" ref="B1:B2">A1*2+$A$2</f><v>10</v></c>"#),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(r#"<c r="B2"><f t="shared" si="0"/><v>12</v></c>"#),
+        "{xml}"
+    );
+
+    // вся группа удалена – от неё ничего не остаётся
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    sheet(&mut app);
+    app.delete_columns("B", 1)?;
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(!xml.contains("<f"), "{xml}");
+
+    Ok(())
+}
+
+#[test]
+fn deleted_rows_drop_hyperlink_relationships() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.set_hyperlink("A2", "https://example.com/a", Some("a"), None)?
+        .set_hyperlink("A4", "https://example.com/b", Some("b"), None)?
+        .set_hyperlink("B3", "#Data!A1", Some("top"), None)?;
+
+    app.delete_rows(2, 1)?;
+    assert_eq!(
+        app.hyperlink("A3")?.as_deref(),
+        Some("https://example.com/b")
+    );
+    let rels = |app: &XlsxEditor| -> Result<String> {
+        Ok(app
+            .part_bytes("xl/worksheets/_rels/sheet1.xml.rels")?
+            .map(|b| String::from_utf8_lossy(&b).into_owned())
+            .unwrap_or_default())
+    };
+    assert!(!rels(&app)?.contains("example.com/a"));
+    assert!(rels(&app)?.contains("example.com/b"));
+
+    app.delete_columns("A", 1)?;
+    assert_eq!(app.hyperlink("A2")?.as_deref(), Some("#Data!A1"));
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(app.to_bytes()?))?;
+    assert!(zip.by_name("xl/worksheets/_rels/sheet1.xml.rels").is_err());
+    Ok(())
+}
+
+#[test]
+fn insert_and_delete_columns() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row([1, 2, 3, 4])?;
+    app.set_cell("A2", "=SUM(A1:D1)+C1+$D$1")?;
+    app.merge_cells("B3:C3")?;
+    app.set_column_width("C", 30.0)?;
+
+    app.insert_columns("B", 2)?;
+    assert_eq!(app.get_cell("A1")?, CellValue::Number(1.0));
+    assert_eq!(app.get_cell("B1")?, CellValue::Blank);
+    assert_eq!(app.get_cell("D1")?, CellValue::Number(2.0));
+    assert_eq!(app.get_cell("F1")?, CellValue::Number(4.0));
+    let formula = |app: &XlsxEditor, c: &str| match app.get_cell(c) {
+        Ok(CellValue::Formula { formula, .. }) => formula,
+        other => panic!("{other:?}"),
+    };
+    assert_eq!(formula(&app, "A2"), "SUM(A1:F1)+E1+$F$1");
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(xml.contains(r#"<mergeCell ref="D3:E3"/>"#), "{xml}");
+    assert!(xml.contains(r#"<col min="5" max="5" width="30""#), "{xml}");
+
+    app.delete_columns("E", 1)?;
+    assert_eq!(app.get_cell("E1")?, CellValue::Number(4.0));
+    assert_eq!(formula(&app, "A2"), "SUM(A1:E1)+#REF!+$E$1");
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(!xml.contains("<mergeCell"), "{xml}");
+    assert!(!xml.contains("<cols>"), "{xml}");
+    Ok(())
+}
+
+#[test]
+fn column_shift_keeps_grouping() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row([1, 2, 3, 4, 5])?;
+    app.set_column_width("B", 12.0)?;
+    // сгруппированные B:D (свёрнутая группа), как их пишет Excel
+    let xml = String::from_utf8(app.sheet_xml.clone())?.replace(
+        r#"<cols><col min="2" max="2" width="12" customWidth="1"/></cols>"#,
+        r#"<cols><col min="2" max="3" width="12" customWidth="1" outlineLevel="1" collapsed="1"/><col min="4" max="4" width="9" outlineLevel="1" phonetic="1"/></cols>"#,
+    );
+    assert!(xml.contains("outlineLevel"));
+    app.sheet_xml = xml.into_bytes();
+
+    app.insert_columns("A", 1)?;
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(
+        xml.contains(
+            r#"<col min="3" max="4" width="12" customWidth="1" outlineLevel="1" collapsed="1"/>"#
+        ),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(r#"<col min="5" max="5" width="9" outlineLevel="1" phonetic="1"/>"#),
+        "{xml}"
+    );
+
+    app.delete_columns("C", 1)?;
+    app.set_column_width("D", 20.0)?;
+    let xml = String::from_utf8_lossy(&app.sheet_xml).into_owned();
+    assert!(
+        xml.contains(
+            r#"<col min="3" max="3" width="12" customWidth="1" outlineLevel="1" collapsed="1"/>"#
+        ),
+        "{xml}"
+    );
+    assert!(
+        xml.contains(
+            r#"<col min="4" max="4" width="20" customWidth="1" outlineLevel="1" phonetic="1"/>"#
+        ),
+        "{xml}"
+    );
+    Ok(())
+}
+
+#[test]
+fn transform_formula_cases() {
+    use crate::formula::{RefTransform, Reference, transform_formula};
+    let t = |f: &str, t| transform_formula(f, "Data", t);
+    assert_eq!(
+        t(
+            "A2+Data!B3+Other!A3+'data'!A:A",
+            RefTransform::InsertRows {
+                sheet: "Data",
+                at: 3,
+                n: 1
+            }
+        ),
+        "A2+Data!B4+Other!A3+'data'!A:A"
+    );
+    assert_eq!(
+        t(
+            "SUM(Other!B1:D1)+Other!C5",
+            RefTransform::DeleteColumns {
+                sheet: "Other",
+                at: 2,
+                n: 1
+            }
+        ),
+        "SUM(Other!B1:C1)+Other!#REF!"
+    );
+    assert_eq!(
+        t(
+            "'Old name'!A1+A1+\"'Old name'!A1\"",
+            RefTransform::RenameSheet {
+                from: "Old name",
+                to: "New's"
+            }
+        ),
+        "'New''s'!A1+A1+\"'Old name'!A1\""
+    );
+    assert_eq!(
+        t(
+            "SUM(Gone!A1:B2)+Kept!A1",
+            RefTransform::DeleteSheet { name: "Gone" }
+        ),
+        "SUM(#REF!)+Kept!A1"
+    );
+    // 3D-ссылки: переименование и удаление любого из концов
+    assert_eq!(
+        t(
+            "SUM(Data:Other!A1)+'Jan 1:data'!B2",
+            RefTransform::RenameSheet {
+                from: "Data",
+                to: "Main"
+            }
+        ),
+        "SUM(Main:Other!A1)+'Jan 1:Main'!B2"
+    );
+    assert_eq!(
+        t(
+            "SUM(Data:Other!A1)+Other!B2",
+            RefTransform::DeleteSheet { name: "Other" }
+        ),
+        "SUM(#REF!)+#REF!"
+    );
+    assert_eq!(
+        t(
+            "SUM(A1:INDEX(B:B,5))",
+            RefTransform::InsertRows {
+                sheet: "Data",
+                at: 1,
+                n: 1
+            }
+        ),
+        "SUM(A2:INDEX(B:B,5))"
+    );
+
+    let r = Reference::parse("'My Sheet'!$A$1:C3").unwrap();
+    assert_eq!(r.sheet.as_deref(), Some("My Sheet"));
+    assert_eq!(r.to_string(), "'My Sheet'!$A$1:C3");
+    assert!(Reference::parse("Sheet1!").is_none());
+}
+
+#[test]
+fn sheet_rename_and_delete_update_formulas() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("Summary")?;
+    app.add_worksheet("Q1 data")?;
+    app.append_row([10, 20])?;
+    app.with_worksheet("Summary")?;
+    app.set_cell("A1", "='Q1 data'!A1+'Q1 data'!B1")?;
+
+    let formula = |app: &XlsxEditor| match app.get_cell("A1") {
+        Ok(CellValue::Formula { formula, .. }) => formula,
+        other => panic!("{other:?}"),
+    };
+    app.rename_worksheet("Q1 data", "Sales")?;
+    assert_eq!(formula(&app), "Sales!A1+Sales!B1");
+    assert!(app.rename_worksheet("Sales", "x/y:[z]").is_err());
+    assert!(app.rename_worksheet("Sales", &"x".repeat(40)).is_err());
+
+    // формулы на другом листе следуют за вставкой строк
+    app.with_worksheet("Sales")?.insert_rows(1, 1)?;
+    app.with_worksheet("Summary")?;
+    assert_eq!(formula(&app), "Sales!A2+Sales!B2");
+
+    app.delete_worksheet("Sales")?;
+    assert_eq!(formula(&app), "#REF!+#REF!");
+
+    let bytes = app.to_bytes()?;
+    let reopened = XlsxEditor::open_from_bytes(bytes, "Summary")?;
+    assert_eq!(formula(&reopened), "#REF!+#REF!");
+    Ok(())
+}
+
+#[test]
+fn copy_worksheet_with_table_and_names() -> Result<()> {
+    use std::io::Read;
+    let mut app = XlsxEditor::new_workbook("Template")?;
+    app.append_row(["Region", "Sales"])?;
+    app.append_row([CellValue::from("x"), CellValue::Number(5.0)])?;
+    app.set_cell("C1", "=Template!B2*2")?;
+    // таблица на листе шаблона
+    let table = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="1" name="Sales" displayName="Sales" ref="A1:B2"><tableColumns count="2"><tableColumn id="1" name="Region"/><tableColumn id="2" name="Sales"/></tableColumns></table>"#;
+    app.new_files
+        .insert("xl/tables/table1.xml".into(), table.as_bytes().to_vec());
+    app.new_files.insert(
+        "xl/worksheets/_rels/sheet1.xml.rels".into(),
+        br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/table" Target="../tables/table1.xml"/></Relationships>"#
+            .to_vec(),
+    );
+    let ct = app.new_files.get_mut("[Content_Types].xml").unwrap();
+    let pos = memchr::memmem::rfind(ct, b"</Types>").unwrap();
+    ct.splice(
+        pos..pos,
+        br#"<Override PartName="/xl/tables/table1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml"/>"#
+            .iter()
+            .copied(),
+    );
+    let pos = memchr::memmem::find(&app.sheet_xml, b"</worksheet>").unwrap();
+    app.sheet_xml.splice(
+        pos..pos,
+        br#"<tableParts count="1"><tablePart r:id="rId1" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"/></tableParts>"#
+            .iter()
+            .copied(),
+    );
+    let pos = memchr::memmem::find(&app.workbook_xml, b"</sheets>").unwrap() + 9;
+    app.workbook_xml.splice(
+        pos..pos,
+        br#"<definedNames><definedName name="_xlnm.Print_Area" localSheetId="0">Template!$A$1:$B$2</definedName></definedNames>"#
+            .iter()
+            .copied(),
+    );
+    // дальше работаем как с обычным файлом
+    let mut app = XlsxEditor::open_from_bytes(app.to_bytes()?, "Template")?;
+    app.copy_worksheet("Template", "North", 0)?;
+    app.set_cell("A2", "north")?;
+    assert!(app.copy_worksheet("Template", "north", 5).is_err());
+    assert!(app.copy_worksheet("Missing", "South", 5).is_err());
+    let bytes = app.to_bytes()?;
+
+    assert_eq!(
+        scan_reader(std::io::Cursor::new(&bytes))?,
+        vec!["North", "Template"]
+    );
+    let north = XlsxEditor::open_from_bytes(bytes.clone(), "North")?;
+    assert_eq!(north.get_cell("A2")?, CellValue::Text("north".into()));
+    assert_eq!(north.get_cell("B2")?, CellValue::Number(5.0));
+    match north.get_cell("C1")? {
+        CellValue::Formula { formula, .. } => assert_eq!(formula, "North!B2*2"),
+        other => panic!("{other:?}"),
+    }
+    let template = XlsxEditor::open_from_bytes(bytes.clone(), "Template")?;
+    assert_eq!(template.get_cell("A2")?, CellValue::Text("x".into()));
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut read = |name: &str| -> Result<String> {
+        let mut s = String::new();
+        zip.by_name(name)?.read_to_string(&mut s)?;
+        Ok(s)
+    };
+    let table2 = read("xl/tables/table2.xml")?;
+    assert!(table2.contains(r#"id="2" name="Sales2" displayName="Sales2""#));
+    assert!(read("xl/tables/table1.xml")?.contains(r#"displayName="Sales""#));
+    assert!(read("xl/worksheets/_rels/sheet2.xml.rels")?.contains("../tables/table2.xml"));
+    assert!(read("[Content_Types].xml")?.contains(r#"PartName="/xl/tables/table2.xml""#));
+    let wb = read("xl/workbook.xml")?;
+    assert!(wb.contains(r#"localSheetId="1">Template!$A$1:$B$2<"#));
+    assert!(wb.contains(r#"localSheetId="0">North!$A$1:$B$2<"#));
+    Ok(())
+}
+
+#[test]
+fn delete_worksheet_remaps_sheet_indices() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("A")?;
+    app.add_worksheet("B")?
+        .add_worksheet("C")?
+        .add_worksheet("D")?;
+    let pos = memchr::memmem::find(&app.workbook_xml, b"</sheets>").unwrap() + 9;
+    app.workbook_xml.splice(
+        pos..pos,
+        br#"<definedNames><definedName name="_xlnm.Print_Area" localSheetId="1">B!$A$1:$C$3</definedName><definedName name="x" localSheetId="3">D!$A$1</definedName><definedName name="g">A!$A$1</definedName></definedNames>"#
+            .iter()
+            .copied(),
+    );
+    let pos = memchr::memmem::find(&app.workbook_xml, b"<sheets>").unwrap();
+    app.workbook_xml.splice(
+        pos..pos,
+        br#"<bookViews><workbookView firstSheet="2" activeTab="3"/></bookViews>"#
+            .iter()
+            .copied(),
+    );
+
+    app.delete_worksheet("B")?;
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(!wb.contains("_xlnm.Print_Area"), "{wb}");
+    assert!(wb.contains(r#"<definedName name="x" localSheetId="2">D!$A$1</definedName>"#));
+    assert!(wb.contains(r#"<definedName name="g">A!$A$1</definedName>"#));
+    assert!(wb.contains(r#"firstSheet="1" activeTab="2""#), "{wb}");
+
+    // последний лист: активная вкладка не уходит за конец
+    app.delete_worksheet("D")?;
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(wb.contains(r#"firstSheet="1" activeTab="1""#), "{wb}");
+    assert!(!wb.contains("localSheetId"), "{wb}");
+
+    app.delete_worksheet("A")?;
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(wb.contains(r#"firstSheet="0" activeTab="0""#), "{wb}");
+    // глобальное имя остаётся, ссылка на удалённый лист – #REF!
+    assert!(
+        wb.contains(r#"<definedName name="g">#REF!</definedName>"#),
+        "{wb}"
+    );
+    assert_eq!(app.sheet_names()?, vec!["C"]);
+    Ok(())
+}
+
+#[test]
+fn move_worksheet_reorders_tabs_and_names() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("A")?;
+    app.add_worksheet("B")?.add_worksheet("C")?;
+    app.set_cell("A1", "on C")?;
+    let pos = memchr::memmem::find(&app.workbook_xml, b"</sheets>").unwrap() + 9;
+    app.workbook_xml.splice(
+        pos..pos,
+        br#"<definedNames><definedName name="x" localSheetId="0">A!$A$1</definedName><definedName name="x" localSheetId="2">C!$A$1</definedName></definedNames>"#
+            .iter()
+            .copied(),
+    );
+    let pos = memchr::memmem::find(&app.workbook_xml, b"<sheets>").unwrap();
+    app.workbook_xml.splice(
+        pos..pos,
+        br#"<bookViews><workbookView activeTab="2"/></bookViews>"#
+            .iter()
+            .copied(),
+    );
+
+    app.move_worksheet("C", 0)?;
+    assert_eq!(app.sheet_names()?, vec!["C", "A", "B"]);
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(wb.contains(r#"activeTab="0""#));
+    assert!(wb.contains(r#"localSheetId="1">A!$A$1<"#));
+    assert!(wb.contains(r#"localSheetId="0">C!$A$1<"#));
+
+    app.move_worksheet("C", 99)?;
+    assert_eq!(app.sheet_names()?, vec!["A", "B", "C"]);
+    assert!(app.move_worksheet("D", 0).is_err());
+
+    app.move_worksheet("B", 0)?;
+    let bytes = app.to_bytes()?;
+    assert_eq!(
+        scan_reader(std::io::Cursor::new(&bytes))?,
+        vec!["B", "A", "C"]
+    );
+    let c = XlsxEditor::open_from_bytes(bytes, "C")?;
+    assert_eq!(c.get_cell("A1")?, CellValue::Text("on C".into()));
+    Ok(())
+}
+
+#[test]
+fn move_worksheet_keeps_state_and_escaped_names() -> Result<()> {
+    let mut app = XlsxEditor::new_workbook("A")?;
+    app.add_worksheet("R&D")?.add_worksheet("Secret")?;
+    app.workbook_xml = String::from_utf8(app.workbook_xml.clone())?
+        .replace(
+            r#"<sheet name="Secret""#,
+            r#"<sheet state="hidden" name="Secret""#,
+        )
+        .into_bytes();
+
+    app.move_worksheet("R&D", 0)?;
+    app.move_worksheet("Secret", 1)?;
+    app.move_worksheet("R&D", 2)?;
+    assert_eq!(app.sheet_names()?, vec!["Secret", "A", "R&D"]);
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(wb.contains(r#"name="R&amp;D""#), "{wb}");
+    assert!(!wb.contains("&amp;amp;"), "{wb}");
+    assert!(
+        wb.contains(r#"<sheet name="Secret" sheetId="3" state="hidden""#),
+        "{wb}"
+    );
+
+    // добавление листа перестраивает <sheets> так же
+    app.add_worksheet("New")?;
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(
+        wb.contains(r#"<sheet name="Secret" sheetId="1" state="hidden""#),
+        "{wb}"
+    );
+    assert!(!wb.contains("&amp;amp;"), "{wb}");
+
+    let bytes = app.to_bytes()?;
+    assert_eq!(
+        scan_reader(std::io::Cursor::new(&bytes))?,
+        vec!["Secret", "A", "R&D", "New"]
+    );
+    XlsxEditor::open_from_bytes(bytes, "R&D")?;
+    Ok(())
+}
+
+#[test]
+fn import_worksheet_from_other_workbook() -> Result<()> {
+    let mut src = XlsxEditor::new_workbook("Prices")?;
+    src.set_string_storage(StringStorage::Shared);
+    src.append_row(["Item", "Price"])?;
+    src.append_row([CellValue::from("Tea"), CellValue::Number(2.5)])?;
+    src.set_cell("C2", "=Prices!B2*2")?;
+    src.set_fill("A1:B1", "FFFF00")?;
+    src.set_number_format("B2", "0.000")?;
+    src.merge_cells("A3:B3")?;
+    src.set_column_width("A", 30.0)?;
+    let src = XlsxEditor::open_from_bytes(src.to_bytes()?, "Prices")?;
+
+    let mut dst = XlsxEditor::new_workbook("Main")?;
+    dst.set_string_storage(StringStorage::Shared);
+    dst.append_row(["Other", "Tea"])?;
+    dst.set_fill("A1", "00FF00")?;
+    dst.import_worksheet(&src, "Prices", "Imported", 0)?;
+    assert!(dst.import_worksheet(&src, "Prices", "main", 0).is_err());
+    assert!(dst.import_worksheet(&src, "Missing", "X", 0).is_err());
+    let bytes = dst.to_bytes()?;
+
+    assert_eq!(
+        scan_reader(std::io::Cursor::new(&bytes))?,
+        vec!["Imported", "Main"]
+    );
+    let book = XlsxEditor::open_from_bytes(bytes.clone(), "Imported")?;
+    assert_eq!(book.get_cell("A1")?, CellValue::Text("Item".into()));
+    assert_eq!(book.get_cell("A2")?, CellValue::Text("Tea".into()));
+    assert_eq!(book.get_cell("B2")?, CellValue::Number(2.5));
+    match book.get_cell("C2")? {
+        CellValue::Formula { formula, .. } => assert_eq!(formula, "Imported!B2*2"),
+        other => panic!("{other:?}"),
+    }
+    let sheet = String::from_utf8(book.sheet_xml.clone())?;
+    assert!(sheet.contains(r#"<mergeCell ref="A3:B3"/>"#));
+    assert!(sheet.contains(r#"width="30""#));
+
+    // стиль A1 указывает на жёлтую заливку, B2 – на формат 0.000
+    let styles = String::from_utf8(book.styles_xml.clone())?;
+    let xf = |s: u32| -> String {
+        let xfs = &styles[styles.find("<cellXfs").unwrap()..styles.find("</cellXfs>").unwrap()];
+        xfs.split("<xf ").nth(s as usize + 1).unwrap().to_owned()
+    };
+    let attr = |tag: &str, key: &str| -> String {
+        let a = tag
+            .find(&format!(" {key}=\""))
+            .or_else(|| tag.find(&format!("{key}=\"")))
+            .unwrap();
+        let v = &tag[a..];
+        let v = &v[v.find('"').unwrap() + 1..];
+        v[..v.find('"').unwrap()].to_owned()
+    };
+    let a1 = xf(book.cell_style_id("A1")?.unwrap());
+    let fill_id: usize = attr(&a1, "fillId").parse()?;
+    let fills = &styles[styles.find("<fills").unwrap()..styles.find("</fills>").unwrap()];
+    assert!(
+        fills
+            .split("<fill>")
+            .nth(fill_id + 1)
+            .unwrap()
+            .contains("FFFF00")
+    );
+    let b2 = xf(book.cell_style_id("B2")?.unwrap());
+    let fmt_id = attr(&b2, "numFmtId");
+    assert!(styles.contains(&format!(r#"numFmtId="{fmt_id}" formatCode="0.000""#)));
+
+    let main = XlsxEditor::open_from_bytes(bytes, "Main")?;
+    assert_eq!(main.get_cell("B1")?, CellValue::Text("Tea".into()));
+    Ok(())
+}
+
+#[test]
+fn import_worksheet_copies_only_used_styles() -> Result<()> {
+    use crate::sheet_copy::block_children;
+    let mut src = XlsxEditor::new_workbook("Used")?;
+    src.append_row(["a", "b"])?;
+    src.set_fill("A1", "FFFF00")?;
+    src.add_worksheet("Unused")?;
+    src.append_row(["c"])?;
+    src.set_fill("A1", "FF0000")?;
+    src.set_font("A1", "Arial", 20.0, true, false)?;
+    let src = XlsxEditor::open_from_bytes(src.to_bytes()?, "Used")?;
+
+    let mut dst = XlsxEditor::new_workbook("Main")?;
+    dst.set_fill("A1", "FFFF00")?;
+    let xfs_before = block_children(&dst.styles_xml, b"cellXfs")?.len();
+    let fills_before = block_children(&dst.styles_xml, b"fills")?.len();
+
+    dst.import_worksheet(&src, "Used", "First", 1)?;
+    let styles = String::from_utf8(dst.styles_xml.clone())?;
+    assert!(!styles.contains("FF0000"), "{styles}");
+    assert!(!styles.contains("Arial"), "{styles}");
+    // жёлтая заливка и её xf уже есть в книге
+    assert_eq!(
+        block_children(&dst.styles_xml, b"fills")?.len(),
+        fills_before
+    );
+    assert_eq!(
+        block_children(&dst.styles_xml, b"cellXfs")?.len(),
+        xfs_before
+    );
+
+    dst.import_worksheet(&src, "Used", "Second", 2)?;
+    assert_eq!(
+        block_children(&dst.styles_xml, b"cellXfs")?.len(),
+        xfs_before
+    );
+    let second = dst.cell_style_id("A1")?;
+    dst.with_worksheet("First")?;
+    assert_eq!(dst.cell_style_id("A1")?, second);
+    Ok(())
+}
+
+#[test]
+fn merge_overlap_and_unmerge() -> Result<()> {
+    use crate::MergeOptions;
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row(["a", "b", "c"])?;
+    app.append_row(["d", "e", "f"])?;
+    app.set_fill("B1", "FFCC00")?;
+    app.merge_cells("A1:B1")?;
+    app.merge_cells("A3:C4")?;
+    assert_eq!(app.merged_ranges()?, vec!["A1:B1", "A3:C4"]);
+
+    assert!(app.merge_cells("B1:C2").is_err());
+    assert!(app.merge_cells("D1").is_err());
+    assert_eq!(app.merged_ranges()?.len(), 2);
+
+    app.merge_cells_with(
+        "C2:B1",
+        MergeOptions {
+            replace_overlapping: true,
+            clear_non_anchor: true,
+        },
+    )?;
+    assert_eq!(app.merged_ranges()?, vec!["A3:C4", "B1:C2"]);
+    assert_eq!(app.get_cell("B1")?, CellValue::Text("b".into()));
+    assert_eq!(app.get_cell("C1")?, CellValue::Blank);
+    assert_eq!(app.get_cell("C2")?, CellValue::Blank);
+    assert_eq!(app.get_cell("A1")?, CellValue::Text("a".into()));
+    assert!(app.cell_style_id("B1")?.is_some());
+
+    app.unmerge_cells("C4")?;
+    assert_eq!(app.merged_ranges()?, vec!["B1:C2"]);
+    app.unmerge_cells("A1:Z9")?;
+    assert!(app.merged_ranges()?.is_empty());
+    assert!(memchr::memmem::find(&app.sheet_xml, b"<mergeCells").is_none());
+    Ok(())
+}
+
+#[test]
+fn border_sides_outline_and_inner() -> Result<()> {
+    use crate::style::{BorderSide, BorderSpec, Color};
+    fn border_of(xl: &mut XlsxEditor, cell: &str) -> Result<BorderSpec> {
+        let sid = xl.cell_style_id(cell)?.unwrap_or(0);
+        let ix = xl.style_ix_mut()?;
+        let bid = ix.xfs[sid as usize].border_id.unwrap_or(0);
+        Ok(ix.borders[bid as usize].clone())
+    }
+
+    let mut app = XlsxEditor::new_workbook("Report")?;
+    app.append_table_at("A1", [["h1", "h2", "h3"], ["1", "2", "3"], ["4", "5", "6"]])?;
+    let thick = BorderSide::new("thick");
+    let red = BorderSide::new("medium").with_color("ff0000");
+    let hair = BorderSide::new("hair");
+
+    app.set_border_spec(
+        "A1:C1",
+        &BorderSpec {
+            bottom: Some(thick.clone()),
+            ..Default::default()
+        },
+    )?
+    .set_outline_border("A2:C3", &red)?
+    .set_inner_borders("A1:C3", Some(&hair), None)?;
+
+    let a2 = border_of(&mut app, "A2")?;
+    assert_eq!(
+        a2.left.as_ref().unwrap().color,
+        Some(Color::rgb("FFFF0000"))
+    );
+    assert_eq!(a2.top, Some(red.clone().with_color("FFFF0000")));
+    assert_eq!(a2.bottom, Some(hair.clone()));
+    assert_eq!(a2.right, None);
+    // внутренняя линия перекрыла толстую под заголовком
+    assert_eq!(border_of(&mut app, "B1")?.bottom, Some(hair.clone()));
+    let c3 = border_of(&mut app, "C3")?;
+    assert_eq!(c3.right.as_ref().map(|s| s.style.as_str()), Some("medium"));
+    assert_eq!(c3.bottom.as_ref().map(|s| s.style.as_str()), Some("medium"));
+    assert_eq!(border_of(&mut app, "B2")?.left, None);
+
+    // стороны "none" стирают линию, одинаковые рамки переиспользуются
+    app.set_border_spec(
+        "A2",
+        &BorderSpec {
+            left: Some(BorderSide::new("none")),
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(border_of(&mut app, "A2")?.left, None);
+    app.set_border("A1:B1", "thin")?;
+    let count = app.style_ix_mut()?.borders_count;
+    app.set_border_spec("D1", &BorderSpec::all(BorderSide::new("thin")))?;
+    assert_eq!(app.style_ix_mut()?.borders_count, count);
+
+    // после переоткрытия рамки читаются так же
+    let bytes = app.to_bytes()?;
+    let mut back = XlsxEditor::open_from_bytes(bytes, "Report")?;
+    assert_eq!(border_of(&mut back, "C3")?, c3);
+    let before = back.style_ix_mut()?.borders_count;
+    back.set_outline_border("A2:C3", &BorderSide::new("medium").with_color("FF0000"))?;
+    assert_eq!(back.style_ix_mut()?.borders_count, before);
+
+    // цвета темы и палитры; значение атрибута экранируется
+    let theme = BorderSide::new("thin").with_color_spec(Color::theme_tint(4, -0.25));
+    back.set_outline_border("E5", &theme)?;
+    back.set_border_spec(
+        "E6",
+        &BorderSpec::all(BorderSide::new("thin").with_color(r#"FF0000"/><x y=""#)),
+    )?;
+    let styles = String::from_utf8(back.styles_xml.clone())?;
+    assert!(styles.contains(r#"<left style="thin"><color theme="4" tint="-0.25"/></left>"#));
+    assert!(!styles.contains("<x "));
+    let mut back = XlsxEditor::open_from_bytes(back.to_bytes()?, "Report")?;
+    assert_eq!(border_of(&mut back, "E5")?.top, Some(theme));
+    Ok(())
+}
+
+#[test]
+fn font_spec_color_underline_roundtrip() -> Result<()> {
+    use crate::style::{Color, FontSpec, FontVertAlign, Underline};
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row([-1.5, 2.0])?;
+    let red = FontSpec {
+        color: Some(Color::rgb("ff0000")),
+        underline: Some(Underline::DoubleAccounting),
+        strike: true,
+        family: Some(2),
+        ..FontSpec::new("Arial", 10.0)
+    };
+    let blue_sup = FontSpec {
+        color: Some(Color::theme(4)),
+        vert_align: Some(FontVertAlign::Superscript),
+        bold: true,
+        scheme: Some("minor".into()),
+        ..FontSpec::default()
+    };
+    app.set_font_spec("A1", &red)?
+        .set_font_spec("B1", &blue_sup)?;
+    let fonts = app.style_ix_mut()?.fonts_count;
+    // такой же шрифт переиспользуется, RGB без альфы == с альфой
+    app.set_font_spec(
+        "A2",
+        &FontSpec {
+            color: Some(Color::rgb("FFFF0000")),
+            ..red.clone()
+        },
+    )?;
+    app.set_font("C1", "Arial", 10.0, true, false)?;
+    app.set_font("C2", "Arial", 10.0, true, false)?;
+    assert_eq!(app.style_ix_mut()?.fonts_count, fonts + 1);
+
+    let styles = String::from_utf8(app.styles_xml.clone())?;
+    assert!(styles.contains(
+        r#"<font><strike/><u val="doubleAccounting"/><sz val="10"/><color rgb="FFFF0000"/><name val="Arial"/><family val="2"/></font>"#
+    ));
+    assert!(styles.contains(
+        r#"<font><b/><vertAlign val="superscript"/><sz val="11"/><color theme="4"/><name val="Calibri"/><scheme val="minor"/></font>"#
+    ));
+
+    // после переоткрытия индекс узнаёт те же шрифты
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "Data")?;
+    let before = back.style_ix_mut()?.fonts_count;
+    back.set_font_spec("D1", &red)?
+        .set_font_spec("D2", &blue_sup)?;
+    assert_eq!(back.style_ix_mut()?.fonts_count, before);
+    Ok(())
+}
+
+#[test]
+fn pattern_and_gradient_fills() -> Result<()> {
+    use crate::style::{Color, FillSpec, GradientType, PatternType};
+    let mut app = XlsxEditor::new_workbook("Data")?;
+    app.append_row([1.0, 2.0, 3.0])?;
+    let hatch = FillSpec::pattern(
+        PatternType::LightUp,
+        Some(Color::theme_tint(4, -0.25)),
+        Some(Color::rgb("ffffff")),
+    );
+    let linear = FillSpec::linear(
+        90.0,
+        vec![(0.0, Color::rgb("FFFFFF")), (1.0, Color::theme(4))],
+    );
+    let path = FillSpec::Gradient {
+        gradient: GradientType::Path {
+            left: 0.5,
+            right: 0.5,
+            top: 0.5,
+            bottom: 0.5,
+        },
+        stops: vec![(0.0, Color::indexed(9)), (1.0, Color::rgb("FF0000"))],
+    };
+    app.set_fill_spec("A1", &hatch)?
+        .set_fill_spec("B1", &linear)?
+        .set_fill_spec("C1", &path)?
+        .set_fill("A2", "ffcc00")?;
+    let fills = app.style_ix_mut()?.fills_count;
+    app.set_fill_spec("B2", &FillSpec::solid(Color::rgb("FFFFCC00")))?
+        .set_fill_spec("C2", &hatch)?;
+    assert_eq!(app.style_ix_mut()?.fills_count, fills);
+
+    let styles = String::from_utf8(app.styles_xml.clone())?;
+    assert!(styles.contains(
+        r#"<fill><patternFill patternType="lightUp"><fgColor theme="4" tint="-0.25"/><bgColor rgb="FFFFFFFF"/></patternFill></fill>"#
+    ));
+    assert!(styles.contains(
+        r#"<fill><gradientFill degree="90"><stop position="0"><color rgb="FFFFFFFF"/></stop><stop position="1"><color theme="4"/></stop></gradientFill></fill>"#
+    ));
+    assert!(
+        styles.contains(
+            r#"<gradientFill type="path" left="0.5" right="0.5" top="0.5" bottom="0.5">"#
+        )
+    );
+
+    // существующие заливки (в т.ч. solid с bgColor indexed=64) узнаются после переоткрытия
+    let mut back = XlsxEditor::open("../test/style_test.xlsx", "Sheet1")?;
+    back.set_fill("A1", "FFCCCC")?;
+    let mut back = XlsxEditor::open_from_bytes(back.to_bytes()?, "Sheet1")?;
+    let before = back.style_ix_mut()?.fills_count;
+    back.set_fill("B1", "FFCCCC")?
+        .set_fill_spec("C1", &FillSpec::pattern(PatternType::Gray125, None, None))?;
+    assert_eq!(back.style_ix_mut()?.fills_count, before);
+
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "Data")?;
+    let before = back.style_ix_mut()?.fills_count;
+    back.set_fill_spec("D1", &hatch)?
+        .set_fill_spec("D2", &linear)?
+        .set_fill_spec("D3", &path)?;
+    assert_eq!(back.style_ix_mut()?.fills_count, before);
+    Ok(())
+}
+
+#[test]
+fn alignment_indent_rotation_shrink() -> Result<()> {
+    use crate::style::{AlignmentSpec, ReadingOrder, TEXT_ROTATION_VERTICAL};
+    let mut app = XlsxEditor::new_workbook("PnL")?;
+    app.append_row(["Revenue", "Q1", "Q2"])?;
+    app.append_row(["Services", "1", "2"])?;
+    app.set_alignment_spec(
+        "B1:C1",
+        &AlignmentSpec::default()
+            .with_horiz(HorizAlignment::CenterContinuous)
+            .with_text_rotation(45),
+    )?
+    .set_alignment_spec("A2", &AlignmentSpec::default().with_indent(2))?
+    // второй патч дополняет первый, а не затирает его
+    .set_alignment_spec(
+        "A2",
+        &AlignmentSpec::default()
+            .with_horiz(HorizAlignment::Left)
+            .with_shrink_to_fit(true)
+            .with_reading_order(ReadingOrder::RightToLeft),
+    )?
+    .set_alignment_spec(
+        "A1",
+        &AlignmentSpec::default()
+            .with_vert(VertAlignment::Distributed)
+            .with_text_rotation(TEXT_ROTATION_VERTICAL),
+    )?;
+    assert!(
+        app.set_alignment_spec("A3", &AlignmentSpec::default().with_text_rotation(200))
+            .is_err()
+    );
+    // старый AlignSpec по-прежнему работает и не сбрасывает отступ
+    app.set_alignment(
+        "A2",
+        &AlignSpec {
+            horiz: None,
+            vert: Some(VertAlignment::Top),
+            wrap: false,
+        },
+    )?;
+
+    let styles = String::from_utf8(app.styles_xml.clone())?;
+    assert!(
+        styles.contains(
+            r#"<alignment horizontal="left" vertical="top" indent="2" shrinkToFit="1" readingOrder="2"/>"#
+        )
+    );
+    assert!(styles.contains(r#"<alignment horizontal="centerContinuous" textRotation="45"/>"#));
+
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "PnL")?;
+    let sid = back.cell_style_id("A2")?.unwrap();
+    let a2 = back.style_ix_mut()?.xfs[sid as usize]
+        .align
+        .clone()
+        .unwrap();
+    assert_eq!(a2.indent, Some(2));
+    assert_eq!(a2.reading_order, Some(ReadingOrder::RightToLeft));
+    assert_eq!(a2.shrink_to_fit, Some(true));
+    let sid = back.cell_style_id("A1")?.unwrap();
+    let a1 = back.style_ix_mut()?.xfs[sid as usize]
+        .align
+        .clone()
+        .unwrap();
+    assert_eq!(a1.text_rotation, Some(255));
+
+    // такой же стиль находится в индексе после переоткрытия
+    let xfs = back.style_ix_mut()?.xfs.len();
+    back.set_alignment_spec("C2", &a2)?;
+    assert_eq!(back.style_ix_mut()?.xfs.len(), xfs);
+
+    // Some(false) снимает флаг, остальное выравнивание остаётся
+    back.set_alignment_spec("A2", &AlignmentSpec::default().with_shrink_to_fit(false))?;
+    let sid = back.cell_style_id("A2")?.unwrap();
+    let a2 = back.style_ix_mut()?.xfs[sid as usize]
+        .align
+        .clone()
+        .unwrap();
+    assert_eq!(a2.shrink_to_fit, None);
+    assert_eq!(a2.indent, Some(2));
+    Ok(())
+}
+
+#[test]
+fn sheet_and_workbook_protection() -> Result<()> {
+    use crate::{
+        SheetProtection,
+        protection::{legacy_password_hash, sha512_password_hash},
+        style::CellProtection,
+    };
+    use base64::{Engine, engine::general_purpose::STANDARD};
+
+    assert_eq!(legacy_password_hash("password"), "83AF");
+    assert_eq!(legacy_password_hash("test"), "CBEB");
+    assert_eq!(legacy_password_hash("abcdefghijklmno"), "C6BC");
+    // длинный пароль не переполняет сдвиг: учитываются первые 15 символов
+    assert_eq!(
+        legacy_password_hash(&format!("abcdefghijklmno{}", "x".repeat(100))),
+        "C6BC"
+    );
+    let salt: Vec<u8> = (0..16).collect();
+    assert_eq!(
+        STANDARD.encode(sha512_password_hash("secret", &salt, 100_000)),
+        "M5SOVnbQG4SHyBnRVAYzAx8mPtxyyzMuWxcMv7tkyFO3MBXX9OJjklwPglNHdoHVkKPm4MPfUblqHmAsXfF5HA=="
+    );
+
+    let mut app = XlsxEditor::new_workbook("PnL")?;
+    app.append_row(["Revenue", "1"])?;
+    app.merge_cells("A3:B3")?;
+    app.set_cell_protection(
+        "B1",
+        CellProtection {
+            locked: false,
+            hidden: true,
+        },
+    )?;
+    app.protect_sheet(
+        &SheetProtection {
+            format_columns: true,
+            sort: true,
+            ..Default::default()
+        },
+        Some("secret"),
+    )?;
+    // повторная защита заменяет, а не дублирует
+    app.protect_sheet(
+        &SheetProtection {
+            format_columns: true,
+            sort: true,
+            legacy_hash: true,
+            ..Default::default()
+        },
+        Some("password"),
+    )?;
+    app.protect_workbook(true, false, Some("secret"), false)?;
+    assert!(app.is_sheet_protected());
+
+    let sheet = String::from_utf8(app.sheet_xml.clone())?;
+    assert_eq!(sheet.matches("<sheetProtection").count(), 1);
+    assert!(sheet.contains(
+        r#"<sheetProtection password="83AF" sheet="1" objects="1" scenarios="1" formatColumns="0" sort="0"/>"#
+    ));
+    // sheetProtection идёт после sheetData и до mergeCells
+    let p = sheet.find("<sheetProtection").unwrap();
+    assert!(sheet.find("</sheetData>").unwrap() < p);
+    assert!(p < sheet.find("<mergeCells").unwrap());
+
+    let wb = String::from_utf8(app.workbook_xml.clone())?;
+    assert!(
+        wb.contains(r#"<workbookProtection workbookAlgorithmName="SHA-512" workbookHashValue=""#)
+    );
+    assert!(wb.contains(r#"workbookSpinCount="100000" lockStructure="1"/>"#));
+    assert!(wb.find("<workbookProtection").unwrap() < wb.find("<sheets").unwrap());
+
+    let styles = String::from_utf8(app.styles_xml.clone())?;
+    assert!(styles.contains(r#"applyProtection="1"><protection locked="0" hidden="1"/></xf>"#));
+
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "PnL")?;
+    assert!(back.is_sheet_protected());
+    let sid = back.cell_style_id("B1")?.unwrap();
+    let prot = back.style_ix_mut()?.xfs[sid as usize].protection.unwrap();
+    assert!(!prot.locked && prot.hidden);
+
+    back.unprotect_sheet()?.unprotect_workbook()?;
+    assert!(!back.is_sheet_protected());
+    assert!(!String::from_utf8(back.workbook_xml.clone())?.contains("workbookProtection"));
+    Ok(())
+}
+
+// pageMargins, как в листах Excel: новые блоки должны встать перед ним
+#[cfg(test)]
+fn add_page_margins(app: &mut XlsxEditor) {
+    app.sheet_xml = String::from_utf8_lossy(&app.sheet_xml)
+        .replace(
+            "</sheetData>",
+            r#"</sheetData><pageMargins left="0.7" right="0.7" top="0.75" bottom="0.75" header="0.3" footer="0.3"/>"#,
+        )
+        .into_bytes();
+}
+
+#[test]
+fn conditional_formats_and_dxfs() -> Result<()> {
+    use crate::{
+        CfOperator, Cfvo, ConditionalRule,
+        style::{Color, DxfStyle},
+    };
+    let red = DxfStyle {
+        font_color: Some(Color::rgb("9C0006")),
+        fill: Some(Color::rgb("FFC7CE")),
+        ..Default::default()
+    };
+    let mut app = XlsxEditor::new_workbook("KPI")?;
+    add_page_margins(&mut app);
+    app.append_row(["Region", "Plan", "Fact"])?;
+    app.append_row(["North", "10", "12"])?;
+    app.append_row(["South", "10", "7"])?;
+    app.merge_cells("A5:B5")?;
+
+    app.add_conditional_format(
+        "C2:C3",
+        &ConditionalRule::CellIs {
+            operator: CfOperator::LessThan,
+            formula: "=B2".into(),
+            formula2: None,
+            style: red.clone(),
+        },
+    )?
+    .add_conditional_format(
+        "A2:A3",
+        &ConditionalRule::Expression {
+            formula: "$C2<$B2".into(),
+            style: red.clone(),
+        },
+    )?
+    .add_conditional_format(
+        "C2:C3",
+        &ConditionalRule::ColorScale {
+            stops: vec![
+                (Cfvo::Min, Color::rgb("F8696B")),
+                ("percentile:50".parse()?, Color::rgb("FFEB84")),
+                (Cfvo::Max, Color::rgb("63BE7B")),
+            ],
+        },
+    )?
+    .add_conditional_format(
+        "B2:B3",
+        &ConditionalRule::DataBar {
+            min: Cfvo::Min,
+            max: Cfvo::Max,
+            color: Color::rgb("638EC6"),
+        },
+    )?
+    .add_conditional_format(
+        "C2:C3",
+        &ConditionalRule::IconSet {
+            icons: "3TrafficLights1".into(),
+            thresholds: vec![],
+            reverse: false,
+            show_value: true,
+        },
+    )?
+    .add_conditional_format(
+        "B2:C3",
+        &ConditionalRule::Top {
+            rank: 1,
+            bottom: true,
+            percent: false,
+            style: DxfStyle {
+                bold: Some(true),
+                ..Default::default()
+            },
+        },
+    )?
+    .add_conditional_format("A2:A3", &ConditionalRule::Duplicates { style: red.clone() })?;
+
+    // ошибки не портят лист
+    let before = app.sheet_xml.clone();
+    assert!(
+        app.add_conditional_format(
+            "C2:C3",
+            &ConditionalRule::CellIs {
+                operator: CfOperator::Between,
+                formula: "1".into(),
+                formula2: None,
+                style: red.clone(),
+            },
+        )
+        .is_err()
+    );
+    assert!(
+        app.add_conditional_format(
+            "C2",
+            &ConditionalRule::IconSet {
+                icons: "3TrafficLights1".into(),
+                thresholds: vec![Cfvo::Min],
+                reverse: false,
+                show_value: true,
+            },
+        )
+        .is_err()
+    );
+    assert_eq!(app.sheet_xml, before);
+
+    let sheet = String::from_utf8(app.sheet_xml.clone())?;
+    assert!(sheet.contains(
+        r#"<conditionalFormatting sqref="C2:C3"><cfRule type="cellIs" dxfId="0" priority="1" operator="lessThan"><formula>B2</formula></cfRule></conditionalFormatting>"#
+    ));
+    // одинаковый стиль – один dxf
+    assert!(sheet.contains(
+        r#"<cfRule type="expression" dxfId="0" priority="2"><formula>$C2&lt;$B2</formula>"#
+    ));
+    assert!(sheet.contains(r#"<cfvo type="percentile" val="50"/>"#));
+    assert!(sheet.contains(
+        r#"<iconSet iconSet="3TrafficLights1"><cfvo type="percent" val="0"/><cfvo type="percent" val="33"/><cfvo type="percent" val="67"/></iconSet>"#
+    ));
+    assert!(sheet.contains(r#"<cfRule type="top10" dxfId="1" priority="6" bottom="1" rank="1"/>"#));
+    assert!(sheet.contains(r#"<cfRule type="duplicateValues" dxfId="0" priority="7"/>"#));
+    // порядок по схеме: mergeCells → conditionalFormatting → pageMargins
+    let cf = sheet.find("<conditionalFormatting").unwrap();
+    assert!(sheet.find("<mergeCells").unwrap() < cf);
+    assert!(sheet.rfind("</conditionalFormatting>").unwrap() < sheet.find("<pageMargins").unwrap());
+
+    let styles = String::from_utf8(app.styles_xml.clone())?;
+    assert!(styles.contains(
+        r#"<dxfs count="2"><dxf><font><color rgb="FF9C0006"/></font><fill><patternFill><bgColor rgb="FFFFC7CE"/></patternFill></fill></dxf><dxf><font><b/></font></dxf></dxfs>"#
+    ));
+
+    // после переоткрытия существующие dxf и priority подхватываются
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "KPI")?;
+    back.add_conditional_format(
+        "B2:B3",
+        &ConditionalRule::Unique {
+            style: DxfStyle {
+                bold: Some(true),
+                ..Default::default()
+            },
+        },
+    )?;
+    let sheet = String::from_utf8(back.sheet_xml.clone())?;
+    assert!(sheet.contains(r#"<cfRule type="uniqueValues" dxfId="1" priority="8"/>"#));
+    assert!(String::from_utf8(back.styles_xml.clone())?.contains(r#"<dxfs count="2">"#));
+    Ok(())
+}
+
+#[test]
+fn data_validation_lists_and_bounds() -> Result<()> {
+    use crate::{CfOperator, DataValidation, ErrorStyle, ValidationRule, ValidationType};
+    let mut app = XlsxEditor::new_workbook("Input")?;
+    add_page_margins(&mut app);
+    app.append_row(["Status", "Currency", "Qty", "Due", "Code"])?;
+    app.merge_cells("G1:H1")?;
+
+    let status = DataValidation::new(ValidationRule::List(vec![
+        "Open".into(),
+        "Done & closed".into(),
+    ]))
+    .with_prompt("Status", "Pick a status")
+    .with_error(ErrorStyle::Warning, "Oops", "Not in the list");
+    app.add_data_validation("A2:A100", &status)?
+        .add_data_validation(
+            "B2:B100",
+            &DataValidation::new(ValidationRule::ListRange("$J$1:$J$3".into())),
+        )?
+        .add_data_validation(
+            "C2:C100",
+            &DataValidation::new(ValidationRule::Compare {
+                kind: ValidationType::Whole,
+                operator: CfOperator::Between,
+                formula1: "1".into(),
+                formula2: Some("1000".into()),
+            }),
+        )?
+        .add_data_validation(
+            "D2:D100",
+            &DataValidation::new(ValidationRule::Compare {
+                kind: ValidationType::Date,
+                operator: CfOperator::GreaterThanOrEqual,
+                formula1: "DATE(2025,1,1)".into(),
+                formula2: None,
+            }),
+        )?
+        .add_data_validation(
+            "E2:E100",
+            &DataValidation::new(ValidationRule::Custom("=LEN(E2)<=5".into())),
+        )?;
+
+    // пересечение и ошибки не меняют лист
+    let before = app.sheet_xml.clone();
+    assert!(app.add_data_validation("A50:B50", &status).is_err());
+    assert!(
+        app.add_data_validation(
+            "F2",
+            &DataValidation::new(ValidationRule::List(vec!["a,b".into()]))
+        )
+        .is_err()
+    );
+    assert!(
+        app.add_data_validation(
+            "F2",
+            &DataValidation::new(ValidationRule::List(vec!["x".into()]))
+                .with_prompt("A title that is far too long to fit", "x")
+        )
+        .is_err()
+    );
+    assert_eq!(app.sheet_xml, before);
+
+    let sheet = String::from_utf8(app.sheet_xml.clone())?;
+    assert!(sheet.contains(r#"<dataValidations count="5">"#));
+    assert!(sheet.contains(
+        r#"<dataValidation type="list" errorStyle="warning" allowBlank="1" showInputMessage="1" showErrorMessage="1" errorTitle="Oops" error="Not in the list" promptTitle="Status" prompt="Pick a status" sqref="A2:A100"><formula1>"Open,Done &amp; closed"</formula1></dataValidation>"#
+    ));
+    assert!(sheet.contains(r#"sqref="C2:C100"><formula1>1</formula1><formula2>1000</formula2>"#));
+    assert!(sheet.contains(r#"<dataValidation type="date" operator="greaterThanOrEqual""#));
+    assert!(sheet.contains(r#"<formula1>LEN(E2)&lt;=5</formula1>"#));
+    // mergeCells → dataValidations → pageMargins
+    let dv = sheet.find("<dataValidations").unwrap();
+    assert!(sheet.find("<mergeCells").unwrap() < dv);
+    assert!(dv < sheet.find("<pageMargins").unwrap());
+
+    let mut back = XlsxEditor::open_from_bytes(app.to_bytes()?, "Input")?;
+    assert_eq!(back.data_validation_ranges().len(), 5);
+    back.remove_data_validations("A1:B1")?;
+    assert_eq!(
+        back.data_validation_ranges(),
+        ["A2:A100", "B2:B100", "C2:C100", "D2:D100", "E2:E100"]
+    );
+    back.remove_data_validations("A10:B10")?;
+    assert_eq!(
+        back.data_validation_ranges(),
+        ["C2:C100", "D2:D100", "E2:E100"]
+    );
+    back.add_data_validation("A2:B100", &status)?;
+    let sheet = String::from_utf8(back.sheet_xml.clone())?;
+    assert!(sheet.contains(r#"<dataValidations count="4">"#));
+    back.remove_data_validations("A1:E100")?;
+    assert!(!String::from_utf8(back.sheet_xml.clone())?.contains("dataValidations"));
+    Ok(())
+}
+
+#[test]
+fn hyperlinks_external_and_internal() -> Result<()> {
+    use std::io::Read;
+    let mut app = XlsxEditor::new_workbook("Index")?;
+    add_page_margins(&mut app);
+    app.add_worksheet("Detail 1")?;
+    app.with_worksheet("Index")?;
+    app.append_row(["Tab", "Ticket"])?;
+    app.merge_cells("D1:E1")?;
+    app.set_hyperlink("a2", "#'Detail 1'!A1", Some("Detail 1"), Some("Open tab"))?
+        .set_hyperlink(
+            "B2",
+            "https://tracker.example.com/browse/FIN-1?x=1&y=2",
+            Some("FIN-1"),
+            None,
+        )?
+        .set_hyperlink("B3", "mailto:finance@example.com", Some("Mail"), None)?
+        // повторная ссылка на ту же ячейку заменяет старую вместе со связью
+        .set_hyperlink("B3", "mailto:ops@example.com", None, None)?;
+    assert!(app.set_hyperlink("C1", "", None, None).is_err());
+
+    let sheet = String::from_utf8(app.sheet_xml.clone())?;
+    assert!(sheet.contains(
+        r#"xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships""#
+    ));
+    assert!(sheet.contains(
+        r#"<hyperlink ref="A2" location="&apos;Detail 1&apos;!A1" tooltip="Open tab" display="Detail 1"/>"#
+    ));
+    assert!(sheet.contains(r#"<hyperlink ref="B2" r:id="rId1" display="FIN-1"/>"#));
+    assert!(sheet.contains(r#"<hyperlink ref="B3" r:id="rId2"/>"#));
+    assert_eq!(sheet.matches("<hyperlink ").count(), 3);
+    // mergeCells → hyperlinks → pageMargins
+    let h = sheet.find("<hyperlinks>").unwrap();
+    assert!(sheet.find("<mergeCells").unwrap() < h);
+    assert!(h < sheet.find("<pageMargins").unwrap());
+
+    // ссылка на другом листе – своя часть rels
+    app.with_worksheet("Detail 1")?;
+    app.set_hyperlink("A1", "#Index!A1", Some("Back"), None)?
+        .set_hyperlink("A2", "https://example.com", None, None)?;
+
+    let bytes = app.to_bytes()?;
+    let index = XlsxEditor::open_from_bytes(bytes.clone(), "Index")?;
+    assert_eq!(
+        index.hyperlink("B2")?.as_deref(),
+        Some("https://tracker.example.com/browse/FIN-1?x=1&y=2")
+    );
+    assert_eq!(index.hyperlink("A2")?.as_deref(), Some("#'Detail 1'!A1"));
+    assert_eq!(
+        index.hyperlink("B3")?.as_deref(),
+        Some("mailto:ops@example.com")
+    );
+    assert_eq!(index.hyperlink("C3")?, None);
+    assert_eq!(index.get_cell("B2")?, CellValue::Text("FIN-1".into()));
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes.clone()))?;
+    let mut read = |name: &str| -> Result<String> {
+        let mut s = String::new();
+        zip.by_name(name)?.read_to_string(&mut s)?;
+        Ok(s)
+    };
+    let rels1 = read("xl/worksheets/_rels/sheet1.xml.rels")?;
+    assert!(rels1.contains(
+        r#"Target="https://tracker.example.com/browse/FIN-1?x=1&amp;y=2" TargetMode="External""#
+    ));
+    assert!(!rels1.contains("finance@"));
+    assert!(read("xl/worksheets/_rels/sheet2.xml.rels")?.contains("https://example.com"));
+
+    // удаление последней внешней ссылки убирает и часть rels
+    let mut detail = XlsxEditor::open_from_bytes(bytes, "Detail 1")?;
+    detail.remove_hyperlink("A1:A2")?;
+    assert!(!String::from_utf8(detail.sheet_xml.clone())?.contains("hyperlink"));
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(detail.to_bytes()?))?;
+    assert!(zip.by_name("xl/worksheets/_rels/sheet2.xml.rels").is_err());
+    assert!(zip.by_name("xl/worksheets/_rels/sheet1.xml.rels").is_ok());
+
+    // подпись пишется как текст, без угадывания типа
+    detail.set_hyperlink("B1", "#Index!A1", Some("2024"), None)?;
+    detail.set_hyperlink("B2", "#Index!A1", Some("=Index!A1"), None)?;
+    assert_eq!(detail.get_cell("B1")?, CellValue::Text("2024".into()));
+    assert_eq!(detail.get_cell("B2")?, CellValue::Text("=Index!A1".into()));
+    Ok(())
+}
+
+#[test]
+fn comments_with_vml_notes() -> Result<()> {
+    use crate::Comment;
+    use std::io::Read;
+    let mut app = XlsxEditor::new_workbook("Index")?;
+    add_page_margins(&mut app);
+    app.add_worksheet("Detail")?;
+    app.with_worksheet("Index")?;
+    app.append_row(["Tab", "Amount"])?;
+    app.merge_cells("D1:E1")?;
+    app.set_hyperlink("A2", "https://example.com", None, None)?;
+    assert!(app.comments()?.is_empty());
+    app.add_comment("b2", "Anna & Co", "Checked <ok>\nsecond line")?
+        .add_comment("C5", "Boris", "Needs review")?
+        // повторное примечание на той же ячейке заменяет старое
+        .add_comment("C5", "Anna & Co", "Reviewed")?;
+    assert!(app.add_comment("5C", "Anna", "bad").is_err());
+
+    let sheet = String::from_utf8(app.sheet_xml.clone())?;
+    // hyperlinks → pageMargins → legacyDrawing
+    let ld = sheet.find(r#"<legacyDrawing r:id="rId3"/>"#).unwrap();
+    assert!(sheet.find("<hyperlinks>").unwrap() < ld);
+    assert!(sheet.find("<pageMargins").unwrap() < ld);
+
+    // заметки второго листа – отдельные части
+    app.with_worksheet("Detail")?;
+    app.add_comment("A1", "Boris", "Source: ERP")?;
+
+    let bytes = app.to_bytes()?;
+    let index = XlsxEditor::open_from_bytes(bytes.clone(), "Index")?;
+    assert_eq!(
+        index.comments()?,
+        vec![
+            Comment {
+                cell: "B2".into(),
+                author: "Anna & Co".into(),
+                text: "Checked <ok>\nsecond line".into(),
+            },
+            Comment {
+                cell: "C5".into(),
+                author: "Anna & Co".into(),
+                text: "Reviewed".into(),
+            },
+        ]
+    );
+    let detail = XlsxEditor::open_from_bytes(bytes.clone(), "Detail")?;
+    assert_eq!(detail.comments()?.len(), 1);
+    assert_eq!(detail.comments()?[0].author, "Boris");
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(bytes.clone()))?;
+    let mut read = |name: &str| -> Result<String> {
+        let mut s = String::new();
+        zip.by_name(name)?.read_to_string(&mut s)?;
+        Ok(s)
+    };
+    let ct = read("[Content_Types].xml")?;
+    assert!(ct.contains(r#"PartName="/xl/comments1.xml""#));
+    assert!(ct.contains(r#"PartName="/xl/comments2.xml""#));
+    assert_eq!(ct.matches(r#"Extension="vml""#).count(), 1);
+    let rels1 = read("xl/worksheets/_rels/sheet1.xml.rels")?;
+    assert!(rels1.contains(r#"Target="../comments1.xml""#));
+    assert!(rels1.contains(r#"Target="../drawings/vmlDrawing1.vml""#));
+    let vml = read("xl/drawings/vmlDrawing1.vml")?;
+    assert_eq!(vml.matches("<v:shape ").count(), 2);
+    assert!(vml.contains("<x:Row>1</x:Row><x:Column>1</x:Column>"));
+    assert!(vml.contains("<x:Row>4</x:Row><x:Column>2</x:Column>"));
+    assert!(read("xl/drawings/vmlDrawing2.vml")?.contains(r#"data="2""#));
+    let comments1 = read("xl/comments1.xml")?;
+    assert_eq!(comments1.matches("<author>").count(), 2);
+
+    // дописывание в уже сохранённый файл: новые части не создаются
+    let mut index = XlsxEditor::open_from_bytes(bytes, "Index")?;
+    // Excel пишет теги фигуры с переносами строк – фигура всё равно находится
+    let vml_path = "xl/drawings/vmlDrawing1.vml".to_owned();
+    let excel_vml = vml.replace("</x:Row><x:Column>", "</x:Row>\n   <x:Column>");
+    index
+        .new_files
+        .insert(vml_path.clone(), excel_vml.into_bytes());
+    index.add_comment("C5", "Boris", "Again")?;
+    index.add_comment("A1", "Boris", "Header")?;
+    let vml = String::from_utf8(index.part_bytes(&vml_path)?.unwrap())?;
+    assert_eq!(vml.matches("<v:shape ").count(), 3);
+
+    // удалённый лист забирает свои примечания с собой
+    index.delete_worksheet("Detail")?;
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(index.to_bytes()?))?;
+    assert!(zip.by_name("xl/comments2.xml").is_err());
+    assert!(zip.by_name("xl/drawings/vmlDrawing2.vml").is_err());
+    assert!(zip.by_name("xl/comments1.xml").is_ok());
+    let mut ct = String::new();
+    zip.by_name("[Content_Types].xml")?
+        .read_to_string(&mut ct)?;
+    assert!(!ct.contains("comments2.xml"));
+    assert!(ct.contains("comments1.xml"));
+    let reopened = XlsxEditor::open_from_bytes(index.to_bytes()?, "Index")?;
+    let all = reopened.comments()?;
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[2].author, "Boris");
+    Ok(())
+}
+
+#[test]
+fn comments_follow_row_and_column_shifts() -> Result<()> {
+    use std::io::Read;
+    let mut app = XlsxEditor::new_workbook("Notes")?;
+    app.append_table_at("A1", [["a", "b", "c"], ["d", "e", "f"], ["g", "h", "i"]])?;
+    app.add_comment("B2", "Ann", "keep")?
+        .add_comment("C3", "Ann", "drop row")?
+        .add_comment("A3", "Ann", "drop col")?;
+
+    app.insert_rows(2, 2)?; // B2 → B4, C3 → C5, A3 → A5
+    let cells = |app: &XlsxEditor| -> Result<Vec<String>> {
+        Ok(app.comments()?.into_iter().map(|c| c.cell).collect())
+    };
+    assert_eq!(cells(&app)?, vec!["B4", "C5", "A5"]);
+
+    app.delete_rows(5, 1)?; // C5 и A5 пропадают
+    assert_eq!(cells(&app)?, vec!["B4"]);
+    app.add_comment("A1", "Ann", "drop col")?;
+    app.insert_columns("B", 1)?; // B4 → C4
+    app.delete_columns("A", 1)?; // A1 пропадает, C4 → B4
+    assert_eq!(cells(&app)?, vec!["B4"]);
+    assert_eq!(app.comments()?[0].text, "keep");
+
+    let mut zip = zip::ZipArchive::new(std::io::Cursor::new(app.to_bytes()?))?;
+    let mut vml = String::new();
+    zip.by_name("xl/drawings/vmlDrawing1.vml")?
+        .read_to_string(&mut vml)?;
+    // одна фигура, привязанная к B4 (с нуля: строка 3, столбец 1), рамка сдвинута вместе с ней
+    assert_eq!(vml.matches("<v:shape ").count(), 1, "{vml}");
+    assert!(
+        vml.contains("<x:Row>3</x:Row><x:Column>1</x:Column>"),
+        "{vml}"
+    );
+    assert!(
+        vml.contains("<x:Anchor>2, 15, 2, 10, 4, 15, 6, 4</x:Anchor>"),
+        "{vml}"
+    );
+    Ok(())
+}
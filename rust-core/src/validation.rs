@@ -0,0 +1,362 @@
+//! validation.rs – проверка данных: `<dataValidations>` листа (выпадающие списки и ограничения)
+
+use anyhow::{Result, bail};
+use memchr::memmem;
+use quick_xml::escape::{escape, partial_escape};
+use std::{fmt, str::FromStr};
+
+use crate::{
+    CfOperator, XlsxEditor,
+    files_part::{worksheet_element_range, worksheet_insert_pos},
+    find_bytes_from,
+    merge::{overlaps, parse_rect},
+};
+
+/// Value type checked by [`ValidationRule::Compare`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValidationType {
+    Whole,
+    Decimal,
+    Date,
+    Time,
+    TextLength,
+}
+impl fmt::Display for ValidationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ValidationType::Whole => "whole",
+            ValidationType::Decimal => "decimal",
+            ValidationType::Date => "date",
+            ValidationType::Time => "time",
+            ValidationType::TextLength => "textLength",
+        })
+    }
+}
+impl FromStr for ValidationType {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "whole" => ValidationType::Whole,
+            "decimal" => ValidationType::Decimal,
+            "date" => ValidationType::Date,
+            "time" => ValidationType::Time,
+            "textLength" => ValidationType::TextLength,
+            _ => bail!("Unknown validation type: {s}"),
+        })
+    }
+}
+
+/// What Excel does when the entered value fails validation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorStyle {
+    /// Reject the value.
+    #[default]
+    Stop,
+    /// Ask whether to keep the value.
+    Warning,
+    /// Only inform, the value is kept.
+    Information,
+}
+impl fmt::Display for ErrorStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ErrorStyle::Stop => "stop",
+            ErrorStyle::Warning => "warning",
+            ErrorStyle::Information => "information",
+        })
+    }
+}
+impl FromStr for ErrorStyle {
+    type Err = anyhow::Error;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "stop" => ErrorStyle::Stop,
+            "warning" => ErrorStyle::Warning,
+            "information" => ErrorStyle::Information,
+            _ => bail!("Unknown error style: {s}"),
+        })
+    }
+}
+
+/// The constraint of a data validation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationRule {
+    /// Dropdown with the given values (no commas; 255 characters in total).
+    List(Vec<String>),
+    /// Dropdown with the values of a range, e.g. `"$H$1:$H$5"` or `"Lists!$A$1:$A$3"`.
+    ListRange(String),
+    /// Compares the value (or text length) with `formula1` (and `formula2`
+    /// for `Between`/`NotBetween`). Dates may be given as `DATE(2025,1,1)`.
+    Compare {
+        kind: ValidationType,
+        operator: CfOperator,
+        formula1: String,
+        formula2: Option<String>,
+    },
+    /// Accepts the value when the formula is true, e.g. `"ISNUMBER(A2)"`.
+    Custom(String),
+}
+
+/// A data validation: a rule plus the messages Excel shows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DataValidation {
+    pub rule: ValidationRule,
+    /// Empty cells are valid.
+    pub allow_blank: bool,
+    /// Show the dropdown arrow for list rules.
+    pub in_cell_dropdown: bool,
+    /// Input message shown when the cell is selected (title up to 32 characters).
+    pub prompt_title: Option<String>,
+    pub prompt: Option<String>,
+    /// Error alert; `None` – Excel's default message.
+    pub error_title: Option<String>,
+    pub error: Option<String>,
+    pub error_style: ErrorStyle,
+}
+
+impl DataValidation {
+    pub fn new(rule: ValidationRule) -> Self {
+        Self {
+            rule,
+            allow_blank: true,
+            in_cell_dropdown: true,
+            prompt_title: None,
+            prompt: None,
+            error_title: None,
+            error: None,
+            error_style: ErrorStyle::Stop,
+        }
+    }
+
+    pub fn with_prompt(mut self, title: &str, message: &str) -> Self {
+        self.prompt_title = Some(title.to_owned());
+        self.prompt = Some(message.to_owned());
+        self
+    }
+
+    pub fn with_error(mut self, style: ErrorStyle, title: &str, message: &str) -> Self {
+        self.error_style = style;
+        self.error_title = Some(title.to_owned());
+        self.error = Some(message.to_owned());
+        self
+    }
+
+    fn to_xml(&self, sqref: &str) -> Result<String> {
+        for (name, text, max) in [
+            ("prompt title", &self.prompt_title, 32),
+            ("prompt", &self.prompt, 255),
+            ("error title", &self.error_title, 32),
+            ("error message", &self.error, 255),
+        ] {
+            if let Some(t) = text
+                && t.chars().count() > max
+            {
+                bail!("data validation {name} is longer than {max} characters");
+            }
+        }
+
+        let (kind, operator, f1, f2) = match &self.rule {
+            ValidationRule::List(values) => {
+                if values.is_empty() {
+                    bail!("list validation needs at least one value");
+                }
+                if let Some(v) = values.iter().find(|v| v.contains(',')) {
+                    bail!("list value `{v}` contains a comma; use a range reference instead");
+                }
+                let joined = values.join(",");
+                if joined.chars().count() > 255 {
+                    bail!("list values exceed 255 characters; use a range reference instead");
+                }
+                (
+                    "list".to_owned(),
+                    None,
+                    format!("\"{}\"", joined.replace('"', "\"\"")),
+                    None,
+                )
+            }
+            ValidationRule::ListRange(r) => ("list".to_owned(), None, r.clone(), None),
+            ValidationRule::Compare {
+                kind,
+                operator,
+                formula1,
+                formula2,
+            } => {
+                let two = matches!(operator, CfOperator::Between | CfOperator::NotBetween);
+                if two && formula2.is_none() {
+                    bail!("`{operator}` needs a second formula");
+                }
+                (
+                    kind.to_string(),
+                    Some(*operator),
+                    formula1.clone(),
+                    formula2.clone().filter(|_| two),
+                )
+            }
+            ValidationRule::Custom(f) => ("custom".to_owned(), None, f.clone(), None),
+        };
+
+        // порядок атрибутов по схеме CT_DataValidation
+        let mut xml = format!(r#"<dataValidation type="{kind}""#);
+        if self.error_style != ErrorStyle::Stop {
+            xml.push_str(&format!(r#" errorStyle="{}""#, self.error_style));
+        }
+        // between – значение по умолчанию
+        if let Some(op) = operator.filter(|op| *op != CfOperator::Between) {
+            xml.push_str(&format!(r#" operator="{op}""#));
+        }
+        if self.allow_blank {
+            xml.push_str(r#" allowBlank="1""#);
+        }
+        // showDropDown="1" стрелку как раз ПРЯЧЕТ
+        if !self.in_cell_dropdown && kind == "list" {
+            xml.push_str(r#" showDropDown="1""#);
+        }
+        xml.push_str(r#" showInputMessage="1" showErrorMessage="1""#);
+        for (name, text) in [
+            ("errorTitle", &self.error_title),
+            ("error", &self.error),
+            ("promptTitle", &self.prompt_title),
+            ("prompt", &self.prompt),
+        ] {
+            if let Some(t) = text {
+                xml.push_str(&format!(r#" {name}="{}""#, escape(t)));
+            }
+        }
+        xml.push_str(&format!(
+            r#" sqref="{sqref}"><formula1>{}</formula1>"#,
+            partial_escape(f1.trim_start_matches('='))
+        ));
+        if let Some(f2) = f2 {
+            xml.push_str(&format!(
+                "<formula2>{}</formula2>",
+                partial_escape(f2.trim_start_matches('='))
+            ));
+        }
+        xml.push_str("</dataValidation>");
+        Ok(xml)
+    }
+}
+
+impl XlsxEditor {
+    /// Returns the `sqref` of every data validation on the current sheet, in file order.
+    pub fn data_validation_ranges(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        while let Some(off) = memmem::find(&self.sheet_xml[i..], b"<dataValidation ") {
+            let start = i + off;
+            let end = find_bytes_from(&self.sheet_xml, b">", start).unwrap_or(self.sheet_xml.len());
+            let tag = &self.sheet_xml[start..end];
+            if let Some(a) = memmem::find(tag, b" sqref=\"") {
+                let v = &tag[a + 8..];
+                if let Some(q) = v.iter().position(|&b| b == b'"') {
+                    out.push(String::from_utf8_lossy(&v[..q]).into_owned());
+                }
+            }
+            i = end;
+        }
+        out
+    }
+
+    /// Adds a data validation to `range` on the current sheet.
+    ///
+    /// Fails if the range overlaps an existing validation; remove it first with
+    /// [`XlsxEditor::remove_data_validations`].
+    ///
+    /// # Arguments
+    /// * `range` - Cells to validate; several ranges are separated by spaces.
+    /// * `spec` - The rule, input prompt and error alert.
+    pub fn add_data_validation(&mut self, range: &str, spec: &DataValidation) -> Result<&mut Self> {
+        let sqref = range.split_whitespace().collect::<Vec<_>>().join(" ");
+        if sqref.is_empty() {
+            bail!("data validation range is empty");
+        }
+        let rects = sqref
+            .split(' ')
+            .map(parse_rect)
+            .collect::<Result<Vec<_>>>()?;
+        if let Some(hit) = self.data_validation_ranges().into_iter().find(|existing| {
+            existing
+                .split(' ')
+                .filter_map(|r| parse_rect(r).ok())
+                .any(|e| rects.iter().any(|&r| overlaps(e, r)))
+        }) {
+            bail!("data validation range `{sqref}` overlaps existing validation `{hit}`");
+        }
+        let dv = spec.to_xml(&sqref)?;
+
+        if let Some((start, end)) = worksheet_element_range(&self.sheet_xml, "dataValidations") {
+            let count = self.data_validation_ranges().len() + 1;
+            let block = &self.sheet_xml[start..end];
+            let mut new = b"<dataValidations".to_vec();
+            if let Some(head_end) = find_bytes_from(block, b">", 0) {
+                // атрибуты блока (disablePrompts и т. п.) сохраняем, count пересчитываем
+                let head = String::from_utf8_lossy(&block[16..head_end]);
+                let head = head.trim_end_matches('/');
+                for attr in head.split_whitespace() {
+                    if !attr.starts_with("count=") {
+                        new.push(b' ');
+                        new.extend_from_slice(attr.as_bytes());
+                    }
+                }
+                new.extend_from_slice(format!(r#" count="{count}">"#).as_bytes());
+                if block[head_end - 1] != b'/' {
+                    new.extend_from_slice(&block[head_end + 1..block.len() - 18]);
+                }
+            }
+            new.extend_from_slice(dv.as_bytes());
+            new.extend_from_slice(b"</dataValidations>");
+            self.sheet_xml.splice(start..end, new);
+        } else {
+            let Some(pos) = worksheet_insert_pos(&self.sheet_xml, "dataValidations") else {
+                bail!("</worksheet> not found");
+            };
+            let block = format!(r#"<dataValidations count="1">{dv}</dataValidations>"#);
+            self.sheet_xml.splice(pos..pos, block.into_bytes());
+        }
+        Ok(self)
+    }
+
+    /// Removes every data validation on the current sheet that intersects `range`.
+    pub fn remove_data_validations(&mut self, range: &str) -> Result<&mut Self> {
+        let rect = parse_rect(range)?;
+        let Some((start, end)) = worksheet_element_range(&self.sheet_xml, "dataValidations") else {
+            return Ok(self);
+        };
+        let block = self.sheet_xml[start..end].to_vec();
+        let mut kept = Vec::new();
+        let mut i = 0;
+        while let Some(off) = memmem::find(&block[i..], b"<dataValidation ") {
+            let s = i + off;
+            let head_end = find_bytes_from(&block, b">", s).unwrap_or(block.len() - 1);
+            let e = if block[head_end - 1] == b'/' {
+                head_end + 1
+            } else {
+                find_bytes_from(&block, b"</dataValidation>", head_end)
+                    .map_or(block.len(), |p| p + 17)
+            };
+            let dv = &block[s..e];
+            let hit = memmem::find(dv, b" sqref=\"").is_some_and(|a| {
+                let v = &dv[a + 8..];
+                let q = v.iter().position(|&b| b == b'"').unwrap_or(0);
+                String::from_utf8_lossy(&v[..q])
+                    .split_whitespace()
+                    .filter_map(|r| parse_rect(r).ok())
+                    .any(|r| overlaps(r, rect))
+            });
+            if !hit {
+                kept.push(dv.to_vec());
+            }
+            i = e;
+        }
+        let new = if kept.is_empty() {
+            Vec::new()
+        } else {
+            let mut new = format!(r#"<dataValidations count="{}">"#, kept.len()).into_bytes();
+            kept.iter().for_each(|dv| new.extend_from_slice(dv));
+            new.extend_from_slice(b"</dataValidations>");
+            new
+        };
+        self.sheet_xml.splice(start..end, new);
+        Ok(self)
+    }
+}